@@ -0,0 +1,90 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::config::AdaptiveOptions;
+
+/// Fator de crescimento do intervalo a cada amostra estável
+const GROWTH: f32 = 1.5;
+
+/// Métricas que mudam a cada amostra por natureza e não indicam atividade
+const IGNORED: &[&str] = &["UPTIME"];
+
+/// Intervalo de coleta que se ajusta à variação das métricas (`--adaptive`):
+/// cresce aos poucos enquanto tudo está estável e volta ao mínimo assim que
+/// alguma métrica varia além do limiar, para não perder o começo de um evento
+pub struct AdaptiveInterval {
+    options: AdaptiveOptions,
+    current: Duration,
+    last: HashMap<String, f32>,
+}
+
+impl AdaptiveInterval {
+    pub fn new(options: AdaptiveOptions) -> Self {
+        Self { current: options.min, options, last: HashMap::new() }
+    }
+
+    /// Intervalo até a próxima coleta, comparando a amostra com a anterior
+    pub fn next(&mut self, metrics: HashMap<String, f32>) -> Duration {
+        let changed = self.largest_change(&metrics)
+            .filter(|(_, change)| *change > self.options.threshold);
+        self.last = metrics;
+
+        let previous = self.current;
+        match changed {
+            Some((name, change)) => {
+                self.current = self.options.min;
+                if previous == self.options.max {
+                    println!(
+                        "⚡ {} variou {:.0}%: intervalo de coleta volta a {}ms",
+                        name, change * 100.0, self.current.as_millis()
+                    );
+                }
+            }
+            None => {
+                self.current = self.current.mul_f32(GROWTH).min(self.options.max);
+                if previous < self.options.max && self.current == self.options.max {
+                    println!("🐢 Métricas estáveis: intervalo de coleta em {}ms", self.current.as_millis());
+                }
+            }
+        }
+        self.current
+    }
+
+    /// Métrica com a maior variação relativa desde a amostra anterior. Perto de
+    /// zero a base é 1, para que 0,1 → 0,2 não conte como 100%
+    fn largest_change(&self, metrics: &HashMap<String, f32>) -> Option<(String, f32)> {
+        metrics.iter()
+            .filter(|(name, _)| !IGNORED.contains(&name.as_str()))
+            .filter_map(|(name, value)| {
+                let previous = self.last.get(name)?;
+                Some((name, (value - previous).abs() / previous.abs().max(1.0)))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(name, change)| (name.clone(), change))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(cpu: f32, uptime: f32) -> HashMap<String, f32> {
+        HashMap::from([("CPU".to_string(), cpu), ("UPTIME".to_string(), uptime)])
+    }
+
+    #[test]
+    fn grows_while_stable_and_resets_on_change() {
+        let options = AdaptiveOptions {
+            min: Duration::from_millis(1000),
+            max: Duration::from_millis(4000),
+            threshold: 0.05,
+        };
+        let mut interval = AdaptiveInterval::new(options);
+
+        let grown: Vec<u128> = (0..5).map(|i| interval.next(sample(40.0, i as f32 * 100.0)).as_millis()).collect();
+        assert_eq!(grown, [1500, 2250, 3375, 4000, 4000]);
+
+        // 40 → 41 fica abaixo de 5%; 41 → 60 não
+        assert_eq!(interval.next(sample(41.0, 600.0)), Duration::from_millis(4000));
+        assert_eq!(interval.next(sample(60.0, 700.0)), Duration::from_millis(1000));
+    }
+}
@@ -0,0 +1,38 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Backoff exponencial com jitter: 1s, 2s, 4s, 8s... até 60s
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    /// Tentativas antes de desistir; `0` significa tentar para sempre
+    pub max_attempts: u32,
+}
+
+impl Backoff {
+    /// Espera antes da tentativa `attempt` (começando em 0), sorteada entre
+    /// metade e o valor cheio do atraso exponencial para espalhar reconexões
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponential = BASE_DELAY
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(MAX_DELAY)
+            .min(MAX_DELAY);
+
+        exponential.mul_f64(0.5 + 0.5 * random_unit())
+    }
+
+    pub fn is_exhausted(&self, attempts: u32) -> bool {
+        self.max_attempts != 0 && attempts >= self.max_attempts
+    }
+}
+
+/// Número pseudoaleatório em [0, 1) a partir da semente aleatória do `RandomState`
+fn random_unit() -> f64 {
+    let value = RandomState::new().build_hasher().finish();
+    (value >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+/// Amostras acumuladas para os servidores que aceitaram batch, enviadas num
+/// único array JSON ao juntar `max_samples` ou ao passar `max_age` desde a primeira
+pub struct SampleBatch {
+    /// Amostras já serializadas, na ordem de coleta
+    samples: Vec<String>,
+    /// Bytes das amostras pendentes, sem os separadores do array
+    bytes: usize,
+    max_samples: usize,
+    max_age: Option<Duration>,
+    /// Coleta da primeira amostra pendente
+    started: Instant,
+}
+
+impl SampleBatch {
+    pub fn new(max_samples: usize, max_age: Option<Duration>) -> Self {
+        Self {
+            samples: Vec::with_capacity(max_samples),
+            bytes: 0,
+            max_samples: max_samples.max(1),
+            max_age,
+            started: Instant::now(),
+        }
+    }
+
+    /// Batch pedido na linha de comando; desligado, toda amostra segue sozinha
+    pub fn is_enabled(&self) -> bool {
+        self.max_samples > 1 || self.max_age.is_some()
+    }
+
+    /// `sample` faria o array, com o `\n` final, passar de `max_bytes`: o que está
+    /// pendente deve ser enviado antes. Uma amostra sozinha sempre cabe
+    pub fn would_exceed(&self, sample: &str, max_bytes: Option<usize>) -> bool {
+        let Some(max_bytes) = max_bytes else {
+            return false;
+        };
+        // `[` + amostras + vírgulas + `]` + `\n`
+        let encoded = self.bytes + sample.len() + self.samples.len() + 3;
+        !self.samples.is_empty() && encoded > max_bytes
+    }
+
+    /// Acrescenta uma amostra; `true` quando o batch deve ser enviado
+    pub fn push(&mut self, sample: &str) -> bool {
+        if self.samples.is_empty() {
+            self.started = Instant::now();
+        }
+        self.bytes += sample.len();
+        self.samples.push(sample.to_string());
+
+        self.samples.len() >= self.max_samples
+            || self.max_age.is_some_and(|max_age| self.started.elapsed() >= max_age)
+    }
+
+    /// Array JSON `[amostra, ...]` com as amostras pendentes
+    pub fn message(&self) -> String {
+        format!("[{}]", self.samples.join(","))
+    }
+
+    /// Esvazia o batch, devolvendo as amostras na ordem de coleta
+    pub fn take(&mut self) -> Vec<String> {
+        self.bytes = 0;
+        std::mem::take(&mut self.samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MAX_BATCH_SAMPLES;
+
+    /// Um batch do tamanho máximo com amostras realistas passa do limite padrão de
+    /// linha do servidor (64 KiB) e é dividido em arrays que cabem nele
+    #[test]
+    fn splits_batches_at_the_server_limit() {
+        const MAX_BYTES: usize = 64 * 1024;
+        let sample = format!(r#"{{"timestamp":1700000000000,"CPU":42.5,"MEM":8192.0,"DISK{{mount=\"/home\"}}":71.3,"TOP_PROCESS":"{}"}}"#, "x".repeat(100));
+        let mut batch = SampleBatch::new(MAX_BATCH_SAMPLES, None);
+
+        let mut messages = Vec::new();
+        for _ in 0..MAX_BATCH_SAMPLES {
+            if batch.would_exceed(&sample, Some(MAX_BYTES)) {
+                messages.push(batch.message());
+                batch.take();
+            }
+            if batch.push(&sample) {
+                messages.push(batch.message());
+                batch.take();
+            }
+        }
+        // O que sobrou sai quando o batch enche ou expira, numa coleta seguinte
+        messages.push(batch.message());
+        batch.take();
+
+        assert!(messages.len() > 1);
+        let mut total = 0;
+        for message in &messages {
+            assert!(message.len() < MAX_BYTES, "array de {} bytes", message.len());
+            total += serde_json::from_str::<Vec<serde_json::Value>>(message).unwrap().len();
+        }
+        assert_eq!(total, MAX_BATCH_SAMPLES);
+        // Sem limite anunciado (servidor antigo), o batch só fecha por contagem
+        batch.push(&sample);
+        assert!(!batch.would_exceed(&sample, None));
+    }
+}
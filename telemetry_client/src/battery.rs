@@ -0,0 +1,274 @@
+use std::time::{Duration, Instant};
+#[cfg(target_os = "linux")]
+use std::{fs, path::Path};
+#[cfg(target_os = "macos")]
+use std::process::Command;
+
+/// Raiz das fontes de energia expostas pelo kernel
+#[cfg(target_os = "linux")]
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Carga agregada das baterias da máquina
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryReading {
+    /// Carga média entre as baterias (%)
+    pub percent: f32,
+    /// Alguma bateria está carregando
+    pub charging: bool,
+}
+
+/// Primeira espera após uma leitura sem bateria; dobra a cada falha seguida
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Teto da espera entre tentativas sem bateria
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(600);
+
+/// Intervalo mínimo entre leituras: no macOS cada uma executa o `pmset`, e a
+/// carga muda devagar demais para justificar um processo por ciclo
+#[cfg(target_os = "macos")]
+const READ_INTERVAL: Duration = Duration::from_secs(30);
+#[cfg(not(target_os = "macos"))]
+const READ_INTERVAL: Duration = Duration::ZERO;
+
+/// Lê a bateria pelo `/sys` no Linux, pelo `pmset` no macOS e pelo
+/// `GetSystemPowerStatus` no Windows. Sem bateria a métrica some, mas a leitura
+/// volta a ser tentada com espera crescente: uma falha passageira do `pmset` ou
+/// uma bateria reconectada não desligam a métrica para sempre
+pub struct BatteryProbe {
+    read_platform: fn() -> Option<BatteryReading>,
+    /// Última leitura, reaproveitada até `READ_INTERVAL`
+    last: Option<(Instant, BatteryReading)>,
+    /// Falhas seguidas desde a última leitura bem-sucedida
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl BatteryProbe {
+    pub fn new() -> Self {
+        Self::with_reader(read_platform)
+    }
+
+    fn with_reader(read_platform: fn() -> Option<BatteryReading>) -> Self {
+        Self { read_platform, last: None, failures: 0, retry_at: None }
+    }
+
+    pub fn read(&mut self) -> Option<BatteryReading> {
+        self.read_at(Instant::now())
+    }
+
+    fn read_at(&mut self, now: Instant) -> Option<BatteryReading> {
+        if let Some((read_at, reading)) = self.last {
+            if now.duration_since(read_at) < READ_INTERVAL {
+                return Some(reading);
+            }
+        }
+        if self.retry_at.is_some_and(|retry_at| now < retry_at) {
+            return None;
+        }
+
+        match (self.read_platform)() {
+            Some(reading) => {
+                if self.failures > 0 {
+                    println!("🔋 Bateria encontrada: métrica BATTERY retomada");
+                }
+                self.failures = 0;
+                self.retry_at = None;
+                self.last = Some((now, reading));
+                Some(reading)
+            }
+            None => {
+                let delay = retry_delay(self.failures);
+                if self.failures == 0 {
+                    println!("ℹ️ Nenhuma bateria encontrada: métrica BATTERY suspensa, nova tentativa em {}s", delay.as_secs());
+                }
+                self.failures = self.failures.saturating_add(1);
+                self.retry_at = Some(now + delay);
+                self.last = None;
+                None
+            }
+        }
+    }
+}
+
+/// Espera antes da próxima tentativa, dobrando a partir de `RETRY_DELAY` até o teto
+fn retry_delay(previous_failures: u32) -> Duration {
+    RETRY_DELAY.saturating_mul(1 << previous_failures.min(16)).min(MAX_RETRY_DELAY)
+}
+
+#[cfg(target_os = "linux")]
+fn read_platform() -> Option<BatteryReading> {
+    read_power_supply(Path::new(POWER_SUPPLY_DIR))
+}
+
+#[cfg(target_os = "macos")]
+fn read_platform() -> Option<BatteryReading> {
+    let output = Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_pmset(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(windows)]
+fn read_platform() -> Option<BatteryReading> {
+    /// `SYSTEM_POWER_STATUS` da Win32; só parte dos campos é lida
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    /// Sem bateria de sistema, ou estado desconhecido
+    const NO_SYSTEM_BATTERY: u8 = 128;
+    const UNKNOWN: u8 = 255;
+    const CHARGING: u8 = 8;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    let mut status = SystemPowerStatus::default();
+    // SAFETY: a estrutura tem o layout de `SYSTEM_POWER_STATUS` e vive durante a chamada
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    if status.battery_flag == UNKNOWN || status.battery_flag & NO_SYSTEM_BATTERY != 0 || status.battery_life_percent > 100 {
+        return None;
+    }
+    Some(BatteryReading {
+        percent: f32::from(status.battery_life_percent),
+        charging: status.battery_flag & CHARGING != 0,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn read_platform() -> Option<BatteryReading> {
+    None
+}
+
+/// Baterias do sistema em `power_supply`; as de periféricos (`scope` Device,
+/// como mouses sem fio) ficam de fora
+#[cfg(target_os = "linux")]
+fn read_power_supply(root: &Path) -> Option<BatteryReading> {
+    let read = |dir: &Path, file: &str| fs::read_to_string(dir.join(file)).ok().map(|raw| raw.trim().to_string());
+
+    let mut count = 0u32;
+    let mut percent = 0.0;
+    let mut charging = false;
+    for entry in fs::read_dir(root).ok()?.flatten() {
+        let dir = entry.path();
+        if read(&dir, "type").as_deref() != Some("Battery") || read(&dir, "scope").as_deref() == Some("Device") {
+            continue;
+        }
+        let Some(capacity) = read(&dir, "capacity").and_then(|raw| raw.parse::<f32>().ok()) else {
+            continue;
+        };
+        percent += capacity;
+        charging |= read(&dir, "status").as_deref() == Some("Charging");
+        count += 1;
+    }
+
+    (count > 0).then(|| BatteryReading { percent: percent / count as f32, charging })
+}
+
+/// Interpreta linhas como ` -InternalBattery-0 (id=...)\t85%; charging; 1:02 remaining`
+#[cfg(any(target_os = "macos", test))]
+fn parse_pmset(output: &str) -> Option<BatteryReading> {
+    let mut count = 0u32;
+    let mut percent = 0.0;
+    let mut charging = false;
+
+    for line in output.lines().filter(|line| line.contains("InternalBattery")) {
+        let Some((_, status)) = line.split_once('\t') else {
+            continue;
+        };
+        let mut fields = status.split(';').map(str::trim);
+        let Some(value) = fields.next().and_then(|raw| raw.strip_suffix('%')).and_then(|raw| raw.parse::<f32>().ok()) else {
+            continue;
+        };
+        percent += value;
+        charging |= fields.next() == Some("charging");
+        count += 1;
+    }
+
+    (count > 0).then(|| BatteryReading { percent: percent / count as f32, charging })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        assert_eq!(retry_delay(0), RETRY_DELAY);
+        assert_eq!(retry_delay(1), RETRY_DELAY * 2);
+        assert_eq!(retry_delay(3), RETRY_DELAY * 8);
+        assert_eq!(retry_delay(10), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY_DELAY);
+    }
+
+    /// Sem bateria a leitura é adiada, não abandonada: volta quando ela aparece
+    #[test]
+    fn missing_battery_is_retried_with_backoff() {
+        static CALLS: AtomicU32 = AtomicU32::new(0);
+        fn flaky() -> Option<BatteryReading> {
+            match CALLS.fetch_add(1, Ordering::Relaxed) {
+                0 | 1 => None,
+                _ => Some(BatteryReading { percent: 50.0, charging: false }),
+            }
+        }
+
+        let mut probe = BatteryProbe::with_reader(flaky);
+        let start = Instant::now();
+        assert_eq!(probe.read_at(start), None);
+        assert_eq!(probe.read_at(start + RETRY_DELAY / 2), None);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+
+        assert_eq!(probe.read_at(start + RETRY_DELAY), None);
+        assert_eq!(probe.read_at(start + RETRY_DELAY * 2), None);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+
+        let later = start + RETRY_DELAY * 3;
+        assert_eq!(probe.read_at(later), Some(BatteryReading { percent: 50.0, charging: false }));
+        assert_eq!(probe.failures, 0);
+        assert_eq!(probe.read_at(later + READ_INTERVAL), Some(BatteryReading { percent: 50.0, charging: false }));
+    }
+
+    #[test]
+    fn parses_pmset_output() {
+        let output = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t85%; charging; 0:40 remaining present: true\n";
+        assert_eq!(parse_pmset(output), Some(BatteryReading { percent: 85.0, charging: true }));
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reads_system_batteries_and_skips_peripherals() {
+        let root = std::env::temp_dir().join(format!("telemetry-power-supply-{}", std::process::id()));
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let dir = root.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            for (file, content) in files {
+                fs::write(dir.join(file), content).unwrap();
+            }
+        };
+        supply("AC", &[("type", "Mains\n"), ("online", "1\n")]);
+        assert_eq!(read_power_supply(&root), None);
+
+        supply("BAT0", &[("type", "Battery\n"), ("capacity", "80\n"), ("status", "Discharging\n")]);
+        supply("BAT1", &[("type", "Battery\n"), ("capacity", "60\n"), ("status", "Charging\n")]);
+        supply("hidpp_battery_0", &[("type", "Battery\n"), ("scope", "Device\n"), ("capacity", "5\n")]);
+        let reading = read_power_supply(&root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(reading, Some(BatteryReading { percent: 70.0, charging: true }));
+    }
+}
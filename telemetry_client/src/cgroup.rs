@@ -0,0 +1,255 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Instant,
+};
+
+use sysinfo::{MemoryRefreshKind, RefreshKind, System};
+use telemetry_common::sample::percent_of;
+
+use crate::{
+    collector::MetricCollector,
+    config::{ContainerMode, MemoryMode},
+};
+
+/// Ponto de montagem do cgroup v2 (hierarquia unificada)
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// Cgroup do próprio processo; no v2 é a linha `0::/caminho`
+const SELF_CGROUP: &str = "/proc/self/cgroup";
+
+/// Cgroup v2 do processo. Dentro de um container (Docker, Kubernetes) é onde
+/// ficam os limites de CPU e memória que o `sysinfo`, lendo o host, não enxerga
+#[derive(Debug, Clone)]
+pub struct Cgroup {
+    dir: PathBuf,
+}
+
+impl Cgroup {
+    /// `None` fora do cgroup v2 (cgroup v1, macOS, Windows)
+    pub fn detect() -> Option<Self> {
+        Self::at(Path::new(CGROUP_ROOT), fs::read_to_string(SELF_CGROUP).ok().as_deref())
+    }
+
+    fn at(root: &Path, self_cgroup: Option<&str>) -> Option<Self> {
+        if !root.join("cgroup.controllers").exists() {
+            return None;
+        }
+        // Com namespace de cgroup (o padrão do Docker) o caminho é `/` e a raiz já é o container
+        let relative = self_cgroup
+            .and_then(|content| content.lines().find_map(|line| line.strip_prefix("0::")))
+            .map(|path| path.trim().trim_start_matches('/'))
+            .unwrap_or_default();
+        let dir = root.join(relative);
+        Some(Self { dir: if dir.is_dir() { dir } else { root.to_path_buf() } })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn read(&self, file: &str) -> Option<String> {
+        fs::read_to_string(self.dir.join(file)).ok()
+    }
+
+    /// Há algum limite de CPU ou memória, ou seja, vale medir contra o cgroup
+    pub fn has_limits(&self) -> bool {
+        self.memory_limit().is_some() || self.cpu_limit().is_some()
+    }
+
+    /// `memory.max` em bytes; `None` quando é `max` (sem limite)
+    pub fn memory_limit(&self) -> Option<u64> {
+        self.read("memory.max")?.trim().parse().ok()
+    }
+
+    /// Memória em uso como o `docker stats` a mostra: `memory.current` sem o
+    /// page cache inativo, que o kernel recupera antes de chegar ao limite
+    pub fn memory_used(&self) -> Option<u64> {
+        let current: u64 = self.read("memory.current")?.trim().parse().ok()?;
+        let inactive_file = self.read("memory.stat")
+            .and_then(|stat| stat_field(&stat, "inactive_file"))
+            .unwrap_or(0);
+        Some(current.saturating_sub(inactive_file))
+    }
+
+    /// CPUs permitidas por `cpu.max` (`quota período`); `None` quando a cota é `max`
+    pub fn cpu_limit(&self) -> Option<f32> {
+        let content = self.read("cpu.max")?;
+        let mut fields = content.split_whitespace();
+        let quota: f32 = fields.next()?.parse().ok()?;
+        let period: f32 = fields.next()?.parse().ok()?;
+        (period > 0.0).then_some(quota / period)
+    }
+
+    /// Tempo de CPU acumulado pelo cgroup, em microssegundos
+    pub fn cpu_usage_usec(&self) -> Option<u64> {
+        stat_field(&self.read("cpu.stat")?, "usage_usec")
+    }
+}
+
+/// Valor de uma linha `chave valor` de `cpu.stat` ou `memory.stat`
+fn stat_field(stat: &str, key: &str) -> Option<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Cgroup a usar para CPU e memória conforme `--container`; `None` mede o host
+pub fn resolve(mode: ContainerMode) -> Option<Cgroup> {
+    let cgroup = match mode {
+        ContainerMode::Host => return None,
+        // Todo processo num host com cgroup v2 tem um cgroup; só os limites indicam um container
+        ContainerMode::Auto => Cgroup::detect().filter(Cgroup::has_limits)?,
+        ContainerMode::Cgroup => match Cgroup::detect() {
+            Some(cgroup) => cgroup,
+            None => {
+                eprintln!("⚠️ --container cgroup: cgroup v2 não encontrado, usando as métricas do host");
+                return None;
+            }
+        },
+    };
+    println!("📦 Container detectado (cgroup v2 em {}): CPU e memória relativas aos limites", cgroup.dir().display());
+    Some(cgroup)
+}
+
+/// Uso de CPU do cgroup em `CPU`, em % da cota (`cpu.max`) ou, sem cota, das
+/// CPUs disponíveis ao processo. Sai da diferença do tempo acumulado entre ciclos
+pub struct CgroupCpuCollector {
+    cgroup: Cgroup,
+    last: Option<(u64, Instant)>,
+}
+
+impl CgroupCpuCollector {
+    /// A primeira leitura já serve de base: o warm-up de CPU do startup cobre o intervalo
+    pub fn new(cgroup: Cgroup) -> Self {
+        let last = cgroup.cpu_usage_usec().map(|usage| (usage, Instant::now()));
+        Self { cgroup, last }
+    }
+
+    fn available_cpus(&self) -> f32 {
+        self.cgroup.cpu_limit().unwrap_or_else(|| {
+            thread::available_parallelism().map_or(1, |cpus| cpus.get()) as f32
+        })
+    }
+}
+
+impl MetricCollector for CgroupCpuCollector {
+    fn name(&self) -> &str {
+        "CPU"
+    }
+
+    fn collect(&mut self) -> Option<f32> {
+        let usage = self.cgroup.cpu_usage_usec()?;
+        let now = Instant::now();
+        let (last_usage, last_at) = self.last.replace((usage, now))?;
+
+        let elapsed_usec = now.duration_since(last_at).as_micros() as f32;
+        if elapsed_usec <= 0.0 {
+            return None;
+        }
+        let used = usage.saturating_sub(last_usage) as f32;
+        Some((used / (elapsed_usec * self.available_cpus()) * 100.0).clamp(0.0, 100.0))
+    }
+}
+
+/// Memória do cgroup em `MEM` (bytes) ou `MEM_PERCENT` (% de `memory.max`, ou da
+/// RAM do host quando o container não tem limite de memória)
+pub struct CgroupMemoryCollector {
+    cgroup: Cgroup,
+    mode: MemoryMode,
+    host_total: u64,
+}
+
+impl CgroupMemoryCollector {
+    pub fn new(cgroup: Cgroup, mode: MemoryMode) -> Self {
+        let kind = RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram());
+        let host_total = System::new_with_specifics(kind).total_memory();
+        Self { cgroup, mode, host_total }
+    }
+}
+
+impl MetricCollector for CgroupMemoryCollector {
+    fn name(&self) -> &str {
+        match self.mode {
+            MemoryMode::Bytes => "MEM",
+            MemoryMode::Percent => "MEM_PERCENT",
+        }
+    }
+
+    fn collect(&mut self) -> Option<f32> {
+        let used = self.cgroup.memory_used()?;
+        match self.mode {
+            MemoryMode::Bytes => Some(used as f32),
+            MemoryMode::Percent => Some(percent_of(used, self.cgroup.memory_limit().unwrap_or(self.host_total))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Árvore de cgroup v2 falsa num diretório temporário
+    fn fake_cgroup(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("telemetry-cgroup-{}-{}", name, std::process::id()));
+        let dir = root.join("kubepods/pod1");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(root.join("cgroup.controllers"), "cpu memory\n").unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn reads_limits_and_usage_of_own_cgroup() {
+        let root = fake_cgroup("limits", &[
+            ("memory.max", "536870912\n"),
+            ("memory.current", "300000000\n"),
+            ("memory.stat", "anon 200000000\ninactive_file 100000000\nactive_file 1\n"),
+            ("cpu.max", "150000 100000\n"),
+            ("cpu.stat", "usage_usec 12345\nuser_usec 10000\n"),
+        ]);
+        let cgroup = Cgroup::at(&root, Some("0::/kubepods/pod1\n")).unwrap();
+
+        assert!(cgroup.has_limits());
+        assert_eq!(cgroup.memory_limit(), Some(536_870_912));
+        assert_eq!(cgroup.memory_used(), Some(200_000_000));
+        assert_eq!(cgroup.cpu_limit(), Some(1.5));
+        assert_eq!(cgroup.cpu_usage_usec(), Some(12_345));
+
+        let mut memory = CgroupMemoryCollector::new(cgroup.clone(), MemoryMode::Percent);
+        let percent = memory.collect().unwrap();
+        assert!((percent - 37.25).abs() < 0.01, "{}", percent);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn unlimited_or_missing_cgroup_falls_back_to_host() {
+        let root = fake_cgroup("unlimited", &[("memory.max", "max\n"), ("cpu.max", "max 100000\n")]);
+        let cgroup = Cgroup::at(&root, Some("0::/kubepods/pod1\n")).unwrap();
+        assert!(!cgroup.has_limits());
+
+        // Caminho que não existe dentro da montagem: vale a raiz
+        assert_eq!(Cgroup::at(&root, Some("0::/outro\n")).unwrap().dir(), root.as_path());
+        assert!(Cgroup::at(&root.join("kubepods"), None).is_none());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn cpu_percent_is_relative_to_quota() {
+        let root = fake_cgroup("cpu", &[("cpu.max", "50000 100000\n"), ("cpu.stat", "usage_usec 0\n")]);
+        let cgroup = Cgroup::at(&root, Some("0::/kubepods/pod1\n")).unwrap();
+        let mut collector = CgroupCpuCollector::new(cgroup);
+
+        // Meia CPU de cota, 0,25 CPU usada ao longo do intervalo: 50%
+        let started = Instant::now();
+        thread::sleep(std::time::Duration::from_millis(200));
+        let used = started.elapsed().as_micros() as u64 / 4;
+        fs::write(root.join("kubepods/pod1/cpu.stat"), format!("usage_usec {}\n", used)).unwrap();
+
+        let cpu = collector.collect().unwrap();
+        assert!((45.0..=50.5).contains(&cpu), "{}", cpu);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
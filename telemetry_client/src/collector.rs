@@ -0,0 +1,371 @@
+use std::{
+    fs,
+    io::Read,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+use telemetry_common::sample::percent_of;
+
+use crate::{
+    cgroup::{self, CgroupCpuCollector, CgroupMemoryCollector},
+    config::{ClientConfig, CpuMode, MemoryMode},
+    metric::HardwareMetric,
+    tcp::TcpConnectionsCollector,
+};
+
+/// Fonte de uma métrica numérica. Embutidos e customizados passam pelo mesmo
+/// laço de coleta, e o valor vai para o JSON com o nome do coletor. Rodam na
+/// thread de coleta, daí o `Send`
+pub trait MetricCollector: Send {
+    /// Nome da métrica no JSON (`CPU`, `FAN_RPM`...)
+    fn name(&self) -> &str;
+
+    /// Valor atual; `None` deixa a métrica de fora neste ciclo
+    fn collect(&mut self) -> Option<f32>;
+}
+
+/// Média de uso entre todos os núcleos, em `CPU`
+pub struct CpuCollector {
+    system: System,
+}
+
+impl CpuCollector {
+    pub fn new() -> Self {
+        let kind = RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing().with_cpu_usage());
+        Self { system: System::new_with_specifics(kind) }
+    }
+}
+
+impl MetricCollector for CpuCollector {
+    fn name(&self) -> &str {
+        "CPU"
+    }
+
+    fn collect(&mut self) -> Option<f32> {
+        self.system.refresh_cpu_usage();
+        let cpus = self.system.cpus();
+        if cpus.is_empty() {
+            return Some(0.0);
+        }
+        Some(cpus.iter().map(|cpu| cpu.cpu_usage()).sum::<f32>() / cpus.len() as f32)
+    }
+}
+
+/// Memória em uso: bytes em `MEM` ou porcentagem do total em `MEM_PERCENT`
+pub struct MemoryCollector {
+    system: System,
+    mode: MemoryMode,
+}
+
+impl MemoryCollector {
+    pub fn new(mode: MemoryMode) -> Self {
+        let kind = RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram());
+        Self { system: System::new_with_specifics(kind), mode }
+    }
+}
+
+impl MetricCollector for MemoryCollector {
+    fn name(&self) -> &str {
+        match self.mode {
+            MemoryMode::Bytes => "MEM",
+            MemoryMode::Percent => "MEM_PERCENT",
+        }
+    }
+
+    fn collect(&mut self) -> Option<f32> {
+        self.system.refresh_memory_specifics(MemoryRefreshKind::nothing().with_ram());
+        let used = self.system.used_memory();
+        match self.mode {
+            MemoryMode::Bytes => Some(used as f32),
+            MemoryMode::Percent => Some(percent_of(used, self.system.total_memory())),
+        }
+    }
+}
+
+/// Número lido de um arquivo a cada ciclo (`--file-metric FAN_RPM=/sys/...`).
+/// Arquivo ausente ou conteúdo não numérico pulam a métrica, com aviso só na
+/// primeira falha seguida para não inundar o console
+pub struct FileCollector {
+    name: String,
+    path: PathBuf,
+    failing: bool,
+}
+
+impl FileCollector {
+    pub fn new(name: String, path: PathBuf) -> Self {
+        Self { name, path, failing: false }
+    }
+
+    fn read(&self) -> Result<f32, String> {
+        let raw = fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        raw.trim().parse::<f32>().map_err(|_| format!("conteúdo não numérico '{}'", raw.trim()))
+    }
+}
+
+impl MetricCollector for FileCollector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn collect(&mut self) -> Option<f32> {
+        match self.read() {
+            Ok(value) => {
+                self.failing = false;
+                Some(value)
+            }
+            Err(e) => {
+                if !self.failing {
+                    eprintln!("⚠️ Métrica {} ({}) ignorada: {}", self.name, self.path.display(), e);
+                }
+                self.failing = true;
+                None
+            }
+        }
+    }
+}
+
+/// Intervalo entre as verificações de término do comando
+const COMMAND_POLL: Duration = Duration::from_millis(10);
+
+/// Número impresso por um comando shell a cada ciclo (`--command-metric
+/// BATTERY='cat /sys/class/power_supply/BAT0/capacity'`). O comando que passa
+/// de `timeout` é morto, com os processos que ele criou, para não atrasar a
+/// coleta; falha, timeout ou saída não numérica pulam a métrica
+pub struct CommandCollector {
+    name: String,
+    command: String,
+    timeout: Duration,
+    failing: bool,
+}
+
+impl CommandCollector {
+    pub fn new(name: String, command: String, timeout: Duration) -> Self {
+        Self { name, command, timeout, failing: false }
+    }
+
+    fn shell(&self) -> Command {
+        #[cfg(windows)]
+        let mut command = {
+            let mut command = Command::new("cmd");
+            command.arg("/C");
+            command
+        };
+        #[cfg(not(windows))]
+        let mut command = {
+            use std::os::unix::process::CommandExt;
+
+            let mut command = Command::new("sh");
+            // Grupo próprio: no timeout, o kill alcança também os filhos do shell
+            command.arg("-c").process_group(0);
+            command
+        };
+        command.arg(&self.command).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null());
+        command
+    }
+
+    fn run(&self) -> Result<f32, String> {
+        let mut child = self.shell().spawn().map_err(|e| e.to_string())?;
+        let deadline = Instant::now() + self.timeout;
+
+        // Lida em paralelo: uma saída maior que o buffer do pipe travaria o comando
+        let (sender, output) = mpsc::channel();
+        if let Some(mut stdout) = child.stdout.take() {
+            thread::spawn(move || {
+                let mut text = String::new();
+                let result = stdout.read_to_string(&mut text).map(|_| text);
+                let _ = sender.send(result);
+            });
+        }
+
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                kill_tree(&mut child);
+                return Err(format!("sem resposta em {}ms", self.timeout.as_millis()));
+            }
+            thread::sleep(COMMAND_POLL);
+        };
+
+        // Um processo deixado em background pelo comando pode manter o pipe aberto
+        let output = match output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(result) => result.map_err(|e| e.to_string())?,
+            Err(_) => {
+                kill_tree(&mut child);
+                return Err(format!("saída não terminou em {}ms", self.timeout.as_millis()));
+            }
+        };
+        if !status.success() {
+            return Err(format!("comando falhou ({})", status));
+        }
+        output.trim().parse::<f32>().map_err(|_| format!("saída não numérica '{}'", output.trim()))
+    }
+}
+
+/// Mata o comando e o grupo de processos dele (no Windows, só o próprio processo)
+fn kill_tree(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        unsafe { libc::kill(-pid, libc::SIGKILL) };
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+impl MetricCollector for CommandCollector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn collect(&mut self) -> Option<f32> {
+        match self.run() {
+            Ok(value) => {
+                self.failing = false;
+                Some(value)
+            }
+            Err(e) => {
+                if !self.failing {
+                    eprintln!("⚠️ Métrica {} (`{}`) ignorada: {}", self.name, self.command, e);
+                }
+                self.failing = true;
+                None
+            }
+        }
+    }
+}
+
+/// Coletores da configuração e as métricas que continuam com coleta própria no
+/// cliente, por dependerem do `System` compartilhado (processos, núcleos, clock...).
+/// Num container com limites, CPU média e memória vêm do cgroup
+pub fn from_config(config: &ClientConfig) -> (Vec<Box<dyn MetricCollector>>, Vec<HardwareMetric>) {
+    let mut collectors: Vec<Box<dyn MetricCollector>> = Vec::new();
+    let mut remaining = Vec::new();
+    // Com núcleos, a média sai do mesmo refresh deles, no sampler. As médias por
+    // socket (`--cpu-sockets`) são só um acréscimo e não mudam de onde vem a `CPU`
+    let average_only = config.cpu_mode == CpuMode::Average;
+    let uses_cgroup = config.metrics.contains(&HardwareMetric::Memory)
+        || (config.metrics.contains(&HardwareMetric::Cpu) && average_only);
+    let cgroup = uses_cgroup.then(|| cgroup::resolve(config.container)).flatten();
+
+    for metric in &config.metrics {
+        match (metric, &cgroup) {
+            // Por núcleo, a média sai do mesmo refresh dos núcleos, sempre do host
+            (HardwareMetric::Cpu, Some(cgroup)) if average_only => {
+                collectors.push(Box::new(CgroupCpuCollector::new(cgroup.clone())));
+            }
+            (HardwareMetric::Cpu, None) if average_only => collectors.push(Box::new(CpuCollector::new())),
+            (HardwareMetric::Memory, Some(cgroup)) => collectors.push(Box::new(CgroupMemoryCollector::new(cgroup.clone(), config.mem_mode))),
+            (HardwareMetric::Memory, None) => collectors.push(Box::new(MemoryCollector::new(config.mem_mode))),
+            (HardwareMetric::TcpConnections, _) => collectors.push(Box::new(TcpConnectionsCollector::new(config.tcp_state))),
+            (other, _) => remaining.push(*other),
+        }
+    }
+    for (name, path) in &config.file_metrics {
+        collectors.push(Box::new(FileCollector::new(name.clone(), path.clone())));
+    }
+    for (name, command) in &config.command_metrics {
+        collectors.push(Box::new(CommandCollector::new(name.clone(), command.clone(), config.command_timeout)));
+    }
+    (collectors, remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_collector_reads_number_and_skips_garbage() {
+        let path = std::env::temp_dir().join(format!("telemetry-file-metric-{}", std::process::id()));
+        let mut collector = FileCollector::new("FAN_RPM".to_string(), path.clone());
+        assert_eq!(collector.collect(), None);
+
+        fs::write(&path, "1234\n").unwrap();
+        assert_eq!(collector.collect(), Some(1234.0));
+
+        fs::write(&path, "n/a").unwrap();
+        assert_eq!(collector.collect(), None);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(collector.name(), "FAN_RPM");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_collector_parses_stdout_and_skips_failures() {
+        let timeout = Duration::from_millis(500);
+        assert_eq!(CommandCollector::new("BATTERY".into(), "echo ' 87 '".into(), timeout).collect(), Some(87.0));
+        assert_eq!(CommandCollector::new("X".into(), "echo abc".into(), timeout).collect(), None);
+        assert_eq!(CommandCollector::new("X".into(), "echo 1; exit 3".into(), timeout).collect(), None);
+
+        let started = Instant::now();
+        assert_eq!(CommandCollector::new("X".into(), "sleep 5".into(), timeout).collect(), None);
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        // Saída maior que o buffer do pipe (64 KiB no Linux) antes do número
+        let verbose = "head -c 200000 /dev/zero | tr '\\0' ' '; echo 42";
+        assert_eq!(CommandCollector::new("X".into(), verbose.into(), timeout).collect(), Some(42.0));
+    }
+
+    /// No timeout, os processos criados pelo comando morrem junto com o shell
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn command_timeout_kills_the_process_group() {
+        let pid_file = std::env::temp_dir().join(format!("telemetry-command-{}.pid", std::process::id()));
+        let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let mut collector = CommandCollector::new("X".into(), command, Duration::from_millis(300));
+        assert_eq!(collector.collect(), None);
+
+        let pid = fs::read_to_string(&pid_file).unwrap().trim().to_string();
+        let _ = fs::remove_file(&pid_file);
+        // Morto: sumiu ou virou zumbi à espera do reaper
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let alive = loop {
+            let state = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()
+                .and_then(|stat| stat.rsplit(") ").next().and_then(|rest| rest.chars().next()));
+            let alive = state.is_some_and(|state| state != 'Z');
+            if !alive || Instant::now() >= deadline {
+                break alive;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert!(!alive, "sleep {} sobreviveu ao timeout", pid);
+    }
+
+    #[test]
+    fn first_cpu_sample_after_warm_up_is_plausible() {
+        let mut collector = CpuCollector::new();
+        let started = Instant::now();
+        // Carga durante o warm-up: um 0% aqui seria necessariamente falso
+        let busy = thread::spawn(move || {
+            while started.elapsed() < sysinfo::MINIMUM_CPU_UPDATE_INTERVAL * 2 {
+                std::hint::spin_loop();
+            }
+        });
+        crate::refresh::wait_cpu_warm_up(started);
+        let cpu = collector.collect().unwrap();
+        busy.join().unwrap();
+
+        assert!(started.elapsed() >= sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        assert!(cpu > 0.0 && cpu <= 100.0, "CPU implausível na primeira amostra: {}", cpu);
+    }
+
+    /// `--cpu-sockets` não tira a média em `CPU` do coletor (cgroup num container);
+    /// só o modo por núcleo a devolve ao sampler
+    #[test]
+    fn cpu_sockets_keep_the_average_collector() {
+        let args = ["--metrics", "cpu", "--cpu-sockets", "--container", "host"].map(String::from);
+        let (collectors, remaining) = from_config(&ClientConfig::parse(args.into_iter()).unwrap());
+        assert_eq!(collectors.iter().map(|collector| collector.name()).collect::<Vec<_>>(), ["CPU"]);
+        assert!(remaining.is_empty());
+
+        let args = ["--metrics", "cpu", "--cpu-sockets", "--cpu-mode", "both"].map(String::from);
+        let (collectors, remaining) = from_config(&ClientConfig::parse(args.into_iter()).unwrap());
+        assert!(collectors.is_empty());
+        assert_eq!(remaining, [HardwareMetric::Cpu]);
+    }
+}
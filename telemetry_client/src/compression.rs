@@ -0,0 +1,32 @@
+pub use telemetry_common::compression::{compress, MODE_GZIP};
+
+/// Mensagens entre cada relatório da razão de compressão
+const REPORT_INTERVAL: u32 = 100;
+
+/// Acumula bytes antes e depois da compressão para o relatório periódico
+#[derive(Debug, Default)]
+pub struct CompressionStats {
+    raw_bytes: u64,
+    compressed_bytes: u64,
+    messages: u32,
+}
+
+impl CompressionStats {
+    /// Registra uma mensagem e, a cada `REPORT_INTERVAL`, loga a razão média e zera a janela
+    pub fn record(&mut self, raw: usize, compressed: usize) {
+        self.raw_bytes += raw as u64;
+        self.compressed_bytes += compressed as u64;
+        self.messages += 1;
+
+        if self.messages >= REPORT_INTERVAL {
+            println!(
+                "🗜️  Compressão média nas últimas {} mensagens: {:.1}% ({} → {} bytes)",
+                self.messages,
+                self.compressed_bytes as f64 * 100.0 / self.raw_bytes.max(1) as f64,
+                self.raw_bytes,
+                self.compressed_bytes
+            );
+            *self = Self::default();
+        }
+    }
+}
@@ -0,0 +1,609 @@
+use std::{
+    env,
+    fmt,
+    net::SocketAddr,
+    path::PathBuf,
+    time::Duration,
+};
+
+use telemetry_common::{framing::Framing, UNIX_ADDRESS_PREFIX};
+
+use crate::{metric::HardwareMetric, priority::MAX_NICE};
+
+// Endereço padrão quando nenhum é passado na linha de comando
+const SERVER_ADDRESS: &str = "127.0.0.1:8080";
+const TELEMETRY_INTERVAL_MS: u64 = 1000;
+/// Menor intervalo aceito, evita uma tempestade de pacotes com valores como 0
+pub const MIN_INTERVAL_MS: u64 = 50;
+/// Variável de ambiente com o intervalo de coleta em milissegundos
+const INTERVAL_ENV: &str = "TELEMETRY_INTERVAL_MS";
+/// Variável de ambiente com as métricas habilitadas, no formato de `--metrics`
+const METRICS_ENV: &str = "TELEMETRY_METRICS";
+/// Variável de ambiente com o modo de `--container`
+const CONTAINER_ENV: &str = "TELEMETRY_CONTAINER";
+/// Maior número de amostras num batch, para o array caber folgado num frame
+pub const MAX_BATCH_SAMPLES: usize = 1000;
+/// Espera máxima por uma conexão TCP (e pelo handshake TLS)
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_COMMAND_TIMEOUT_MS: u64 = 2000;
+/// Espera pelas respostas ao broadcast de descoberta
+const DEFAULT_DISCOVERY_TIMEOUT_MS: u64 = 2000;
+/// Tentativas de reconexão antes de encerrar o cliente
+const DEFAULT_MAX_RETRIES: u32 = 10;
+/// Amostras guardadas durante uma queda: 5 minutos no intervalo padrão
+const DEFAULT_OFFLINE_BUFFER: usize = 300;
+/// Teto do `--offline-buffer`, para uma queda longa não estourar a memória
+pub const MAX_OFFLINE_BUFFER: usize = 100_000;
+/// Amostras à espera de envio com a conexão congestionada: 1 minuto no intervalo padrão
+const DEFAULT_SEND_QUEUE: usize = 60;
+/// Variação, em %, que faz o intervalo adaptativo voltar ao mínimo
+const DEFAULT_ADAPTIVE_THRESHOLD_PERCENT: f32 = 5.0;
+const MAX_SEND_QUEUE: usize = 10_000;
+
+/// Quais valores de CPU são enviados
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuMode {
+    /// Só a média de todos os núcleos em `CPU` (padrão)
+    Average,
+    /// Um valor por núcleo em `CPU_0`, `CPU_1`, ...
+    PerCore,
+    /// A média e os núcleos
+    Both,
+}
+
+impl CpuMode {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw.to_ascii_lowercase().as_str() {
+            "average" => Ok(CpuMode::Average),
+            "per-core" => Ok(CpuMode::PerCore),
+            "both" => Ok(CpuMode::Both),
+            _ => Err(format!("Modo de CPU inválido '{}' (use average, per-core ou both)", raw)),
+        }
+    }
+}
+
+/// Como o clock dos núcleos vira o valor único de `CPU_FREQ`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreqMode {
+    /// Média dos núcleos (padrão)
+    Average,
+    /// Núcleo mais rápido
+    Max,
+}
+
+impl FreqMode {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw.to_ascii_lowercase().as_str() {
+            "average" | "avg" => Ok(FreqMode::Average),
+            "max" => Ok(FreqMode::Max),
+            _ => Err(format!("Modo de frequência inválido '{}' (use average ou max)", raw)),
+        }
+    }
+}
+
+/// Como a memória em uso é enviada
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryMode {
+    /// Valor absoluto em bytes em `MEM` (padrão)
+    Bytes,
+    /// Porcentagem da memória total em `MEM_PERCENT`
+    Percent,
+}
+
+impl MemoryMode {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw.to_ascii_lowercase().as_str() {
+            "bytes" => Ok(MemoryMode::Bytes),
+            "percent" => Ok(MemoryMode::Percent),
+            _ => Err(format!("Modo de memória inválido '{}' (use bytes ou percent)", raw)),
+        }
+    }
+}
+
+/// Quais conexões entram em `TCP_CONNS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpState {
+    /// Só as estabelecidas (padrão)
+    Established,
+    /// Todas, inclusive em escuta e em `TIME_WAIT`
+    All,
+}
+
+impl TcpState {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw.to_ascii_lowercase().as_str() {
+            "established" => Ok(TcpState::Established),
+            "all" => Ok(TcpState::All),
+            _ => Err(format!("Estado TCP inválido '{}' (use established ou all)", raw)),
+        }
+    }
+}
+
+/// De onde vêm CPU e memória: do host ou dos limites do container (cgroup v2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerMode {
+    /// Cgroup v2 quando houver limite de CPU ou memória; o host nos demais casos (padrão)
+    Auto,
+    Host,
+    Cgroup,
+}
+
+impl ContainerMode {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw.to_ascii_lowercase().as_str() {
+            "auto" => Ok(ContainerMode::Auto),
+            "host" => Ok(ContainerMode::Host),
+            "cgroup" => Ok(ContainerMode::Cgroup),
+            _ => Err(format!("Modo de container inválido '{}' (use auto, host ou cgroup)", raw)),
+        }
+    }
+}
+
+/// Limites do intervalo adaptativo (`--adaptive MIN:MAX`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveOptions {
+    /// Intervalo com as métricas variando; também o inicial
+    pub min: Duration,
+    /// Teto alcançado com as métricas estáveis
+    pub max: Duration,
+    /// Variação relativa (0.05 = 5%) entre amostras que conta como mudança
+    pub threshold: f32,
+}
+
+impl AdaptiveOptions {
+    /// `MIN:MAX` em milissegundos, com o mínimo de `MIN_INTERVAL_MS`
+    fn parse(raw: &str, threshold_percent: f32) -> Result<Self, String> {
+        let invalid = || format!("--adaptive inválido: {} (use MIN:MAX em ms, ex.: 500:10000)", raw);
+        let (min, max) = raw.split_once(':').ok_or_else(invalid)?;
+        let min: u64 = min.trim().parse().map_err(|_| invalid())?;
+        let max: u64 = max.trim().parse().map_err(|_| invalid())?;
+        if min < MIN_INTERVAL_MS || max < min {
+            return Err(format!("--adaptive inválido: {} (mínimo de {}ms e MAX >= MIN)", raw, MIN_INTERVAL_MS));
+        }
+        Ok(Self {
+            min: Duration::from_millis(min),
+            max: Duration::from_millis(max),
+            threshold: threshold_percent / 100.0,
+        })
+    }
+}
+
+/// Opções de TLS vindas da linha de comando
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    /// Certificado(s) de CA em PEM usados para validar o servidor
+    pub ca_path: Option<PathBuf>,
+    /// Fingerprint SHA-256 (hex) esperado para o certificado do servidor
+    pub pin: Option<String>,
+    /// Nome validado no certificado; padrão é o IP do endereço
+    pub server_name: Option<String>,
+}
+
+/// Endereço de um servidor: `host:porta` na rede ou `unix:/caminho` na mesma máquina
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerAddress {
+    Inet(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl ServerAddress {
+    fn parse(raw: &str) -> Result<Self, String> {
+        if let Some(path) = raw.strip_prefix(UNIX_ADDRESS_PREFIX) {
+            if path.is_empty() {
+                return Err(format!("Endereço inválido '{}': falta o caminho do socket (ex.: unix:/tmp/telemetry.sock)", raw));
+            }
+            return Ok(ServerAddress::Unix(PathBuf::from(path)));
+        }
+        raw.parse()
+            .map(ServerAddress::Inet)
+            .map_err(|e| format!("Endereço inválido '{}': {} (ex.: 192.168.0.10:8080)", raw, e))
+    }
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerAddress::Inet(address) => write!(f, "{}", address),
+            ServerAddress::Unix(path) => write!(f, "{}{}", UNIX_ADDRESS_PREFIX, path.display()),
+        }
+    }
+}
+
+/// Configuração do cliente montada a partir da linha de comando e do ambiente
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Servidores de destino; cada amostra é enviada a todos
+    pub addresses: Vec<ServerAddress>,
+    /// Sem endereço na linha de comando: procura um servidor na LAN antes de usar o padrão
+    pub discover: bool,
+    pub discovery_timeout: Duration,
+    pub interval: Duration,
+    /// Timeout de cada tentativa de conexão (`--connect-timeout`)
+    pub connect_timeout: Duration,
+    pub framing: Framing,
+    /// Tentativas de reconexão; `0` tenta indefinidamente
+    pub max_retries: u32,
+    /// Envia também o nome e a %CPU do processo de maior consumo
+    pub top_process: bool,
+    /// TLS habilitado quando presente
+    pub tls: Option<TlsOptions>,
+    /// Compacta cada frame com gzip (exige framing por comprimento)
+    pub compress: bool,
+    /// Envia cada amostra como um datagrama UDP, sem conexão persistente
+    pub udp: bool,
+    /// Fator da média móvel exponencial (0 < alpha <= 1); `1.0` desativa a suavização
+    pub smoothing: f32,
+    /// Métricas coletadas; por padrão, todas
+    pub metrics: Vec<HardwareMetric>,
+    pub cpu_mode: CpuMode,
+    /// Média por socket físico/nó NUMA em `CPU_SOCKET_<n>` (`--cpu-sockets`)
+    pub cpu_sockets: bool,
+    pub freq_mode: FreqMode,
+    pub mem_mode: MemoryMode,
+    /// CPU e memória do host ou do container (`--container`)
+    pub container: ContainerMode,
+    /// Conexões contadas em `TCP_CONNS` (`--tcp-state`)
+    pub tcp_state: TcpState,
+    /// Amostras por batch (`--batch`); `1` envia cada amostra sozinha
+    pub batch_size: usize,
+    /// Maior espera de uma amostra no batch (`--batch-ms`)
+    pub batch_max_age: Option<Duration>,
+    /// Prefixo das métricas nas exportações do servidor (`--namespace`); o hostname quando ausente
+    pub namespace: Option<String>,
+    /// Métricas customizadas lidas de arquivos (`--file-metric NOME=ARQUIVO`)
+    pub file_metrics: Vec<(String, PathBuf)>,
+    /// Métricas customizadas vindas da saída de comandos (`--command-metric NOME=COMANDO`)
+    pub command_metrics: Vec<(String, String)>,
+    /// Tempo máximo de cada comando antes de ser morto (`--command-timeout`)
+    pub command_timeout: Duration,
+    /// Nice aplicado ao próprio processo no startup (`--nice`); ausente mantém a prioridade normal
+    pub nice: Option<i32>,
+    /// Envia uma única amostra e encerra (`--once`)
+    pub once: bool,
+    /// Imprime cada amostra legível antes de enviá-la (`--print`)
+    pub print: bool,
+    /// Amostras guardadas sem servidor conectado e reenviadas na reconexão; `0` desativa
+    pub offline_buffer: usize,
+    /// Amostras coletadas à espera de um envio lento; além disso, as mais antigas são descartadas
+    pub send_queue: usize,
+    /// Intervalo que se ajusta à variação das métricas (`--adaptive`); ausente usa `interval` fixo
+    pub adaptive: Option<AdaptiveOptions>,
+}
+
+impl ClientConfig {
+    /// Uso: `telemetry_client [ENDEREÇO...] [--interval MS] [--connect-timeout MS] [--framing line|length] [--max-retries N] [--top-process]
+    /// [--metrics cpu,mem,...] [--smoothing ALPHA] [--cpu-mode average|per-core|both] [--cpu-sockets] [--cpu-freq average|max] [--mem-mode bytes|percent] [--container auto|host|cgroup] [--tcp-state established|all] [--batch N] [--batch-ms MS] [--namespace NOME] [--file-metric NOME=ARQUIVO] [--command-metric NOME=COMANDO] [--command-timeout MS] [--nice N] [--once] [--print] [--offline-buffer N] [--send-queue N] [--adaptive MIN:MAX] [--adaptive-threshold PCT] [--discovery-timeout MS] [--no-discovery] [--compress] [--udp] [--tls] [--tls-ca ARQUIVO] [--tls-pin SHA256] [--tls-name NOME]`.
+    /// Os argumentos `--interval`, `--metrics` e `--container` têm prioridade sobre
+    /// `TELEMETRY_INTERVAL_MS`, `TELEMETRY_METRICS` e `TELEMETRY_CONTAINER`;
+    /// Sem `ENDEREÇO`, o cliente procura um servidor por broadcast e cai em
+    /// `SERVER_ADDRESS` se nenhum responder;
+    /// `--compress` implica `--framing length`; `--batch-ms` sem `--batch` limita o batch
+    /// só pelo tempo (até `MAX_BATCH_SAMPLES`).
+    pub fn from_args() -> Result<Self, String> {
+        Self::parse(env::args().skip(1))
+    }
+
+    /// `from_args` sobre uma lista de argumentos já sem o nome do programa
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut addresses: Vec<String> = Vec::new();
+        let mut interval: Option<String> = env::var(INTERVAL_ENV).ok();
+        let mut metrics: Option<String> = env::var(METRICS_ENV).ok();
+        let mut framing: Option<Framing> = None;
+        let mut max_retries = DEFAULT_MAX_RETRIES;
+        let mut connect_timeout = Duration::from_millis(DEFAULT_CONNECT_TIMEOUT_MS);
+        let mut top_process = false;
+        let mut tls: Option<TlsOptions> = None;
+        let mut compress = false;
+        let mut udp = false;
+        let mut smoothing = 1.0f32;
+        let mut cpu_mode = CpuMode::Average;
+        let mut cpu_sockets = false;
+        let mut freq_mode = FreqMode::Average;
+        let mut mem_mode = MemoryMode::Bytes;
+        let mut container: Option<String> = env::var(CONTAINER_ENV).ok();
+        let mut tcp_state = TcpState::Established;
+        let mut batch_size: Option<usize> = None;
+        let mut batch_max_age: Option<Duration> = None;
+        let mut namespace: Option<String> = None;
+        let mut file_metrics = Vec::new();
+        let mut command_metrics = Vec::new();
+        let mut command_timeout = Duration::from_millis(DEFAULT_COMMAND_TIMEOUT_MS);
+        let mut nice: Option<i32> = None;
+        let mut once = false;
+        let mut print = false;
+        let mut offline_buffer = DEFAULT_OFFLINE_BUFFER;
+        let mut send_queue = DEFAULT_SEND_QUEUE;
+        let mut adaptive: Option<String> = None;
+        let mut adaptive_threshold: Option<f32> = None;
+        let mut discovery = true;
+        let mut discovery_timeout = Duration::from_millis(DEFAULT_DISCOVERY_TIMEOUT_MS);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--interval" | "-i" => {
+                    interval = Some(args.next().ok_or("--interval exige um valor em ms")?);
+                }
+                "--connect-timeout" => {
+                    let raw = args.next().ok_or("--connect-timeout exige um valor em ms")?;
+                    let ms = raw.parse::<u64>().ok()
+                        .filter(|ms| *ms > 0)
+                        .ok_or_else(|| format!("--connect-timeout inválido: {}", raw))?;
+                    connect_timeout = Duration::from_millis(ms);
+                }
+                "--framing" => {
+                    framing = Some(Framing::parse(&args.next().ok_or("--framing exige line ou length")?)?);
+                }
+                "--max-retries" => {
+                    let raw = args.next().ok_or("--max-retries exige um número")?;
+                    max_retries = raw.parse()
+                        .map_err(|_| format!("--max-retries inválido: {}", raw))?;
+                }
+                "--top-process" => top_process = true,
+                "--once" => once = true,
+                "--print" => print = true,
+                "--metrics" => {
+                    metrics = Some(args.next().ok_or("--metrics exige uma lista, ex.: cpu,disk,net")?);
+                }
+                "--smoothing" => {
+                    let raw = args.next().ok_or("--smoothing exige um alpha entre 0 e 1")?;
+                    smoothing = raw.parse::<f32>().ok()
+                        .filter(|alpha| *alpha > 0.0 && *alpha <= 1.0)
+                        .ok_or_else(|| format!("--smoothing inválido: {} (use 0 < alpha <= 1)", raw))?;
+                }
+                "--cpu-mode" => {
+                    cpu_mode = CpuMode::parse(&args.next().ok_or("--cpu-mode exige average, per-core ou both")?)?;
+                }
+                "--cpu-sockets" => cpu_sockets = true,
+                "--cpu-freq" => {
+                    freq_mode = FreqMode::parse(&args.next().ok_or("--cpu-freq exige average ou max")?)?;
+                }
+                "--mem-mode" => {
+                    mem_mode = MemoryMode::parse(&args.next().ok_or("--mem-mode exige bytes ou percent")?)?;
+                }
+                "--container" => {
+                    container = Some(args.next().ok_or("--container exige auto, host ou cgroup")?);
+                }
+                "--tcp-state" => {
+                    tcp_state = TcpState::parse(&args.next().ok_or("--tcp-state exige established ou all")?)?;
+                }
+                "--batch" => {
+                    let raw = args.next().ok_or("--batch exige o número de amostras")?;
+                    batch_size = Some(raw.parse::<usize>().ok()
+                        .filter(|size| (1..=MAX_BATCH_SAMPLES).contains(size))
+                        .ok_or_else(|| format!("--batch inválido: {} (use de 1 a {})", raw, MAX_BATCH_SAMPLES))?);
+                }
+                "--batch-ms" => {
+                    let raw = args.next().ok_or("--batch-ms exige um valor em ms")?;
+                    let ms = raw.parse::<u64>().ok()
+                        .filter(|ms| *ms > 0)
+                        .ok_or_else(|| format!("--batch-ms inválido: {}", raw))?;
+                    batch_max_age = Some(Duration::from_millis(ms));
+                }
+                "--namespace" => {
+                    let raw = args.next().ok_or("--namespace exige um nome, ex.: web-server-01")?;
+                    if raw.trim().is_empty() {
+                        return Err("--namespace não pode ser vazio".to_string());
+                    }
+                    namespace = Some(raw.trim().to_string());
+                }
+                "--file-metric" => {
+                    let raw = args.next().ok_or("--file-metric exige NOME=ARQUIVO")?;
+                    let (name, path) = parse_named_metric(&raw)
+                        .ok_or_else(|| format!("--file-metric inválido: {} (ex.: FAN_RPM=/sys/class/hwmon/hwmon0/fan1_input)", raw))?;
+                    file_metrics.push((name, PathBuf::from(path)));
+                }
+                "--command-metric" => {
+                    let raw = args.next().ok_or("--command-metric exige NOME=COMANDO")?;
+                    let metric = parse_named_metric(&raw)
+                        .ok_or_else(|| format!("--command-metric inválido: {} (ex.: BATTERY='cat /sys/class/power_supply/BAT0/capacity')", raw))?;
+                    command_metrics.push(metric);
+                }
+                "--command-timeout" => {
+                    let raw = args.next().ok_or("--command-timeout exige um valor em ms")?;
+                    let ms = raw.parse::<u64>().ok()
+                        .filter(|ms| *ms > 0)
+                        .ok_or_else(|| format!("--command-timeout inválido: {}", raw))?;
+                    command_timeout = Duration::from_millis(ms);
+                }
+                "--offline-buffer" => {
+                    let raw = args.next().ok_or("--offline-buffer exige o número de amostras")?;
+                    offline_buffer = raw.parse::<usize>().ok()
+                        .filter(|size| *size <= MAX_OFFLINE_BUFFER)
+                        .ok_or_else(|| format!("--offline-buffer inválido: {} (use de 0 a {})", raw, MAX_OFFLINE_BUFFER))?;
+                }
+                "--send-queue" => {
+                    let raw = args.next().ok_or("--send-queue exige o número de amostras")?;
+                    send_queue = raw.parse::<usize>().ok()
+                        .filter(|size| (1..=MAX_SEND_QUEUE).contains(size))
+                        .ok_or_else(|| format!("--send-queue inválido: {} (use de 1 a {})", raw, MAX_SEND_QUEUE))?;
+                }
+                "--adaptive" => {
+                    adaptive = Some(args.next().ok_or("--adaptive exige MIN:MAX em ms")?);
+                }
+                "--adaptive-threshold" => {
+                    let raw = args.next().ok_or("--adaptive-threshold exige uma porcentagem")?;
+                    adaptive_threshold = Some(raw.parse::<f32>().ok()
+                        .filter(|percent| *percent > 0.0 && percent.is_finite())
+                        .ok_or_else(|| format!("--adaptive-threshold inválido: {}", raw))?);
+                }
+                "--nice" => {
+                    let raw = args.next().ok_or("--nice exige um valor de 1 a 19")?;
+                    nice = Some(raw.parse::<i32>().ok()
+                        .filter(|nice| (1..=MAX_NICE).contains(nice))
+                        .ok_or_else(|| format!("--nice inválido: {} (use 1 a {})", raw, MAX_NICE))?);
+                }
+                "--discovery-timeout" => {
+                    let raw = args.next().ok_or("--discovery-timeout exige um valor em ms")?;
+                    let ms = raw.parse::<u64>().ok()
+                        .filter(|ms| *ms > 0)
+                        .ok_or_else(|| format!("--discovery-timeout inválido: {}", raw))?;
+                    discovery_timeout = Duration::from_millis(ms);
+                }
+                "--no-discovery" => discovery = false,
+                "--compress" => compress = true,
+                "--udp" => udp = true,
+                "--tls" => {
+                    tls.get_or_insert_with(TlsOptions::default);
+                }
+                "--tls-ca" => {
+                    let path = args.next().ok_or("--tls-ca exige o caminho do PEM")?;
+                    tls.get_or_insert_with(TlsOptions::default).ca_path = Some(PathBuf::from(path));
+                }
+                "--tls-pin" => {
+                    let pin = args.next().ok_or("--tls-pin exige o fingerprint SHA-256")?;
+                    tls.get_or_insert_with(TlsOptions::default).pin = Some(pin);
+                }
+                "--tls-name" => {
+                    let name = args.next().ok_or("--tls-name exige um nome")?;
+                    tls.get_or_insert_with(TlsOptions::default).server_name = Some(name);
+                }
+                _ if arg.starts_with('-') => return Err(format!("Opção desconhecida: {}", arg)),
+                // Vários endereços (repetidos ou separados por vírgula) ativam o fan-out
+                _ => addresses.extend(arg.split(',').filter(|part| !part.is_empty()).map(str::to_string)),
+            }
+        }
+
+        let discover = discovery && addresses.is_empty();
+        if addresses.is_empty() {
+            addresses.push(SERVER_ADDRESS.to_string());
+        }
+        let addresses = addresses.iter()
+            .map(|raw| ServerAddress::parse(raw))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Socket local: sem datagramas e sem sessão TLS (o tráfego não sai da máquina)
+        if addresses.iter().any(|address| matches!(address, ServerAddress::Unix(_))) && (udp || tls.is_some()) {
+            return Err("endereços unix: não combinam com --udp nem --tls".to_string());
+        }
+
+        // No UDP cada datagrama já é uma mensagem: não há frames, byte de modo nem sessão TLS
+        if udp && (compress || tls.is_some() || framing == Some(Framing::Length)) {
+            return Err("--udp não combina com --compress, --tls nem --framing length".to_string());
+        }
+
+        // O batch é negociado no handshake, e no UDP não há resposta do servidor
+        if udp && (batch_size.is_some() || batch_max_age.is_some()) {
+            return Err("--batch e --batch-ms exigem conexão TCP (o batch é negociado com o servidor)".to_string());
+        }
+        let batch_size = match (batch_size, batch_max_age) {
+            (Some(size), _) => size,
+            (None, Some(_)) => MAX_BATCH_SAMPLES,
+            (None, None) => 1,
+        };
+
+        // A compressão é por frame: sem prefixo de comprimento não há como delimitar o gzip
+        let framing = match (framing, compress) {
+            (Some(Framing::Line), true) => return Err("--compress exige --framing length".to_string()),
+            (Some(framing), _) => framing,
+            (None, true) => Framing::Length,
+            (None, false) => Framing::Line,
+        };
+
+        let adaptive = match (adaptive, adaptive_threshold) {
+            (Some(raw), threshold) => Some(AdaptiveOptions::parse(&raw, threshold.unwrap_or(DEFAULT_ADAPTIVE_THRESHOLD_PERCENT))?),
+            (None, Some(_)) => return Err("--adaptive-threshold exige --adaptive".to_string()),
+            (None, None) => None,
+        };
+
+        let container = match container {
+            Some(raw) => ContainerMode::parse(&raw)?,
+            None => ContainerMode::Auto,
+        };
+
+        Ok(Self {
+            addresses,
+            discover,
+            discovery_timeout,
+            interval: parse_interval(interval.as_deref()),
+            connect_timeout,
+            metrics: parse_metrics(metrics.as_deref()),
+            framing,
+            max_retries,
+            top_process,
+            tls,
+            compress,
+            udp,
+            smoothing,
+            cpu_mode,
+            cpu_sockets,
+            freq_mode,
+            mem_mode,
+            container,
+            tcp_state,
+            batch_size,
+            batch_max_age,
+            namespace,
+            file_metrics,
+            command_metrics,
+            command_timeout,
+            nice,
+            once,
+            print,
+            offline_buffer,
+            send_queue,
+            adaptive,
+        })
+    }
+}
+
+/// `NOME=VALOR` das métricas customizadas; só o primeiro `=` separa, já que
+/// comandos podem conter outros. Num nome com labels (`TEMP{sensor="nvme"}=...`),
+/// vale o primeiro `=` depois das chaves
+fn parse_named_metric(raw: &str) -> Option<(String, String)> {
+    let mut split = raw.find('=')?;
+    if raw[..split].contains('{') {
+        let labels_end = raw.find('}')? + 1;
+        split = labels_end + raw[labels_end..].find('=')?;
+    }
+    let (name, value) = (raw[..split].trim(), raw[split + 1..].trim());
+    if name.is_empty() || value.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), value.to_string()))
+}
+
+/// Métricas habilitadas; nomes desconhecidos são avisados e ignorados, e uma
+/// lista sem nenhum nome válido cai no padrão (todas)
+fn parse_metrics(raw: Option<&str>) -> Vec<HardwareMetric> {
+    let Some(raw) = raw else {
+        return HardwareMetric::all().to_vec();
+    };
+
+    let (metrics, unknown) = HardwareMetric::parse_list(raw);
+    for name in unknown {
+        eprintln!(
+            "❌ Métrica desconhecida ignorada: {} (use cpu, mem, swap, disk, net, temp, proc, uptime ou gpu)",
+            name
+        );
+    }
+    if metrics.is_empty() {
+        eprintln!("⚠️ Nenhuma métrica válida em '{}' (coletando todas)", raw);
+        return HardwareMetric::all().to_vec();
+    }
+    metrics
+}
+
+/// Converte o intervalo informado; valores inválidos ou abaixo do mínimo
+/// geram aviso e caem no padrão de 1000ms
+fn parse_interval(raw: Option<&str>) -> Duration {
+    let default = Duration::from_millis(TELEMETRY_INTERVAL_MS);
+
+    let Some(raw) = raw else {
+        return default;
+    };
+
+    match raw.trim().parse::<u64>() {
+        Ok(ms) if ms >= MIN_INTERVAL_MS => Duration::from_millis(ms),
+        Ok(ms) => {
+            eprintln!(
+                "⚠️ Intervalo de {}ms abaixo do mínimo de {}ms (usando {}ms)",
+                ms, MIN_INTERVAL_MS, TELEMETRY_INTERVAL_MS
+            );
+            default
+        }
+        Err(_) => {
+            eprintln!("⚠️ Intervalo inválido '{}' (usando {}ms)", raw, TELEMETRY_INTERVAL_MS);
+            default
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    io::{self, Result as IoResult, Write},
+    time::{Duration, Instant},
+};
+
+use telemetry_common::commands::CommandInbox;
+
+use crate::{backoff::Backoff, open_transport, transport::{TlsSettings, Transport}};
+
+/// Um servidor de destino, com conexão e agenda de reconexão próprias: a queda
+/// de um destino não interrompe o envio aos demais
+pub struct Destination {
+    pub address: String,
+    tls: Option<TlsSettings>,
+    /// `None` enquanto o destino está fora, aguardando a próxima tentativa
+    connection: Option<Transport>,
+    /// Bytes recebidos deste servidor ainda sem formar um comando completo
+    pub inbox: CommandInbox,
+    /// O servidor respondeu `accept_batch` nesta conexão
+    pub batch: bool,
+    /// Maior array que o servidor aceita, quando ele o anuncia no `accept_batch`
+    pub batch_limit: Option<usize>,
+    /// Último ping enviado (nonce e instante), base da medição e do intervalo entre pings
+    ping_sent: Option<(u64, Instant)>,
+    /// O servidor respondeu ao ping do handshake: recebe pings periódicos
+    pub ping_supported: bool,
+    /// Último RTT medido com este servidor
+    pub rtt: Option<Duration>,
+    /// Tentativas de reconexão falhas desde a queda
+    pub attempts: u32,
+    /// Momento a partir do qual a próxima tentativa pode ser feita
+    pub retry_at: Instant,
+    /// Início da queda atual, exibido quando a reconexão acontece
+    pub down_since: Instant,
+}
+
+impl Destination {
+    pub fn new(address: String, tls: Option<TlsSettings>) -> Self {
+        let now = Instant::now();
+        Self {
+            address,
+            tls,
+            connection: None,
+            inbox: CommandInbox::default(),
+            batch: false,
+            batch_limit: None,
+            ping_sent: None,
+            ping_supported: false,
+            rtt: None,
+            attempts: 0,
+            retry_at: now,
+            down_since: now,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    pub fn connection(&mut self) -> Option<&mut Transport> {
+        self.connection.as_mut()
+    }
+
+    /// Abre uma conexão nova, descartando o resto de mensagens da anterior.
+    /// O batch é renegociado a cada conexão: o servidor pode ter sido trocado
+    pub fn open(&mut self, udp: bool, timeout: Duration) -> IoResult<()> {
+        let connection = open_transport(&self.address, self.tls.as_ref(), udp, timeout)?;
+        self.connection = Some(connection);
+        self.inbox.clear();
+        self.batch = false;
+        self.batch_limit = None;
+        self.ping_sent = None;
+        self.ping_supported = false;
+        self.rtt = None;
+        Ok(())
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> IoResult<()> {
+        let connection = self.connection.as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "destino desconectado"))?;
+        connection.write_all(data)?;
+        connection.flush() // Garante que dados são enviados imediatamente
+    }
+
+    /// Registra o envio de um ping com este nonce
+    pub fn ping_sent(&mut self, nonce: u64) {
+        self.ping_sent = Some((nonce, Instant::now()));
+    }
+
+    /// Hora de um novo ping: servidor compatível e `interval` desde o último
+    pub fn ping_due(&self, interval: Duration) -> bool {
+        self.ping_supported
+            && self.is_connected()
+            && self.ping_sent.is_none_or(|(_, sent)| sent.elapsed() >= interval)
+    }
+
+    /// Fecha a medição do ping de mesmo nonce; `pong` de um ping antigo é ignorado.
+    /// O primeiro `pong` (do handshake) só habilita os pings: ele é lido depois da
+    /// primeira coleta, então o tempo medido não seria o da rede. Devolve `true` nele
+    pub fn pong(&mut self, nonce: u64) -> bool {
+        let Some((expected, sent)) = self.ping_sent else {
+            return false;
+        };
+        if expected != nonce {
+            return false;
+        }
+
+        if !self.ping_supported {
+            self.ping_supported = true;
+            self.ping_sent = None;
+            return true;
+        }
+        self.rtt = Some(sent.elapsed());
+        false
+    }
+
+    /// Fecha a conexão e agenda a primeira tentativa de reconexão
+    pub fn mark_down(&mut self, backoff: &Backoff) {
+        let now = Instant::now();
+        self.connection = None;
+        self.attempts = 0;
+        self.down_since = now;
+        self.retry_at = now + backoff.delay(0);
+    }
+
+    /// Registra uma tentativa falha e agenda a próxima
+    pub fn schedule_retry(&mut self, backoff: &Backoff) {
+        self.connection = None;
+        self.attempts += 1;
+        self.retry_at = Instant::now() + backoff.delay(self.attempts);
+    }
+
+    /// Destino fora e sem tentativas restantes: não é mais reconectado
+    pub fn is_abandoned(&self, backoff: &Backoff) -> bool {
+        !self.is_connected() && backoff.is_exhausted(self.attempts)
+    }
+
+    /// Destino fora cuja próxima tentativa já pode ser feita
+    pub fn is_due(&self, backoff: &Backoff, now: Instant) -> bool {
+        !self.is_connected() && !backoff.is_exhausted(self.attempts) && self.retry_at <= now
+    }
+
+    /// Quanto falta para a próxima tentativa
+    pub fn retry_in(&self) -> Duration {
+        self.retry_at.saturating_duration_since(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    /// Servidor fora nas três primeiras tentativas: com 4 permitidas o destino
+    /// não é abandonado, e a quarta conecta quando o servidor volta
+    #[test]
+    fn reconnects_after_three_failed_attempts() {
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let backoff = Backoff { max_attempts: 4 };
+        let mut destination = Destination::new(address.to_string(), None);
+        destination.mark_down(&backoff);
+
+        for attempt in 1..=3 {
+            assert!(destination.open(false, Duration::from_secs(1)).is_err());
+            destination.schedule_retry(&backoff);
+            assert_eq!(destination.attempts, attempt);
+            assert!(!destination.is_abandoned(&backoff));
+        }
+        assert!(destination.retry_in() <= Duration::from_secs(8));
+
+        let _server = TcpListener::bind(address).unwrap();
+        destination.open(false, Duration::from_secs(1)).unwrap();
+        assert!(destination.is_connected());
+
+        // Com só 3 tentativas, o destino teria sido abandonado
+        assert!(Backoff { max_attempts: 3 }.is_exhausted(3));
+    }
+}
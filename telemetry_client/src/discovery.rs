@@ -0,0 +1,40 @@
+use std::{
+    io::Result as IoResult,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use telemetry_common::{
+    discovery::{DiscoveryReply, DISCOVERY_PORT, DISCOVERY_REQUEST},
+    framing::is_timeout,
+};
+
+/// Envia o pedido de descoberta em broadcast e devolve o primeiro servidor que
+/// responder dentro de `timeout` com o mesmo transporte do cliente.
+/// `Ok(None)` quando nenhum servidor responde a tempo
+pub fn discover(timeout: Duration, udp: bool) -> IoResult<Option<(SocketAddr, DiscoveryReply)>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_broadcast(true)?;
+    socket.send_to(DISCOVERY_REQUEST.as_bytes(), (Ipv4Addr::BROADCAST, DISCOVERY_PORT))?;
+
+    let transport = if udp { "udp" } else { "tcp" };
+    let deadline = Instant::now() + timeout;
+    let mut buffer = [0u8; 512];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        socket.set_read_timeout(Some(remaining))?;
+
+        let (len, from) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if is_timeout(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        // Servidores no outro transporte respondem também; ficam de fora
+        if let Some(reply) = DiscoveryReply::parse(&buffer[..len]).filter(|reply| reply.transport == transport) {
+            return Ok(Some((reply.server_address(from), reply)));
+        }
+    }
+}
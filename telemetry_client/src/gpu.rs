@@ -0,0 +1,67 @@
+use std::process::Command;
+
+/// Consulta que devolve uma linha `utilização, memória usada (MiB)` por GPU
+const NVIDIA_SMI_ARGS: &[&str] = &[
+    "--query-gpu=utilization.gpu,memory.used",
+    "--format=csv,noheader,nounits",
+];
+
+/// Leitura agregada das GPUs NVIDIA da máquina
+#[derive(Debug, Clone, Copy)]
+pub struct GpuReading {
+    /// Utilização média entre as GPUs (%)
+    pub utilization: f32,
+    /// Memória de vídeo usada somada entre as GPUs (MB)
+    pub memory_used_mb: f32,
+}
+
+/// Lê a GPU via `nvidia-smi`. A ausência do binário (ou do driver) é detectada
+/// na primeira tentativa e memorizada, para não spawnar um processo a cada ciclo
+#[derive(Debug)]
+pub struct GpuProbe {
+    available: bool,
+}
+
+impl GpuProbe {
+    pub fn new() -> Self {
+        Self { available: true }
+    }
+
+    pub fn read(&mut self) -> Option<GpuReading> {
+        if !self.available {
+            return None;
+        }
+
+        let output = match Command::new("nvidia-smi").args(NVIDIA_SMI_ARGS).output() {
+            Ok(output) if output.status.success() => output,
+            _ => {
+                println!("ℹ️ nvidia-smi indisponível: métricas de GPU desativadas");
+                self.available = false;
+                return None;
+            }
+        };
+
+        parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// Interpreta a saída CSV do `nvidia-smi`; linhas ilegíveis (ex.: `[N/A]`) são ignoradas
+fn parse_nvidia_smi(output: &str) -> Option<GpuReading> {
+    let mut count = 0u32;
+    let mut utilization = 0.0;
+    let mut memory_used_mb = 0.0;
+
+    for line in output.lines() {
+        let mut fields = line.split(',').map(|field| field.trim().parse::<f32>());
+        if let (Some(Ok(util)), Some(Ok(mem))) = (fields.next(), fields.next()) {
+            utilization += util;
+            memory_used_mb += mem;
+            count += 1;
+        }
+    }
+
+    (count > 0).then(|| GpuReading {
+        utilization: utilization / count as f32,
+        memory_used_mb,
+    })
+}
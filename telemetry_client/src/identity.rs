@@ -0,0 +1,66 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+};
+use sysinfo::System;
+use uuid::Uuid;
+
+/// Variável de ambiente com o token pré-compartilhado (mesmo nome no servidor)
+const AUTH_TOKEN_ENV: &str = "TELEMETRY_AUTH_TOKEN";
+/// Variável de ambiente que sobrescreve o arquivo do identificador
+const CLIENT_ID_FILE_ENV: &str = "TELEMETRY_CLIENT_ID_FILE";
+const CLIENT_ID_FILE_NAME: &str = ".telemetry_client_id";
+
+/// Identidade enviada ao servidor no handshake
+#[derive(Debug, Clone)]
+pub struct ClientIdentity {
+    pub client_id: String,
+    pub hostname: String,
+    /// Namespace das métricas; sem ele o servidor usa o hostname
+    pub namespace: Option<String>,
+    /// Token enviado no handshake quando `TELEMETRY_AUTH_TOKEN` está definido
+    pub auth_token: Option<String>,
+}
+
+impl ClientIdentity {
+    /// Carrega o UUID persistido ou gera um novo na primeira execução
+    pub fn load(namespace: Option<String>) -> Self {
+        Self {
+            client_id: load_or_create_client_id(),
+            hostname: System::host_name().unwrap_or_else(|| "desconhecido".to_string()),
+            namespace,
+            auth_token: env::var(AUTH_TOKEN_ENV).ok().filter(|token| !token.is_empty()),
+        }
+    }
+}
+
+/// `$TELEMETRY_CLIENT_ID_FILE`, ou `~/.telemetry_client_id`, ou o diretório atual
+fn client_id_path() -> PathBuf {
+    if let Ok(path) = env::var(CLIENT_ID_FILE_ENV) {
+        return PathBuf::from(path);
+    }
+
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(CLIENT_ID_FILE_NAME)
+}
+
+fn load_or_create_client_id() -> String {
+    let path = client_id_path();
+
+    if let Ok(content) = fs::read_to_string(&path) {
+        if let Ok(id) = Uuid::parse_str(content.trim()) {
+            return id.to_string();
+        }
+        eprintln!("⚠️ Identificador inválido em {}, gerando outro", path.display());
+    }
+
+    let id = Uuid::new_v4().to_string();
+    if let Err(e) = fs::write(&path, &id) {
+        // Sem persistência o ID vale só para esta execução
+        eprintln!("⚠️ Não foi possível salvar o identificador em {}: {}", path.display(), e);
+    }
+    id
+}
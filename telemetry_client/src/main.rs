@@ -1,184 +1,925 @@
+mod adaptive;
+mod backoff;
+mod batch;
+mod battery;
+mod cgroup;
+mod collector;
+mod compression;
+mod config;
+mod destination;
+mod discovery;
+mod gpu;
+mod identity;
+mod metric;
+mod numa;
+mod offline;
+mod priority;
+mod queue;
+mod refresh;
+mod sampler;
+mod smoothing;
+mod tcp;
+mod transport;
+
 use std::{
-    io::{Result as IoResult, Write},
-    net::TcpStream,
+    io::{self, Read, Result as IoResult},
+    process::ExitCode,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
     thread,
-    time::Duration,
-    fmt,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use telemetry_common::{
+    commands::{Ping, ServerCommand, ServerMessage},
+    framing::{self, Framing},
+    protocol,
 };
-use sysinfo::System;
+use backoff::Backoff;
+use batch::SampleBatch;
+use compression::CompressionStats;
+use config::{ClientConfig, ServerAddress, MIN_INTERVAL_MS};
+use destination::Destination;
+use identity::ClientIdentity;
+use metric::HardwareMetric;
+use offline::OfflineBuffer;
+use queue::SampleReceiver;
+use sampler::{CollectorControl, SampleCollector};
+use transport::{TlsSettings, Transport};
 
-// LocalHost IP for Tests
-//const SERVER_ADDRESS: &str = "127.0.0.1:8080";
-const SERVER_ADDRESS: &str = "<Server IP Here>:8080";
-const TELEMETRY_INTERVAL_MS: u64 = 1000;
 const JSON_BUFFER_CAPACITY: usize = 256;
 
+/// Diferença para o relógio do servidor que gera aviso sugerindo NTP
+const CLOCK_WARNING_MS: i64 = 2 * 60 * 1_000;
+
+/// Códigos de saída, para scripts distinguirem as falhas
+const EXIT_FAILURE: u8 = 1;
+const EXIT_CONNECTION: u8 = 3;
+const EXIT_CONFIG: u8 = 4;
+
 type TelemetryResult<T> = IoResult<T>;
 
-/// Tipos de hardware monitorados
-#[derive(Debug, Clone, Copy)]
-enum HardwareMetric {
-    Cpu,
-    Memory,
+/// Maior silêncio entre mensagens; abaixo do `read_timeout_secs` padrão do servidor (10s)
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Intervalo entre pings de medição de RTT a cada servidor compatível
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Maior espera por comandos do servidor enquanto nenhuma amostra chega da coleta
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Epoch atual em milissegundos, usado para marcar o momento da coleta
+fn epoch_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
 }
 
-impl HardwareMetric {
-    fn as_str(&self) -> &'static str {
-        match self {
-            HardwareMetric::Cpu => "CPU",
-            HardwareMetric::Memory => "MEM",
+/// Abre o transporte escolhido: UDP ou TCP (com TLS opcional e timeout de conexão)
+fn open_transport(address: &str, tls: Option<&TlsSettings>, udp: bool, timeout: Duration) -> TelemetryResult<Transport> {
+    if udp {
+        transport::connect_udp(address)
+    } else {
+        transport::connect(address, tls, timeout)
+    }
+}
+
+fn main() -> ExitCode {
+    let mut config = match ClientConfig::from_args() {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("❌ {}", message);
+            return ExitCode::from(EXIT_CONFIG);
         }
+    };
+    if let Some(nice) = config.nice {
+        match priority::lower_priority(nice) {
+            Ok(()) => println!("🐢 Prioridade reduzida (nice {}): a coleta cede a CPU aos demais processos", nice),
+            Err(e) => eprintln!("⚠️ Não foi possível reduzir a prioridade ({}); seguindo na prioridade normal", e),
+        }
+    }
+    if config.discover {
+        discover_server(&mut config);
     }
 
-    fn all() -> &'static [HardwareMetric] {
-        &[HardwareMetric::Cpu, HardwareMetric::Memory]
+    let result = TelemetryClient::new(&config).and_then(|(client, sampler)| {
+        if config.once {
+            client.run_once(sampler)
+        } else {
+            client.run(sampler)
+        }
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let code = exit_code(&e);
+            match code {
+                EXIT_CONFIG => eprintln!("❌ Configuração inválida: {}", e),
+                EXIT_CONNECTION => eprintln!("❌ Sem conexão com o servidor: {}", e),
+                _ => eprintln!("❌ Cliente encerrado com erro: {}", e),
+            }
+            ExitCode::from(code)
+        }
     }
 }
 
-impl fmt::Display for HardwareMetric {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.as_str())
+/// Código de saída conforme o tipo do erro que encerrou o cliente
+fn exit_code(error: &io::Error) -> u8 {
+    match error.kind() {
+        io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData | io::ErrorKind::Unsupported => EXIT_CONFIG,
+        io::ErrorKind::ConnectionRefused
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::NotConnected
+        | io::ErrorKind::TimedOut
+        | io::ErrorKind::BrokenPipe
+        | io::ErrorKind::UnexpectedEof
+        | io::ErrorKind::AddrNotAvailable
+        | io::ErrorKind::HostUnreachable
+        | io::ErrorKind::NetworkUnreachable
+        // Socket Unix inexistente ou sem permissão
+        | io::ErrorKind::NotFound
+        | io::ErrorKind::PermissionDenied => EXIT_CONNECTION,
+        _ => EXIT_FAILURE,
     }
 }
 
-fn main() -> TelemetryResult<()> {
-    let client = TelemetryClient::new(SERVER_ADDRESS)?;
-    client.run()
+/// Troca o endereço padrão pelo primeiro servidor que responder ao broadcast
+fn discover_server(config: &mut ClientConfig) {
+    println!("🔍 Procurando servidor na rede local...");
+    match discovery::discover(config.discovery_timeout, config.udp) {
+        Ok(Some((address, reply))) => {
+            println!("📡 Servidor encontrado em {}", address);
+            if reply.tls && config.tls.is_none() {
+                eprintln!("⚠️ O servidor {} exige TLS; use --tls-ca ou --tls-pin", address);
+            }
+            config.addresses = vec![ServerAddress::Inet(address)];
+        }
+        Ok(None) => println!("📡 Nenhum servidor respondeu em {}ms; usando {}", config.discovery_timeout.as_millis(), config.addresses[0]),
+        Err(e) => eprintln!("⚠️ Falha na descoberta ({}); usando {}", e, config.addresses[0]),
+    }
 }
 
+/// Thread de envio: conexões, framing, batch e buffer offline. As amostras chegam
+/// da thread de coleta (`SampleCollector`) por um canal
 struct TelemetryClient {
-    connection: TcpStream,
-    system: System,
-    address: String,
+    /// Servidores que recebem cada amostra (fan-out)
+    destinations: Vec<Destination>,
+    /// Espera máxima por uma conexão nova, na inicial e nas reconexões
+    connect_timeout: Duration,
+    framing: Framing,
+    identity: ClientIdentity,
+    backoff: Backoff,
+    /// Amostras coletadas sem nenhum servidor conectado, à espera da reconexão
+    offline: OfflineBuffer,
+    /// Amostras que podem esperar na fila de envio; além disso, as mais antigas são descartadas
+    send_queue: usize,
+    udp: bool,
+    /// Estatísticas da compressão gzip, presente quando ela está habilitada
+    compression: Option<CompressionStats>,
+    messages: MessageCounters,
+    /// Amostras pendentes para os destinos que aceitaram batch
+    batch: SampleBatch,
+    /// Próximo nonce de ping, único entre todos os destinos
+    next_nonce: u64,
+}
+
+/// Ponta da thread de envio nos canais da thread de coleta
+struct CollectorLink {
+    samples: SampleReceiver,
+    control: Sender<CollectorControl>,
+}
+
+impl CollectorLink {
+    /// Repassa um pedido à coleta; se ela já parou, `run` encerra logo em seguida
+    fn request(&self, control: CollectorControl) {
+        let _ = self.control.send(control);
+    }
+
+    /// Avisa das amostras que a fila descartou enquanto o envio estava atrasado
+    fn report_dropped(&self) {
+        let dropped = self.samples.take_dropped();
+        if dropped > 0 {
+            eprintln!(
+                "⚠️ Envio atrasado: fila de envio cheia (limite {}); {} amostras mais antigas descartadas",
+                self.samples.capacity(), dropped
+            );
+        }
+    }
+}
+
+/// Amostras enviadas desde o início e desde a última reconexão
+#[derive(Debug, Default)]
+struct MessageCounters {
+    total: u64,
+    since_reconnect: u64,
+}
+
+impl MessageCounters {
+    fn record(&mut self) {
+        self.total += 1;
+        self.since_reconnect += 1;
+    }
+
+    /// O total sobrevive às reconexões; só o contador da conexão recomeça
+    fn reset_connection(&mut self) {
+        self.since_reconnect = 0;
+    }
 }
 
 impl TelemetryClient {
-    fn new(address: &str) -> TelemetryResult<Self> {
-        println!("🔌 Conectando ao servidor {}...", address);
-        
-        let connection = TcpStream::connect(address)?;
-        let system = System::new_all();
-        
-        println!("✅ Conectado ao servidor com sucesso!");
+    /// Conecta aos destinos e prepara a coleta, devolvida à parte para rodar na própria thread
+    fn new(config: &ClientConfig) -> TelemetryResult<(Self, SampleCollector)> {
+        let mut destinations = Vec::with_capacity(config.addresses.len());
+        for address in &config.addresses {
+            let tls = match &config.tls {
+                Some(options) => {
+                    let server_name = match (&options.server_name, address) {
+                        (Some(name), _) => name.clone(),
+                        (None, ServerAddress::Inet(address)) => address.ip().to_string(),
+                        (None, ServerAddress::Unix(_)) => "localhost".to_string(),
+                    };
+                    let settings = TlsSettings::new(options.ca_path.as_deref(), options.pin.as_deref(), &server_name)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("TLS: {}", e)))?;
+                    Some(settings)
+                }
+                None => None,
+            };
+            destinations.push(Destination::new(address.to_string(), tls));
+        }
+
+        let identity = ClientIdentity::load(config.namespace.clone());
+        let needs_cpu_warm_up = config.metrics.contains(&HardwareMetric::Cpu) || config.top_process;
+        let (collectors, metrics) = collector::from_config(config);
+        let sampler = SampleCollector::new(config, collectors, metrics);
+        // Depois do refresh inicial dos `System` de CPU e processos: o `sysinfo`
+        // conta o intervalo mínimo a partir dele
+        let cpu_warm_up = Instant::now();
+
+        let mut client = Self {
+            destinations,
+            connect_timeout: config.connect_timeout,
+            framing: config.framing,
+            identity,
+            backoff: Backoff { max_attempts: config.max_retries },
+            offline: OfflineBuffer::new(config.offline_buffer),
+            send_queue: config.send_queue,
+            udp: config.udp,
+            compression: config.compress.then(CompressionStats::default),
+            messages: MessageCounters::default(),
+            batch: SampleBatch::new(config.batch_size, config.batch_max_age),
+            next_nonce: 1,
+        };
+
+        client.connect_all(config)?;
+        // Sem isso a primeira amostra chegaria ao servidor com CPU falsamente em 0%
+        if needs_cpu_warm_up {
+            refresh::wait_cpu_warm_up(cpu_warm_up);
+        }
+        println!("🪪 Cliente {} ({})", client.identity.client_id, client.identity.hostname);
         println!("📊 Iniciando coleta de telemetria...");
         println!("{}", "=".repeat(50));
-        
-        Ok(Self {
-            connection,
-            system,
-            address: address.to_string(),
-        })
+        Ok((client, sampler))
     }
 
-    fn run(mut self) -> TelemetryResult<()> {
-        let mut message_count = 0u64;
-        let mut json_buffer = String::with_capacity(JSON_BUFFER_CAPACITY);
-        
+    /// Conexão inicial com todos os destinos. Basta um responder; os demais
+    /// entram na agenda de reconexão
+    fn connect_all(&mut self, config: &ClientConfig) -> TelemetryResult<()> {
+        let mode = match (&config.tls, config.udp) {
+            (Some(_), _) => " 🔒 TLS",
+            (None, true) => " 📦 UDP",
+            (None, false) => "",
+        };
+
+        let mut last_error = None;
+        for index in 0..self.destinations.len() {
+            println!("🔌 Conectando ao servidor {}...", self.destinations[index].address);
+            match self.connect_destination(index) {
+                Ok(()) => println!("✅ Conectado ao servidor {} com sucesso!{}", self.destinations[index].address, mode),
+                Err(e) => {
+                    eprintln!("❌ Falha ao conectar em {}: {}", self.destinations[index].address, e);
+                    self.destinations[index].mark_down(&self.backoff);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !self.is_connected() => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        self.destinations.iter().any(Destination::is_connected)
+    }
+
+    /// Sobe a thread de coleta e envia o que ela produz; só volta quando as
+    /// reconexões se esgotam
+    fn run(mut self, sampler: SampleCollector) -> TelemetryResult<()> {
+        let (sample_sender, samples) = queue::bounded(self.send_queue);
+        let (control, control_receiver) = mpsc::channel();
+        let collecting = thread::Builder::new()
+            .name("coleta".to_string())
+            .spawn(move || sampler.run(sample_sender, control_receiver))?;
+        let link = CollectorLink { samples, control };
+
         loop {
-            match self.collect_and_send_telemetry(&mut json_buffer) {
-                Ok(_) => {
-                    message_count += 1;
-                    if message_count % 10 == 0 {
-                        println!("📤 {} mensagens enviadas", message_count);
+            // Ela só encerra sozinha num pânico; sem coleta não há o que enviar
+            if collecting.is_finished() {
+                return Err(io::Error::other("a thread de coleta encerrou inesperadamente"));
+            }
+            match self.wait_samples(&link) {
+                Ok(queued) => {
+                    if let Err(e) = self.send_queued(queued) {
+                        self.recover(e, &link)?;
                     }
                 }
-                Err(e) => {
-                    eprintln!("❌ Erro ao enviar telemetria: {}", e);
-                    eprintln!("🔄 Tentando reconectar...");
-                    
-                    if self.try_reconnect().is_err() {
-                        eprintln!("💥 Falha na reconexão. Encerrando cliente.");
-                        return Err(e);
-                    }
-                    
-                    message_count = 0;
+                Err(e) => self.recover(e, &link)?,
+            }
+        }
+    }
+
+    /// `--once`: coleta e envia uma única amostra aos destinos conectados e encerra,
+    /// sem loop, thread de coleta nem reconexão
+    fn run_once(mut self, mut sampler: SampleCollector) -> TelemetryResult<()> {
+        let mut json_buffer = String::with_capacity(JSON_BUFFER_CAPACITY);
+        sampler.build_telemetry_json(&mut json_buffer)?;
+        let sample = json_buffer.clone();
+        self.send_message(&mut json_buffer)?;
+
+        let sent_to = self.destinations.iter().filter(|destination| destination.is_connected()).count();
+        println!("📤 Amostra enviada a {} servidor(es): {}", sent_to, sample);
+        Ok(())
+    }
+
+    /// Reconecta após uma falha de envio; esgotadas as tentativas, devolve o erro original
+    fn recover(&mut self, e: io::Error, link: &CollectorLink) -> TelemetryResult<()> {
+        eprintln!("❌ Erro ao enviar telemetria: {}", e);
+        eprintln!("🔄 Tentando reconectar...");
+
+        if self.try_reconnect(link).is_err() {
+            eprintln!("💥 Tentativas de reconexão esgotadas. Encerrando cliente.");
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Espera a próxima amostra da coleta escutando comandos do servidor e mandando
+    /// heartbeats, para o servidor não tomar o cliente por morto em intervalos longos.
+    /// Devolve, na ordem de coleta, tudo o que chegou até aqui
+    fn wait_samples(&mut self, link: &CollectorLink) -> TelemetryResult<Vec<String>> {
+        let mut next_heartbeat = Instant::now() + HEARTBEAT_INTERVAL;
+
+        loop {
+            let now = Instant::now();
+            if now >= next_heartbeat {
+                self.send_heartbeat()?;
+                next_heartbeat = now + HEARTBEAT_INTERVAL;
+            }
+
+            match link.samples.recv_timeout(COMMAND_POLL_INTERVAL.min(next_heartbeat - now)) {
+                Ok(sample) => {
+                    let mut queued = vec![sample];
+                    queued.extend(link.samples.drain());
+                    link.report_dropped();
+                    return Ok(queued);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                // A coleta parou; `run` percebe e encerra
+                Err(RecvTimeoutError::Disconnected) => return Ok(Vec::new()),
+            }
+
+            for command in self.poll_commands(Duration::ZERO)? {
+                self.handle_command(command, link);
+            }
+        }
+    }
+
+    /// Aguarda até `wait` por dados do servidor. No UDP não há canal de volta: só dorme
+    fn poll_commands(&mut self, wait: Duration) -> TelemetryResult<Vec<ServerCommand>> {
+        if self.udp {
+            thread::sleep(wait);
+            return Ok(Vec::new());
+        }
+
+        // A espera é dividida entre os destinos conectados
+        let connected = self.destinations.iter().filter(|destination| destination.is_connected()).count();
+        let wait = (wait / connected.max(1) as u32).max(Duration::from_millis(1));
+
+        let mut commands = Vec::new();
+        let mut last_error = None;
+        let mut buffer = [0u8; 512];
+        for index in 0..self.destinations.len() {
+            let destination = &mut self.destinations[index];
+            let Some(connection) = destination.connection() else {
+                continue;
+            };
+
+            let received = connection.set_read_timeout(Some(wait)).and_then(|()| connection.read(&mut buffer));
+            match received {
+                Ok(0) => last_error = Some(io::Error::new(io::ErrorKind::UnexpectedEof, "servidor encerrou a conexão")),
+                Ok(n) => {
+                    commands.extend(take_commands(destination, &buffer[..n]));
+                    continue;
+                }
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted) => {
+                    continue;
                 }
+                Err(e) => last_error = Some(e),
+            }
+
+            if let Some(e) = &last_error {
+                self.drop_destination(index, e);
             }
-            
-            thread::sleep(Duration::from_millis(TELEMETRY_INTERVAL_MS));
+        }
+
+        match last_error {
+            Some(e) if !self.is_connected() => Err(e),
+            _ => Ok(commands),
         }
     }
 
-    fn collect_and_send_telemetry(&mut self, json_buffer: &mut String) -> TelemetryResult<()> {
-        // Limpa buffer para reutilização
-        json_buffer.clear();
-        
-        // Coleta métricas
-        self.build_telemetry_json(json_buffer);
-        
-        // Adiciona delimitador newline para o servidor
-        json_buffer.push('\n');
-        
-        // Envia dados
-        self.send_data(json_buffer.as_bytes())
+    /// Os comandos que mudam a coleta seguem para a thread dela
+    fn handle_command(&self, command: ServerCommand, link: &CollectorLink) {
+        match command {
+            ServerCommand::CollectNow => {
+                println!("📥 Coleta imediata solicitada pelo servidor");
+                link.request(CollectorControl::CollectNow);
+            }
+            ServerCommand::SetInterval { interval_ms } if interval_ms < MIN_INTERVAL_MS => {
+                eprintln!(
+                    "⚠️ Intervalo de {}ms pedido pelo servidor abaixo do mínimo de {}ms (ignorado)",
+                    interval_ms, MIN_INTERVAL_MS
+                );
+            }
+            ServerCommand::SetInterval { interval_ms } => {
+                println!("📥 Intervalo de coleta alterado pelo servidor para {}ms", interval_ms);
+                link.request(CollectorControl::SetInterval(Duration::from_millis(interval_ms)));
+            }
+            ServerCommand::SetGpu { enabled } => {
+                println!("📥 Coleta de GPU {} pelo servidor", if enabled { "ligada" } else { "desligada" });
+                link.request(CollectorControl::SetGpu(enabled));
+            }
+            // O servidor já corrige os timestamps; aqui só fica o aviso
+            ServerCommand::Clock { offset_ms, .. } if offset_ms.abs() >= CLOCK_WARNING_MS => {
+                eprintln!(
+                    "⚠️ Relógio deste host difere do servidor em {:+.1}s; sincronize com NTP",
+                    offset_ms as f64 / 1000.0
+                );
+            }
+            ServerCommand::Clock { .. } => {}
+            // Valem só para o destino que respondeu e já são tratados em `take_commands`
+            ServerCommand::AcceptBatch { .. } | ServerCommand::Pong { .. } => {}
+            ServerCommand::Unknown(name) => eprintln!("⚠️ Comando desconhecido do servidor: {}", name),
+        }
     }
 
-    fn build_telemetry_json(&mut self, buffer: &mut String) {
-        buffer.push('{');
-        
-        let metrics = HardwareMetric::all();
-        for (idx, metric) in metrics.iter().enumerate() {
-            let value = self.collect_metric(metric);
-            
-            // Formato: "METRIC": value
-            buffer.push('"');
-            buffer.push_str(metric.as_str());
-            buffer.push_str("\": ");
-            buffer.push_str(&value.to_string());
-            
-            // Adiciona vírgula se não for o último
-            if idx < metrics.len() - 1 {
-                buffer.push_str(", ");
+    /// Mensagem vazia, ignorada pelo servidor além de renovar o timeout de leitura
+    fn send_heartbeat(&mut self) -> TelemetryResult<()> {
+        let mut message = String::new();
+        self.send_message(&mut message)
+    }
+
+    /// Envia, na ordem de coleta, as amostras que esperavam na fila (no máximo
+    /// `--send-queue`, já que a fila descarta as mais antigas).
+    /// Numa falha, o que não foi enviado espera a reconexão no buffer offline
+    fn send_queued(&mut self, queued: Vec<String>) -> TelemetryResult<()> {
+        self.retry_due();
+
+        let mut queued = queued.into_iter();
+        while let Some(sample) = queued.next() {
+            if let Err(e) = self.send_sample(&sample) {
+                for sample in queued {
+                    self.buffer_offline(&sample);
+                }
+                return Err(e);
+            }
+
+            self.messages.record();
+            if self.messages.since_reconnect.is_multiple_of(10) {
+                println!(
+                    "📤 {} mensagens enviadas ({} desde a última reconexão)",
+                    self.messages.total, self.messages.since_reconnect
+                );
             }
         }
-        
-        buffer.push('}');
+        self.send_pings()
     }
 
-    fn collect_metric(&mut self, metric: &HardwareMetric) -> f32 {
-        match metric {
-            HardwareMetric::Cpu => self.get_cpu_usage(),
-            HardwareMetric::Memory => self.get_memory_usage(),
+    /// Conecta um destino e envia o handshake, que identifica o cliente para o servidor.
+    /// Com compressão, o handshake é precedido pelo byte de modo que a anuncia
+    fn connect_destination(&mut self, index: usize) -> TelemetryResult<()> {
+        let mut data = Vec::new();
+        if self.compression.is_some() {
+            data.push(compression::MODE_GZIP);
         }
+
+        let mut handshake = serde_json::json!({
+            "client_id": self.identity.client_id,
+            "hostname": self.identity.hostname,
+            "clock": epoch_millis(),
+            "protocol_version": protocol::PROTOCOL_VERSION,
+        });
+        if let Some(namespace) = &self.identity.namespace {
+            handshake["namespace"] = serde_json::Value::from(namespace.as_str());
+        }
+        if let Some(token) = &self.identity.auth_token {
+            handshake["auth_token"] = serde_json::Value::from(token.as_str());
+        }
+        if self.batch.is_enabled() {
+            handshake["batch"] = serde_json::Value::from(true);
+        }
+        // O primeiro ping vai no handshake: só servidores que respondem recebem os seguintes
+        let nonce = (!self.udp).then(|| self.take_nonce());
+        if let Some(nonce) = nonce {
+            handshake["ping"] = serde_json::Value::from(nonce);
+        }
+
+        let mut message = serde_json::json!({ "handshake": handshake }).to_string();
+        data.extend(self.encode_message(&mut message)?);
+
+        let destination = &mut self.destinations[index];
+        destination.open(self.udp, self.connect_timeout)?;
+        destination.write(&data)?;
+        if let Some(nonce) = nonce {
+            destination.ping_sent(nonce);
+        }
+        Ok(())
+    }
+
+    fn take_nonce(&mut self) -> u64 {
+        let nonce = self.next_nonce;
+        self.next_nonce += 1;
+        nonce
     }
 
-    fn get_cpu_usage(&mut self) -> f32 {
-        self.system.refresh_cpu_usage();
-        
-        // Calcula média de todas as CPUs ao invés de apenas a primeira
-        let cpus = self.system.cpus();
-        if cpus.is_empty() {
-            return 0.0;
+    /// Pinga os servidores compatíveis cuja vez chegou; o RTT anterior vai junto
+    /// para o servidor exibir como `RTT_MS`
+    fn send_pings(&mut self) -> TelemetryResult<()> {
+        for index in 0..self.destinations.len() {
+            if !self.destinations[index].ping_due(PING_INTERVAL) {
+                continue;
+            }
+
+            let nonce = self.take_nonce();
+            let rtt_ms = self.destinations[index].rtt.map(|rtt| rtt.as_secs_f32() * 1000.0);
+            let mut message = Ping { ping: nonce, rtt_ms }.to_json();
+            let data = self.encode_message(&mut message)?;
+            match self.destinations[index].write(&data) {
+                Ok(()) => self.destinations[index].ping_sent(nonce),
+                Err(e) => self.drop_destination(index, &e),
+            }
         }
-        
-        let total: f32 = cpus.iter().map(|cpu| cpu.cpu_usage()).sum();
-        total / cpus.len() as f32
+        Ok(())
     }
 
-    fn get_memory_usage(&mut self) -> f32 {
-        self.system.refresh_memory();
-        self.system.used_memory() as f32
+    /// Envia uma amostra: sozinha aos destinos sem batch, e acumulada para os que
+    /// aceitaram, que recebem o array quando o batch enche, expira ou passaria do
+    /// menor `max_bytes` anunciado. Numa falha, a amostra e o batch pendente vão
+    /// para o buffer offline, exceto o que algum destino já recebeu
+    fn send_sample(&mut self, sample: &str) -> TelemetryResult<()> {
+        if !self.destinations.iter().any(|destination| destination.batch) {
+            let mut message = sample.to_string();
+            return self.send_message(&mut message).inspect_err(|_| self.buffer_offline(sample));
+        }
+        if !self.is_connected() {
+            self.buffer_batch_offline();
+            self.buffer_offline(sample);
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "nenhum servidor conectado"));
+        }
+
+        // O batch vem antes dos destinos sem batch: se o envio dele falhar, a amostra
+        // ainda não chegou a ninguém e pode ir inteira para o buffer offline
+        let limit = self.destinations.iter()
+            .filter(|destination| destination.is_connected() && destination.batch)
+            .filter_map(|destination| destination.batch_limit)
+            .min();
+        if self.batch.would_exceed(sample, limit) {
+            self.flush_batch().inspect_err(|_| self.buffer_offline(sample))?;
+        }
+        if self.batch.push(sample) {
+            self.flush_batch()?;
+        }
+
+        if self.destinations.iter().any(|destination| destination.is_connected() && !destination.batch) {
+            let mut message = sample.to_string();
+            let result = self.encode_message(&mut message)
+                .and_then(|data| self.send_data_where(&data, |destination| !destination.batch));
+            result.inspect_err(|_| self.buffer_batch_offline())?;
+        }
+        Ok(())
     }
 
-    fn send_data(&mut self, data: &[u8]) -> TelemetryResult<()> {
-        self.connection.write_all(data)?;
-        self.connection.flush() // Garante que dados são enviados imediatamente
-    }
-
-    fn try_reconnect(&mut self) -> TelemetryResult<()> {
-        // Aguarda antes de tentar reconectar
-        thread::sleep(Duration::from_secs(2));
-        
-        match TcpStream::connect(&self.address) {
-            Ok(new_connection) => {
-                self.connection = new_connection;
-                println!("✅ Reconexão estabelecida!");
+    /// Envia o array pendente aos destinos que aceitaram batch. O batch só é
+    /// esvaziado depois da escrita; numa falha, as amostras vão para o buffer offline
+    fn flush_batch(&mut self) -> TelemetryResult<()> {
+        let mut message = self.batch.message();
+        let result = self.encode_message(&mut message)
+            .and_then(|data| self.send_data_where(&data, |destination| destination.batch));
+        match result {
+            Ok(()) => {
+                self.batch.take();
                 Ok(())
             }
             Err(e) => {
-                eprintln!("❌ Falha na reconexão: {}", e);
+                self.buffer_batch_offline();
                 Err(e)
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Passa as amostras do batch pendente ao buffer offline, na ordem de coleta
+    fn buffer_batch_offline(&mut self) {
+        for sample in self.batch.take() {
+            self.buffer_offline(&sample);
+        }
+    }
+
+    /// Envia uma mensagem a todos os destinos conectados
+    fn send_message(&mut self, message: &mut String) -> TelemetryResult<()> {
+        let data = self.encode_message(message)?;
+        self.send_data(&data)
+    }
+
+    /// Aplica a delimitação combinada com o servidor; a compressão é feita uma vez
+    /// para todos os destinos
+    fn encode_message(&mut self, message: &mut String) -> TelemetryResult<Vec<u8>> {
+        match self.framing {
+            Framing::Line => {
+                // Adiciona delimitador newline para o servidor
+                message.push('\n');
+                Ok(message.as_bytes().to_vec())
+            }
+            Framing::Length => match &mut self.compression {
+                Some(stats) => {
+                    let compressed = compression::compress(message.as_bytes())?;
+                    stats.record(message.len(), compressed.len());
+                    framing::encode_frame(&compressed)
+                }
+                None => framing::encode_frame(message.as_bytes()),
+            },
+        }
+    }
+
+    /// Escreve em todos os destinos conectados
+    fn send_data(&mut self, data: &[u8]) -> TelemetryResult<()> {
+        self.send_data_where(data, |_| true)
+    }
+
+    /// Escreve nos destinos conectados escolhidos por `filter`. Um destino que falha
+    /// sai da lista até reconectar; só é erro quando nenhum destino continua conectado
+    fn send_data_where(&mut self, data: &[u8], filter: impl Fn(&Destination) -> bool) -> TelemetryResult<()> {
+        let mut last_error = None;
+        for index in 0..self.destinations.len() {
+            if !self.destinations[index].is_connected() || !filter(&self.destinations[index]) {
+                continue;
+            }
+            if let Err(e) = self.destinations[index].write(data) {
+                self.drop_destination(index, &e);
+                last_error = Some(e);
+            }
+        }
+
+        match last_error {
+            Some(e) if !self.is_connected() => Err(e),
+            _ if !self.is_connected() => {
+                Err(io::Error::new(io::ErrorKind::NotConnected, "nenhum servidor conectado"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Tira um destino que falhou da lista de envio e agenda sua reconexão.
+    /// Com outros destinos ativos, a falha só é avisada; sem nenhum, `recover` assume
+    fn drop_destination(&mut self, index: usize, e: &io::Error) {
+        self.destinations[index].mark_down(&self.backoff);
+        if self.is_connected() {
+            eprintln!(
+                "⚠️ Servidor {} caiu ({}); seguindo com os demais",
+                self.destinations[index].address, e
+            );
+        }
+    }
+
+    /// Tenta reconectar, sem esperar, os destinos caídos cuja vez já chegou
+    fn retry_due(&mut self) {
+        let now = Instant::now();
+        for index in 0..self.destinations.len() {
+            if self.destinations[index].is_due(&self.backoff, now) {
+                self.reconnect(index);
+            }
+        }
+    }
+
+    /// Sem nenhum destino conectado: espera a próxima tentativa agendada até que
+    /// algum volte ou todos esgotem as tentativas. A coleta segue durante a espera,
+    /// para o buffer offline, e o que foi acumulado é reenviado na reconexão
+    fn try_reconnect(&mut self, link: &CollectorLink) -> TelemetryResult<()> {
+        loop {
+            let next = self.destinations.iter()
+                .enumerate()
+                .filter(|(_, destination)| !destination.is_abandoned(&self.backoff))
+                .min_by_key(|(_, destination)| destination.retry_at)
+                .map(|(index, _)| index);
+            let Some(index) = next else {
+                return Err(io::Error::new(io::ErrorKind::NotConnected, "tentativas de reconexão esgotadas"));
+            };
+
+            let destination = &self.destinations[index];
+            let attempt = destination.attempts + 1;
+            let remaining = match self.backoff.max_attempts {
+                0 => "∞".to_string(),
+                max => (max - attempt).to_string(),
+            };
+            let delay = destination.retry_in();
+            println!(
+                "⏳ Tentativa {} para {} em {:.1}s (restantes depois desta: {}, tentando há {:.0}s)",
+                attempt,
+                destination.address,
+                delay.as_secs_f32(),
+                remaining,
+                destination.down_since.elapsed().as_secs_f32()
+            );
+            self.buffer_during(delay, link);
+
+            if self.reconnect(index) {
+                match self.flush_offline() {
+                    Ok(()) => return Ok(()),
+                    // Caiu de novo no meio do reenvio: o que faltou continua no buffer
+                    Err(e) => eprintln!("❌ Falha ao reenviar as amostras da queda: {}", e),
+                }
+            }
+        }
+    }
+
+    /// Espera `delay` guardando no buffer offline as amostras que a coleta segue
+    /// produzindo. Sem buffer, elas são descartadas
+    fn buffer_during(&mut self, delay: Duration, link: &CollectorLink) {
+        let deadline = Instant::now() + delay;
+        loop {
+            match link.samples.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(sample) if self.offline.is_enabled() => self.buffer_offline(&sample),
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => return,
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn buffer_offline(&mut self, sample: &str) {
+        if self.offline.push(sample) {
+            eprintln!(
+                "⚠️ Buffer offline cheio ({} amostras): descartando as mais antigas até a reconexão",
+                self.offline.capacity()
+            );
+        }
+    }
+
+    /// Reenvia, na ordem de coleta e com os timestamps originais, as amostras
+    /// acumuladas durante a queda; o servidor as encaixa no histórico pelo timestamp
+    fn flush_offline(&mut self) -> TelemetryResult<()> {
+        if self.offline.is_empty() {
+            return Ok(());
+        }
+        let pending = self.offline.len();
+
+        while let Some(sample) = self.offline.front() {
+            let mut message = sample.to_string();
+            self.send_message(&mut message)?;
+            self.offline.pop_front();
+        }
+        match self.offline.take_dropped() {
+            0 => println!("📤 {} amostra(s) coletada(s) durante a queda reenviada(s)", pending),
+            dropped => println!(
+                "📤 {} amostra(s) coletada(s) durante a queda reenviada(s); {} mais antiga(s) descartada(s) por falta de espaço",
+                pending, dropped
+            ),
+        }
+        Ok(())
+    }
+
+    /// Uma tentativa de reconexão de um destino; `true` se ele voltou
+    fn reconnect(&mut self, index: usize) -> bool {
+        match self.connect_destination(index) {
+            Ok(()) => {
+                let destination = &self.destinations[index];
+                println!(
+                    "✅ Reconexão com {} estabelecida após {} tentativa(s) em {:.0}s! ({} mensagens enviadas até aqui)",
+                    destination.address,
+                    destination.attempts + 1,
+                    destination.down_since.elapsed().as_secs_f32(),
+                    self.messages.total
+                );
+                self.destinations[index].attempts = 0;
+                self.messages.reset_connection();
+                true
+            }
+            Err(e) => {
+                self.destinations[index].schedule_retry(&self.backoff);
+                let destination = &self.destinations[index];
+                eprintln!("❌ Falha na reconexão com {}: {}", destination.address, e);
+                if destination.is_abandoned(&self.backoff) && self.destinations.len() > 1 {
+                    eprintln!("💥 Tentativas esgotadas para {}; destino abandonado", destination.address);
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Separa os comandos do que chegou de um destino; recusas e mensagens inválidas só são exibidas
+fn take_commands(destination: &mut Destination, data: &[u8]) -> Vec<ServerCommand> {
+    destination.inbox
+        .push(data)
+        .into_iter()
+        .filter_map(|message| match message {
+            ServerMessage::Command(ServerCommand::Pong { nonce }) => {
+                if destination.pong(nonce) {
+                    println!("📶 Servidor {} responde a ping; RTT medido a cada {}s", destination.address, PING_INTERVAL.as_secs());
+                }
+                None
+            }
+            ServerMessage::Command(ServerCommand::AcceptBatch { max_bytes }) => {
+                println!("📦 Servidor {} aceitou amostras em batch", destination.address);
+                destination.batch = true;
+                destination.batch_limit = max_bytes;
+                None
+            }
+            ServerMessage::Command(command) => Some(command),
+            ServerMessage::Rejected(error) => {
+                eprintln!("❌ Servidor {} recusou: {}", destination.address, error);
+                None
+            }
+            ServerMessage::Invalid(description) => {
+                eprintln!("⚠️ {}: {}", destination.address, description);
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn total_survives_reconnect_and_connection_count_restarts() {
+        let mut messages = MessageCounters::default();
+        for _ in 0..3 {
+            messages.record();
+        }
+        messages.reset_connection();
+        messages.record();
+
+        assert_eq!(messages.total, 4);
+        assert_eq!(messages.since_reconnect, 1);
+    }
+
+    /// A conexão cai com o batch pela metade: as amostras já aceitas nele e as da
+    /// falha vão para o buffer offline e são todas reenviadas, na ordem, na reconexão
+    #[cfg(unix)]
+    #[test]
+    fn partial_batch_survives_disconnect() {
+        use std::{io::BufRead, os::unix::net::UnixListener};
+
+        let path = std::env::temp_dir().join(format!("telemetry-batch-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let address = format!("unix:{}", path.display());
+
+        let mut client = TelemetryClient {
+            destinations: vec![Destination::new(address, None)],
+            connect_timeout: Duration::from_secs(1),
+            framing: Framing::Line,
+            identity: ClientIdentity { client_id: "teste".to_string(), hostname: "host".to_string(), namespace: None, auth_token: None },
+            backoff: Backoff { max_attempts: 0 },
+            offline: OfflineBuffer::new(10),
+            send_queue: 10,
+            udp: false,
+            compression: None,
+            messages: MessageCounters::default(),
+            batch: SampleBatch::new(3, None),
+            next_nonce: 1,
+        };
+        let samples: Vec<String> = (1..=4).map(|n| format!(r#"{{"timestamp":{},"CPU":1.0}}"#, n)).collect();
+
+        client.destinations[0].open(false, client.connect_timeout).unwrap();
+        client.destinations[0].batch = true;
+        let (server, _) = listener.accept().unwrap();
+        client.send_queued(samples[..2].to_vec()).unwrap();
+        drop(server);
+        assert!(client.send_queued(samples[2..].to_vec()).is_err());
+        assert_eq!(client.offline.len(), samples.len());
+
+        client.destinations[0].open(false, client.connect_timeout).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        client.flush_offline().unwrap();
+        drop(client);
+        let resent: Vec<String> = io::BufReader::new(server).lines().map(Result::unwrap).collect();
+        assert_eq!(resent, samples);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt;
+
+/// Tipos de hardware monitorados
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareMetric {
+    Cpu,
+    Memory,
+    Swap,
+    Disk,
+    Network,
+    Temperature,
+    Processes,
+    /// Tempo desde o boot, em segundos
+    Uptime,
+    /// Utilização e memória da GPU (`GPU_UTIL` e `GPU_MEM`), só com NVIDIA
+    Gpu,
+    /// Clock da CPU em MHz (`CPU_FREQ`); omitido onde a plataforma não informa
+    CpuFreq,
+    /// Leitura e escrita em disco em MB/s (`DISK_READ` e `DISK_WRITE`)
+    DiskIo,
+    /// Carga da bateria em % (`BATTERY`) e se está carregando (`BATTERY_CHARGING`);
+    /// omitida em máquinas sem bateria
+    Battery,
+    /// Conexões TCP abertas (`TCP_CONNS`); omitida fora do Linux
+    TcpConnections,
+}
+
+impl HardwareMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HardwareMetric::Cpu => "CPU",
+            HardwareMetric::Memory => "MEM",
+            HardwareMetric::Swap => "SWAP",
+            HardwareMetric::Disk => "DISK",
+            HardwareMetric::Network => "NET",
+            HardwareMetric::Temperature => "TEMP",
+            HardwareMetric::Processes => "PROC_COUNT",
+            HardwareMetric::Uptime => "UPTIME",
+            HardwareMetric::Gpu => "GPU",
+            HardwareMetric::CpuFreq => "CPU_FREQ",
+            HardwareMetric::DiskIo => "DISK_IO",
+            HardwareMetric::Battery => "BATTERY",
+            HardwareMetric::TcpConnections => "TCP_CONNS",
+        }
+    }
+
+    pub fn all() -> &'static [HardwareMetric] {
+        &[
+            HardwareMetric::Cpu,
+            HardwareMetric::Memory,
+            HardwareMetric::Swap,
+            HardwareMetric::Disk,
+            HardwareMetric::Network,
+            HardwareMetric::Temperature,
+            HardwareMetric::Processes,
+            HardwareMetric::Uptime,
+            HardwareMetric::Gpu,
+            HardwareMetric::CpuFreq,
+            HardwareMetric::DiskIo,
+            HardwareMetric::Battery,
+            HardwareMetric::TcpConnections,
+        ]
+    }
+
+    /// Aceita o nome enviado no JSON ou um apelido, sem diferenciar maiúsculas
+    pub fn parse(name: &str) -> Option<Self> {
+        let metric = match name.trim().to_ascii_lowercase().as_str() {
+            "cpu" => HardwareMetric::Cpu,
+            "mem" | "memory" => HardwareMetric::Memory,
+            "swap" => HardwareMetric::Swap,
+            "disk" => HardwareMetric::Disk,
+            "net" | "network" => HardwareMetric::Network,
+            "temp" | "temperature" => HardwareMetric::Temperature,
+            "proc" | "proc_count" | "processes" => HardwareMetric::Processes,
+            "uptime" => HardwareMetric::Uptime,
+            "gpu" => HardwareMetric::Gpu,
+            "cpu_freq" | "freq" | "clock" => HardwareMetric::CpuFreq,
+            "disk_io" | "io" => HardwareMetric::DiskIo,
+            "battery" | "bat" => HardwareMetric::Battery,
+            "tcp" | "tcp_conns" | "connections" => HardwareMetric::TcpConnections,
+            _ => return None,
+        };
+        Some(metric)
+    }
+
+    /// Lê uma lista separada por vírgulas (`cpu,disk,net`). Nomes desconhecidos
+    /// voltam à parte para o chamador avisar, sem invalidar os demais
+    pub fn parse_list(raw: &str) -> (Vec<HardwareMetric>, Vec<String>) {
+        let mut metrics = Vec::new();
+        let mut unknown = Vec::new();
+        for name in raw.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match Self::parse(name) {
+                Some(metric) if !metrics.contains(&metric) => metrics.push(metric),
+                Some(_) => {}
+                None => unknown.push(name.to_string()),
+            }
+        }
+        (metrics, unknown)
+    }
+}
+
+impl fmt::Display for HardwareMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_list_is_case_insensitive_and_reports_unknown() {
+        let (metrics, unknown) = HardwareMetric::parse_list("CPU, disk,Net,fan,cpu,");
+        assert_eq!(metrics, [HardwareMetric::Cpu, HardwareMetric::Disk, HardwareMetric::Network]);
+        assert_eq!(unknown, ["fan"]);
+    }
+
+    #[test]
+    fn aliases_match_json_names() {
+        for metric in HardwareMetric::all() {
+            assert_eq!(HardwareMetric::parse(metric.as_str()), Some(*metric));
+        }
+        assert_eq!(HardwareMetric::parse("Memory"), Some(HardwareMetric::Memory));
+    }
+}
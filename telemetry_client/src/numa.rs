@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+#[cfg(target_os = "linux")]
+use std::{fs, path::Path};
+
+/// Topologia exposta pelo kernel, um diretório `cpu<N>` por núcleo lógico
+#[cfg(target_os = "linux")]
+const SYS_CPU_DIR: &str = "/sys/devices/system/cpu";
+
+/// Socket físico (ou nó NUMA) de cada núcleo, para as médias por socket em
+/// `CPU_SOCKET_<n>` (`--cpu-sockets`). Só existe com mais de um socket: numa
+/// máquina single-socket a média em `CPU` já diz tudo
+pub struct SocketTopology {
+    /// Socket de cada núcleo, na ordem de `System::cpus`
+    sockets: Vec<usize>,
+}
+
+impl SocketTopology {
+    /// Mapeia os núcleos pelos nomes do sysinfo (`cpu0`, `cpu1`...). Sem o mapeamento
+    /// (plataforma sem `/sys`, VM que não o expõe) ou com um único socket, avisa
+    /// e devolve `None`, e o cliente segue só com a média em `CPU`
+    pub fn detect(cpu_names: &[&str]) -> Option<Self> {
+        let Some(sockets) = cpu_names.iter().map(|name| socket_of(name)).collect::<Option<Vec<usize>>>() else {
+            println!("ℹ️ Mapeamento de núcleos por socket/NUMA indisponível: só a média em CPU é enviada");
+            return None;
+        };
+
+        let topology = Self { sockets };
+        match topology.socket_count() {
+            0 => None,
+            1 => {
+                println!("ℹ️ Um único socket detectado: a média em CPU já o representa");
+                None
+            }
+            count => {
+                println!("🧩 {} sockets detectados: médias por socket em CPU_SOCKET_<n>", count);
+                Some(topology)
+            }
+        }
+    }
+
+    fn socket_count(&self) -> usize {
+        let mut sockets = self.sockets.clone();
+        sockets.sort_unstable();
+        sockets.dedup();
+        sockets.len()
+    }
+
+    /// Média de uso por socket a partir do uso de cada núcleo, na mesma ordem
+    /// usada no `detect`
+    pub fn averages(&self, usages: &[f32]) -> BTreeMap<usize, f32> {
+        let mut totals: BTreeMap<usize, (f32, usize)> = BTreeMap::new();
+        for (socket, usage) in self.sockets.iter().zip(usages) {
+            let total = totals.entry(*socket).or_default();
+            total.0 += usage;
+            total.1 += 1;
+        }
+        totals.into_iter().map(|(socket, (sum, count))| (socket, sum / count as f32)).collect()
+    }
+}
+
+/// Socket de um núcleo pelo `physical_package_id` ou, quando ele falta ou vale
+/// `-1` (comum em VMs), pelo nó NUMA (`cpu<N>/node<K>`)
+#[cfg(target_os = "linux")]
+fn socket_of(cpu_name: &str) -> Option<usize> {
+    let cpu_dir = Path::new(SYS_CPU_DIR).join(format!("cpu{}", cpu_id(cpu_name)?));
+    let package = fs::read_to_string(cpu_dir.join("topology/physical_package_id")).ok()
+        .and_then(|raw| raw.trim().parse().ok());
+    package.or_else(|| {
+        fs::read_dir(&cpu_dir).ok()?
+            .filter_map(Result::ok)
+            .find_map(|entry| entry.file_name().to_str()?.strip_prefix("node")?.parse().ok())
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn socket_of(_cpu_name: &str) -> Option<usize> {
+    None
+}
+
+/// Número do núcleo no nome dado pelo sysinfo no Linux (`cpu12` → 12)
+#[cfg(any(target_os = "linux", test))]
+fn cpu_id(name: &str) -> Option<usize> {
+    name.strip_prefix("cpu")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_cores_by_socket() {
+        let topology = SocketTopology { sockets: vec![0, 0, 1, 1, 1, 0] };
+        assert_eq!(topology.socket_count(), 2);
+
+        let averages = topology.averages(&[10.0, 20.0, 90.0, 80.0, 100.0, 30.0]);
+        assert_eq!(averages, BTreeMap::from([(0, 20.0), (1, 90.0)]));
+
+        assert_eq!(cpu_id("cpu12"), Some(12));
+        assert_eq!(cpu_id("CPU 1"), None);
+    }
+}
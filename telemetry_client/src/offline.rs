@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+
+/// Amostras coletadas enquanto nenhum servidor está conectado, reenviadas na
+/// reconexão com os timestamps originais. Ring buffer limitado: numa queda longa,
+/// as mais antigas dão lugar às novas
+pub struct OfflineBuffer {
+    /// Amostras já serializadas, na ordem de coleta
+    samples: VecDeque<String>,
+    capacity: usize,
+    /// Descartadas por falta de espaço desde o último flush
+    dropped: u64,
+}
+
+impl OfflineBuffer {
+    /// `capacity` 0 desativa o buffer
+    pub fn new(capacity: usize) -> Self {
+        Self { samples: VecDeque::new(), capacity, dropped: 0 }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Guarda uma amostra, descartando a mais antiga se o buffer estiver cheio.
+    /// Devolve `true` no primeiro descarte desde o último flush, para o aviso sair uma vez
+    pub fn push(&mut self, sample: &str) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+        let mut first_drop = false;
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+            self.dropped += 1;
+            first_drop = self.dropped == 1;
+        }
+        self.samples.push_back(sample.to_string());
+        first_drop
+    }
+
+    /// Mais antiga ainda não reenviada
+    pub fn front(&self) -> Option<&str> {
+        self.samples.front().map(String::as_str)
+    }
+
+    /// Confirma o reenvio da mais antiga
+    pub fn pop_front(&mut self) {
+        self.samples.pop_front();
+    }
+
+    /// Descartes desde o último flush, zerando a contagem
+    pub fn take_dropped(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_newest_samples_up_to_capacity() {
+        let mut buffer = OfflineBuffer::new(2);
+        assert!(!buffer.push("a"));
+        assert!(!buffer.push("b"));
+        assert!(buffer.push("c"));
+        assert!(!buffer.push("d"));
+
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.take_dropped(), 2);
+        assert_eq!(buffer.front(), Some("c"));
+        buffer.pop_front();
+        assert_eq!(buffer.front(), Some("d"));
+        buffer.pop_front();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn zero_capacity_disables_buffer() {
+        let mut buffer = OfflineBuffer::new(0);
+        assert!(!buffer.is_enabled());
+        assert!(!buffer.push("a"));
+        assert!(buffer.is_empty());
+    }
+}
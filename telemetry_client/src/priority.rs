@@ -0,0 +1,43 @@
+/// Maior nice aceito por `--nice`; no Unix, 19 é a prioridade mais baixa
+pub const MAX_NICE: i32 = 19;
+
+/// A partir deste nice o Windows usa a classe ociosa em vez de "abaixo do normal"
+#[cfg(windows)]
+const IDLE_NICE: i32 = 15;
+
+/// Baixa a prioridade de scheduling do próprio processo para a coleta não competir
+/// por CPU: `setpriority` no Unix, classe de prioridade no Windows
+#[cfg(unix)]
+pub fn lower_priority(nice: i32) -> Result<(), String> {
+    // SAFETY: só altera a prioridade do processo atual (pid 0)
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) } == -1 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn lower_priority(nice: i32) -> Result<(), String> {
+    use std::ffi::c_void;
+
+    const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x4000;
+    const IDLE_PRIORITY_CLASS: u32 = 0x40;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> *mut c_void;
+        fn SetPriorityClass(process: *mut c_void, priority_class: u32) -> i32;
+    }
+
+    let class = if nice >= IDLE_NICE { IDLE_PRIORITY_CLASS } else { BELOW_NORMAL_PRIORITY_CLASS };
+    // SAFETY: o pseudo-handle do processo atual dispensa fechamento
+    if unsafe { SetPriorityClass(GetCurrentProcess(), class) } == 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn lower_priority(_nice: i32) -> Result<(), String> {
+    Err("não suportado nesta plataforma".to_string())
+}
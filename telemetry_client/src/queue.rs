@@ -0,0 +1,154 @@
+use std::{
+    collections::VecDeque,
+    sync::{mpsc::RecvTimeoutError, Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Fila limitada entre a thread de coleta e a de envio. Cheia, a amostra mais
+/// antiga dá lugar à nova no próprio `send`, para a memória não crescer com o
+/// envio travado (servidor que parou de ler)
+struct Shared {
+    state: Mutex<QueueState>,
+    ready: Condvar,
+    capacity: usize,
+}
+
+struct QueueState {
+    samples: VecDeque<String>,
+    /// Descartadas por falta de espaço desde o último `take_dropped`
+    dropped: u64,
+    /// Uma das pontas já foi solta
+    closed: bool,
+}
+
+/// Ponta da thread de coleta
+pub struct SampleSender {
+    shared: Arc<Shared>,
+}
+
+/// Ponta da thread de envio
+pub struct SampleReceiver {
+    shared: Arc<Shared>,
+}
+
+/// Cria a fila com até `capacity` amostras (pelo menos uma)
+pub fn bounded(capacity: usize) -> (SampleSender, SampleReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(QueueState { samples: VecDeque::new(), dropped: 0, closed: false }),
+        ready: Condvar::new(),
+        capacity: capacity.max(1),
+    });
+    (SampleSender { shared: Arc::clone(&shared) }, SampleReceiver { shared })
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.ready.notify_all();
+    }
+}
+
+impl SampleSender {
+    /// Enfileira sem nunca bloquear, descartando a mais antiga se a fila estiver
+    /// cheia; `false` quando a thread de envio já encerrou
+    pub fn send(&self, sample: String) -> bool {
+        let mut state = self.shared.lock();
+        if state.closed {
+            return false;
+        }
+        if state.samples.len() == self.shared.capacity {
+            state.samples.pop_front();
+            state.dropped += 1;
+        }
+        state.samples.push_back(sample);
+        drop(state);
+        self.shared.ready.notify_one();
+        true
+    }
+}
+
+impl SampleReceiver {
+    /// Espera até `timeout` pela próxima amostra. `Disconnected` só depois que a
+    /// coleta encerrou e a fila esvaziou
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<String, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(sample) = state.samples.pop_front() {
+                return Ok(sample);
+            }
+            if state.closed {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            state = self.shared.ready.wait_timeout(state, remaining)
+                .unwrap_or_else(|e| e.into_inner()).0;
+        }
+    }
+
+    /// Tudo o que está na fila, na ordem de coleta
+    pub fn drain(&self) -> Vec<String> {
+        self.shared.lock().samples.drain(..).collect()
+    }
+
+    /// Descartadas por fila cheia desde a última chamada
+    pub fn take_dropped(&self) -> u64 {
+        std::mem::take(&mut self.shared.lock().dropped)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl Drop for SampleSender {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl Drop for SampleReceiver {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Com ninguém consumindo, a fila guarda só as `capacity` mais novas
+    #[test]
+    fn drops_oldest_when_full() {
+        let (sender, receiver) = bounded(3);
+        for sample in 1..=5 {
+            assert!(sender.send(sample.to_string()));
+        }
+
+        assert_eq!(receiver.take_dropped(), 2);
+        assert_eq!(receiver.take_dropped(), 0);
+        assert_eq!(receiver.recv_timeout(Duration::ZERO), Ok("3".to_string()));
+        assert_eq!(receiver.drain(), ["4", "5"]);
+        assert_eq!(receiver.recv_timeout(Duration::ZERO), Err(RecvTimeoutError::Timeout));
+
+        // Coleta encerrada: o que restou ainda sai antes do Disconnected
+        sender.send("6".to_string());
+        drop(sender);
+        assert_eq!(receiver.recv_timeout(Duration::ZERO), Ok("6".to_string()));
+        assert_eq!(receiver.recv_timeout(Duration::ZERO), Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn send_fails_after_receiver_is_gone() {
+        let (sender, receiver) = bounded(1);
+        drop(receiver);
+        assert!(!sender.send("1".to_string()));
+    }
+}
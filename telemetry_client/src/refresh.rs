@@ -0,0 +1,129 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, ProcessRefreshKind, RefreshKind, MINIMUM_CPU_UPDATE_INTERVAL};
+
+use crate::metric::HardwareMetric;
+
+/// Dados de processo usados: o nome vem sempre, e o %CPU alimenta o `top_process`
+pub fn process_refresh() -> ProcessRefreshKind {
+    ProcessRefreshKind::nothing().with_cpu()
+}
+
+/// Partes do `System` carregadas no startup, só as das métricas habilitadas.
+/// Disco, rede e temperatura têm estruturas próprias e ficam de fora; o uso por
+/// núcleo também serve às médias por socket (`cpu_sockets`)
+pub fn system_refresh_kind(metrics: &[HardwareMetric], top_process: bool, cpu_sockets: bool) -> RefreshKind {
+    let mut cpu = CpuRefreshKind::nothing();
+    if metrics.contains(&HardwareMetric::Cpu) || cpu_sockets {
+        cpu = cpu.with_cpu_usage();
+    }
+    if metrics.contains(&HardwareMetric::CpuFreq) {
+        cpu = cpu.with_frequency();
+    }
+
+    let mut memory = MemoryRefreshKind::nothing();
+    if metrics.contains(&HardwareMetric::Memory) {
+        memory = memory.with_ram();
+    }
+    if metrics.contains(&HardwareMetric::Swap) {
+        memory = memory.with_swap();
+    }
+
+    let mut kind = RefreshKind::nothing();
+    if cpu.cpu_usage() || cpu.frequency() {
+        kind = kind.with_cpu(cpu);
+    }
+    if memory.ram() || memory.swap() {
+        kind = kind.with_memory(memory);
+    }
+    if top_process || metrics.contains(&HardwareMetric::Processes) {
+        kind = kind.with_processes(process_refresh());
+    }
+    kind
+}
+
+/// Warm-up do uso de CPU: o `sysinfo` calcula a %CPU (total e por processo) pela
+/// diferença entre dois refreshes, e com menos de `MINIMUM_CPU_UPDATE_INTERVAL`
+/// entre eles o refresh é ignorado e o valor sai 0. `since` é tomado logo após o
+/// refresh inicial dos `System`; dorme só o que falta, descontando o tempo já gasto conectando
+pub fn wait_cpu_warm_up(since: Instant) {
+    let remaining = MINIMUM_CPU_UPDATE_INTERVAL.saturating_sub(since.elapsed());
+    if !remaining.is_zero() {
+        thread::sleep(remaining);
+    }
+}
+
+/// Tempo gasto coletando as métricas, resumido a cada `every` ciclos
+pub struct CollectTimer {
+    every: u64,
+    cycles: u64,
+    total: Duration,
+    max: Duration,
+}
+
+/// Média e pior caso de uma janela de ciclos
+#[derive(Debug, PartialEq)]
+pub struct CollectReport {
+    pub cycles: u64,
+    pub average: Duration,
+    pub max: Duration,
+}
+
+impl CollectTimer {
+    pub fn new(every: u64) -> Self {
+        Self { every: every.max(1), cycles: 0, total: Duration::ZERO, max: Duration::ZERO }
+    }
+
+    /// Registra um ciclo; fecha a janela e devolve o resumo a cada `every` ciclos
+    pub fn record(&mut self, elapsed: Duration) -> Option<CollectReport> {
+        self.cycles += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        if self.cycles < self.every {
+            return None;
+        }
+
+        let report = CollectReport {
+            cycles: self.cycles,
+            average: self.total / self.cycles as u32,
+            max: self.max,
+        };
+        *self = Self::new(self.every);
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_only_what_enabled_metrics_need() {
+        let kind = system_refresh_kind(&[HardwareMetric::Cpu], false, false);
+        assert_eq!(kind.cpu(), Some(CpuRefreshKind::nothing().with_cpu_usage()));
+        assert_eq!(kind.memory(), None);
+        assert_eq!(kind.processes(), None);
+
+        let kind = system_refresh_kind(&[HardwareMetric::Swap, HardwareMetric::Disk], true, false);
+        assert_eq!(kind.cpu(), None);
+        assert_eq!(kind.memory(), Some(MemoryRefreshKind::nothing().with_swap()));
+        assert_eq!(kind.processes(), Some(process_refresh()));
+
+        assert_eq!(system_refresh_kind(&[HardwareMetric::Network], false, false), RefreshKind::nothing());
+    }
+
+    #[test]
+    fn timer_reports_every_n_cycles() {
+        let mut timer = CollectTimer::new(3);
+        assert_eq!(timer.record(Duration::from_micros(100)), None);
+        assert_eq!(timer.record(Duration::from_micros(400)), None);
+        let report = timer.record(Duration::from_micros(100)).unwrap();
+        assert_eq!(report, CollectReport { cycles: 3, average: Duration::from_micros(200), max: Duration::from_micros(400) });
+
+        // A janela recomeça do zero
+        assert_eq!(timer.record(Duration::from_micros(10)), None);
+    }
+}
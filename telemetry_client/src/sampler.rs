@@ -0,0 +1,589 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    env,
+    io::{self, Result as IoResult},
+    path::Path,
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use sysinfo::{Components, Disks, MemoryRefreshKind, NetworkData, Networks, ProcessesToUpdate, System};
+use telemetry_common::{
+    format::describe_metric,
+    labels,
+    sample::{percent_of, TelemetrySample, TopProcess},
+    value::Value,
+    is_temperature_unavailable, TEMPERATURE_UNAVAILABLE,
+};
+
+use crate::{
+    adaptive::AdaptiveInterval,
+    battery::BatteryProbe,
+    collector::MetricCollector,
+    config::{ClientConfig, CpuMode, FreqMode},
+    epoch_millis,
+    gpu::GpuProbe,
+    metric::HardwareMetric,
+    numa::SocketTopology,
+    queue::SampleSender,
+    refresh::{self, CollectTimer},
+    smoothing::Smoother,
+    JSON_BUFFER_CAPACITY,
+};
+
+/// Ciclos entre os logs do tempo médio de coleta
+const COLLECT_REPORT_CYCLES: u64 = 60;
+
+/// Variável de ambiente que escolhe quais discos entram na métrica DISK
+const DISK_SCOPE_ENV: &str = "TELEMETRY_DISK_SCOPE";
+
+/// Quais pontos de montagem compõem a métrica de disco
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiskScope {
+    /// Apenas o disco raiz (`/` ou a unidade do sistema)
+    Root,
+    /// Soma de todos os pontos de montagem
+    All,
+    /// Disco raiz em `DISK` e uma série `DISK{mount="..."}` por ponto de montagem
+    Mounts,
+}
+
+impl DiskScope {
+    /// Lê o escopo de `TELEMETRY_DISK_SCOPE` (`root`, `all` ou `mounts`), com `root` como padrão
+    fn from_env() -> Self {
+        match env::var(DISK_SCOPE_ENV) {
+            Ok(value) if value.eq_ignore_ascii_case("all") => DiskScope::All,
+            Ok(value) if value.eq_ignore_ascii_case("mounts") => DiskScope::Mounts,
+            Ok(value) if !value.eq_ignore_ascii_case("root") => {
+                eprintln!("⚠️ {} inválido: {} (usando root)", DISK_SCOPE_ENV, value);
+                DiskScope::Root
+            }
+            _ => DiskScope::Root,
+        }
+    }
+}
+
+/// Pedidos do servidor que mudam a coleta, repassados pela thread de envio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollectorControl {
+    SetInterval(Duration),
+    SetGpu(bool),
+    CollectNow,
+}
+
+/// Coleta das amostras, na própria thread: o ritmo segue o relógio mesmo quando
+/// o envio congestiona. Cada amostra sai já serializada pelo canal da thread de envio
+pub struct SampleCollector {
+    system: System,
+    disks: Disks,
+    disk_scope: DiskScope,
+    networks: Networks,
+    /// Total de bytes (rx + tx) e instante da amostra anterior, para calcular a taxa
+    last_network_sample: Option<(u64, Instant)>,
+    /// Bytes lidos e escritos em disco, e o instante, da amostra anterior
+    last_disk_io_sample: Option<(u64, u64, Instant)>,
+    components: Components,
+    interval: Duration,
+    report_top_process: bool,
+    /// `--print`: mostra cada amostra no terminal antes do envio
+    print_samples: bool,
+    /// Métricas com coleta própria, as que dependem do `System` compartilhado
+    metrics: Vec<HardwareMetric>,
+    /// Coletores plugáveis: CPU média, memória e as métricas customizadas
+    collectors: Vec<Box<dyn MetricCollector>>,
+    cpu_mode: CpuMode,
+    /// `--cpu-sockets` numa máquina com mais de um socket mapeado, com a `CPU` vinda
+    /// daqui ou de um coletor
+    sockets: Option<SocketTopology>,
+    freq_mode: FreqMode,
+    gpu: GpuProbe,
+    battery: BatteryProbe,
+    /// Coleta de GPU ligada; o servidor pode desligá-la com `set_gpu`
+    gpu_enabled: bool,
+    /// Média móvel aplicada antes do envio (inativa com alpha 1.0)
+    smoother: Smoother,
+    collect_timer: CollectTimer,
+    /// `--adaptive`: o intervalo passa a seguir a variação das amostras
+    adaptive: Option<AdaptiveInterval>,
+}
+
+impl SampleCollector {
+    pub fn new(config: &ClientConfig, collectors: Vec<Box<dyn MetricCollector>>, metrics: Vec<HardwareMetric>) -> Self {
+        // Só o que as métricas habilitadas usam: em dispositivos modestos, carregar
+        // e atualizar tudo a cada ciclo custa mais que a própria coleta
+        // A CPU pode ter ido para um coletor (média, cgroup) e sair de `metrics`;
+        // as médias por socket seguem aqui, pelos núcleos do `System`
+        let cpu_sockets = config.cpu_sockets && config.metrics.contains(&HardwareMetric::Cpu);
+        let system = System::new_with_specifics(refresh::system_refresh_kind(&metrics, config.top_process, cpu_sockets));
+        let disks = if metrics.contains(&HardwareMetric::Disk) || metrics.contains(&HardwareMetric::DiskIo) {
+            Disks::new_with_refreshed_list()
+        } else {
+            Disks::new()
+        };
+        let networks = if metrics.contains(&HardwareMetric::Network) { Networks::new_with_refreshed_list() } else { Networks::new() };
+        let components = if metrics.contains(&HardwareMetric::Temperature) {
+            Components::new_with_refreshed_list()
+        } else {
+            Components::new()
+        };
+
+        let sockets = if cpu_sockets {
+            let names: Vec<&str> = system.cpus().iter().map(|cpu| cpu.name()).collect();
+            SocketTopology::detect(&names)
+        } else {
+            None
+        };
+
+        Self {
+            system,
+            disks,
+            disk_scope: DiskScope::from_env(),
+            networks,
+            last_network_sample: None,
+            last_disk_io_sample: None,
+            components,
+            interval: config.adaptive.map_or(config.interval, |adaptive| adaptive.min),
+            report_top_process: config.top_process,
+            print_samples: config.print,
+            metrics,
+            collectors,
+            cpu_mode: config.cpu_mode,
+            sockets,
+            freq_mode: config.freq_mode,
+            gpu: GpuProbe::new(),
+            battery: BatteryProbe::new(),
+            gpu_enabled: true,
+            smoother: Smoother::new(config.smoothing),
+            collect_timer: CollectTimer::new(COLLECT_REPORT_CYCLES),
+            adaptive: config.adaptive.map(AdaptiveInterval::new),
+        }
+    }
+
+    /// Laço da thread de coleta. Os ciclos são agendados pelo relógio, não pelo fim
+    /// do envio; termina quando a thread de envio encerra e fecha os canais
+    pub fn run(mut self, samples: SampleSender, control: Receiver<CollectorControl>) {
+        let mut next_sample = Instant::now();
+        loop {
+            match control.recv_timeout(next_sample.saturating_duration_since(Instant::now())) {
+                Ok(CollectorControl::SetInterval(interval)) => {
+                    // O intervalo pedido pelo servidor vale até o fim, sem adaptação
+                    if self.adaptive.take().is_some() {
+                        println!("ℹ️ Intervalo adaptativo desativado pelo intervalo fixo do servidor");
+                    }
+                    self.interval = interval;
+                }
+                Ok(CollectorControl::SetGpu(enabled)) => self.gpu_enabled = enabled,
+                Ok(CollectorControl::CollectNow) => {
+                    if !self.collect_into(&samples) {
+                        return;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if !self.collect_into(&samples) {
+                        return;
+                    }
+                    next_sample += self.interval;
+                    // Ciclo perdido (coleta lenta, máquina suspensa): recomeça a agenda de agora
+                    let now = Instant::now();
+                    if next_sample < now {
+                        next_sample = now + self.interval;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    /// Coleta uma amostra e a entrega à thread de envio sem esperar por ela (a fila
+    /// descarta as mais antigas); `false` quando ela já encerrou
+    fn collect_into(&mut self, samples: &SampleSender) -> bool {
+        let mut json_buffer = String::with_capacity(JSON_BUFFER_CAPACITY);
+        match self.build_telemetry_json(&mut json_buffer) {
+            Ok(()) => samples.send(json_buffer),
+            Err(e) => {
+                eprintln!("⚠️ Falha na coleta: {}", e);
+                true
+            }
+        }
+    }
+
+    pub fn build_telemetry_json(&mut self, buffer: &mut String) -> IoResult<()> {
+        let started = Instant::now();
+        let mut sample = TelemetrySample::new(epoch_millis());
+        for collector in &mut self.collectors {
+            if let Some(value) = collector.collect() {
+                sample.set(collector.name(), value);
+            }
+        }
+        for index in 0..self.metrics.len() {
+            let metric = self.metrics[index];
+            self.collect_metric(&metric, &mut sample);
+        }
+        // Média em `CPU` por coletor: só as médias por socket saem daqui
+        if self.sockets.is_some() && !self.metrics.contains(&HardwareMetric::Cpu) {
+            self.system.refresh_cpu_usage();
+            self.collect_sockets(&mut sample);
+        }
+
+        if self.report_top_process {
+            // Sem PROC_COUNT, ninguém atualizou a lista de processos neste ciclo
+            if !self.metrics.contains(&HardwareMetric::Processes) {
+                self.refresh_processes();
+            }
+            sample.top_process = self.top_process().map(|(name, cpu)| TopProcess { name, cpu });
+        }
+
+        if let Some(report) = self.collect_timer.record(started.elapsed()) {
+            println!(
+                "⏱️ Coleta: média de {} µs, máximo de {} µs nos últimos {} ciclos",
+                report.average.as_micros(), report.max.as_micros(), report.cycles
+            );
+        }
+
+        self.smoother.apply(&mut sample);
+        sample.sanitize();
+        if let Some(adaptive) = &mut self.adaptive {
+            self.interval = adaptive.next(sample.clone().take_metrics().0);
+        }
+        if self.print_samples {
+            print_sample(&sample);
+        }
+        let json = serde_json::to_string(&sample).map_err(io::Error::other)?;
+        buffer.push_str(&json);
+        Ok(())
+    }
+
+    fn collect_metric(&mut self, metric: &HardwareMetric, sample: &mut TelemetrySample) {
+        match metric {
+            HardwareMetric::Cpu => self.collect_cpu(sample),
+            // Sempre via MemoryCollector; nunca sobra para cá
+            HardwareMetric::Memory => {}
+            // Idem, via TcpConnectionsCollector
+            HardwareMetric::TcpConnections => {}
+            HardwareMetric::Swap => sample.swap = Some(self.get_swap_usage()),
+            HardwareMetric::Disk => {
+                sample.disk = Some(self.get_disk_usage());
+                if self.disk_scope == DiskScope::Mounts {
+                    self.collect_mounts(sample);
+                }
+            }
+            HardwareMetric::Network => sample.net = Some(self.get_network_throughput()),
+            HardwareMetric::Temperature => sample.temp = Some(self.get_temperature()),
+            HardwareMetric::Processes => sample.proc_count = Some(self.get_process_count()),
+            HardwareMetric::Uptime => sample.uptime = Some(System::uptime() as f32),
+            HardwareMetric::CpuFreq => sample.cpu_freq = self.get_cpu_frequency(),
+            HardwareMetric::DiskIo => {
+                let (read, write) = self.get_disk_io();
+                sample.disk_read = Some(read);
+                sample.disk_write = Some(write);
+            }
+            HardwareMetric::Battery => {
+                if let Some(battery) = self.battery.read() {
+                    sample.battery = Some(battery.percent);
+                    sample.battery_charging = Some(if battery.charging { 1.0 } else { 0.0 });
+                }
+            }
+            // Sem GPU a métrica simplesmente não é enviada
+            HardwareMetric::Gpu => {
+                if !self.gpu_enabled {
+                    return;
+                }
+                if let Some(gpu) = self.gpu.read() {
+                    sample.gpu_util = Some(gpu.utilization);
+                    sample.gpu_mem = Some(gpu.memory_used_mb);
+                }
+            }
+        }
+    }
+
+    /// Média em `CPU` e/ou um valor por núcleo em `CPU_<n>`, conforme `--cpu-mode`,
+    /// e a média de cada socket em `CPU_SOCKET_<n>` com `--cpu-sockets`
+    fn collect_cpu(&mut self, sample: &mut TelemetrySample) {
+        let average = self.get_cpu_usage();
+        if self.cpu_mode != CpuMode::PerCore {
+            sample.cpu = Some(average);
+        }
+        self.collect_sockets(sample);
+        if self.cpu_mode != CpuMode::Average {
+            for (index, cpu) in self.system.cpus().iter().enumerate() {
+                sample.extra.insert(format!("CPU_{}", index), cpu.cpu_usage().into());
+            }
+        }
+    }
+
+    /// Média de cada socket em `CPU_SOCKET_<n>`, do último refresh dos núcleos
+    fn collect_sockets(&self, sample: &mut TelemetrySample) {
+        if let Some(sockets) = &self.sockets {
+            let usages: Vec<f32> = self.system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
+            for (socket, usage) in sockets.averages(&usages) {
+                sample.extra.insert(format!("CPU_SOCKET_{}", socket), usage.into());
+            }
+        }
+    }
+
+    fn get_cpu_usage(&mut self) -> f32 {
+        self.system.refresh_cpu_usage();
+        
+        // Calcula média de todas as CPUs ao invés de apenas a primeira
+        let cpus = self.system.cpus();
+        if cpus.is_empty() {
+            return 0.0;
+        }
+        
+        let total: f32 = cpus.iter().map(|cpu| cpu.cpu_usage()).sum();
+        total / cpus.len() as f32
+    }
+
+    /// Clock em MHz conforme `--cpu-freq`. Plataformas sem a informação reportam 0
+    /// em todos os núcleos; nesse caso a métrica não é enviada
+    fn get_cpu_frequency(&mut self) -> Option<f32> {
+        self.system.refresh_cpu_frequency();
+
+        let frequencies: Vec<u64> = self.system.cpus().iter()
+            .map(|cpu| cpu.frequency())
+            .filter(|mhz| *mhz > 0)
+            .collect();
+        if frequencies.is_empty() {
+            return None;
+        }
+
+        let mhz = match self.freq_mode {
+            FreqMode::Average => frequencies.iter().sum::<u64>() as f32 / frequencies.len() as f32,
+            FreqMode::Max => frequencies.iter().copied().max().unwrap_or_default() as f32,
+        };
+        Some(mhz)
+    }
+
+    /// Porcentagem do swap em uso; 0 em máquinas sem swap configurado
+    fn get_swap_usage(&mut self) -> f32 {
+        self.system.refresh_memory_specifics(MemoryRefreshKind::nothing().with_swap());
+        percent_of(self.system.used_swap(), self.system.total_swap())
+    }
+
+    fn get_disk_usage(&mut self) -> f32 {
+        self.disks.refresh(true);
+
+        let list = self.disks.list();
+        let selected: Vec<_> = match self.disk_scope {
+            DiskScope::All => list.iter().collect(),
+            DiskScope::Root | DiskScope::Mounts => {
+                // Prefere o ponto de montagem "/" e cai no primeiro disco (ex.: C:\ no Windows)
+                list.iter()
+                    .find(|disk| disk.mount_point() == Path::new("/"))
+                    .or_else(|| list.first())
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        let (total, available) = selected.iter().fold((0u64, 0u64), |(total, available), disk| {
+            (total + disk.total_space(), available + disk.available_space())
+        });
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        (total - available) as f32 / total as f32 * 100.0
+    }
+
+    /// Uso de cada ponto de montagem como série com label, a partir da lista já
+    /// atualizada por `get_disk_usage`; montagens repetidas (bind mounts) colapsam na chave
+    fn collect_mounts(&self, sample: &mut TelemetrySample) {
+        for disk in self.disks.list().iter().filter(|disk| disk.total_space() > 0) {
+            let used = disk.total_space().saturating_sub(disk.available_space());
+            let mount = disk.mount_point().to_string_lossy();
+            sample.extra.insert(labels::series_key("DISK", &[("mount", &mount)]), percent_of(used, disk.total_space()).into());
+        }
+    }
+
+    /// Throughput agregado (rx + tx) de todas as interfaces, exceto loopback, em MB/s
+    fn get_network_throughput(&mut self) -> f32 {
+        self.networks.refresh(true);
+
+        let total_bytes: u64 = self.networks.iter()
+            .filter(|(name, data)| !is_loopback_interface(name, data))
+            .map(|(_, data)| data.total_received() + data.total_transmitted())
+            .sum();
+        let now = Instant::now();
+
+        let rate = match self.last_network_sample {
+            Some((previous_bytes, previous_instant)) => {
+                self.megabytes_per_second(total_bytes, previous_bytes, now.duration_since(previous_instant))
+            }
+            // Primeira amostra: ainda não há delta
+            None => 0.0,
+        };
+
+        self.last_network_sample = Some((total_bytes, now));
+        rate
+    }
+
+    /// Leitura e escrita em disco (MB/s) desde a coleta anterior, somando os
+    /// dispositivos. Um disco montado em vários pontos entra uma vez só
+    fn get_disk_io(&mut self) -> (f32, f32) {
+        self.disks.refresh(true);
+
+        let mut devices = HashSet::new();
+        let (read, written) = self.disks.list().iter()
+            .filter(|disk| devices.insert(disk.name().to_os_string()))
+            .map(|disk| disk.usage())
+            .fold((0u64, 0u64), |(read, written), usage| {
+                (read + usage.total_read_bytes, written + usage.total_written_bytes)
+            });
+        let now = Instant::now();
+
+        let rates = match self.last_disk_io_sample {
+            Some((previous_read, previous_written, previous_instant)) => {
+                let elapsed = now.duration_since(previous_instant);
+                (
+                    self.megabytes_per_second(read, previous_read, elapsed),
+                    self.megabytes_per_second(written, previous_written, elapsed),
+                )
+            }
+            None => (0.0, 0.0),
+        };
+
+        self.last_disk_io_sample = Some((read, written, now));
+        rates
+    }
+
+    /// Taxa em MB/s entre dois totais de bytes acumulados
+    fn megabytes_per_second(&self, total: u64, previous: u64, elapsed: Duration) -> f32 {
+        // Usa o tempo real entre amostras; cai no intervalo nominal se for zero
+        let elapsed = if elapsed.is_zero() { self.interval } else { elapsed };
+        // Contadores podem zerar se um dispositivo sumir; evita underflow
+        let delta = total.saturating_sub(previous);
+        delta as f32 / 1_048_576.0 / elapsed.as_secs_f32()
+    }
+
+    /// Temperatura do pacote da CPU quando disponível, senão a maior entre os componentes.
+    /// Retorna `TEMPERATURE_UNAVAILABLE` quando a plataforma não expõe sensores.
+    fn get_temperature(&mut self) -> f32 {
+        self.components.refresh(true);
+
+        let readings = self.components.iter()
+            .filter_map(|component| component.temperature().map(|temp| (component.label(), temp)));
+
+        select_temperature(readings).unwrap_or(TEMPERATURE_UNAVAILABLE)
+    }
+
+    fn get_process_count(&mut self) -> f32 {
+        self.refresh_processes();
+        self.system.processes().len() as f32
+    }
+
+    fn refresh_processes(&mut self) {
+        self.system.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh::process_refresh());
+    }
+
+    /// Processo com maior %CPU desde o último refresh (feito em `get_process_count`
+    /// ou, sem essa métrica, na própria coleta)
+    fn top_process(&self) -> Option<(String, f32)> {
+        self.system.processes()
+            .values()
+            .max_by(|a, b| a.cpu_usage().total_cmp(&b.cpu_usage()))
+            .map(|process| (process.name().to_string_lossy().into_owned(), process.cpu_usage()))
+    }
+}
+
+/// `--print`: a amostra legível, com os mesmos rótulos e unidades do painel do servidor
+fn print_sample(sample: &TelemetrySample) {
+    let (metrics, values) = sample.clone().take_metrics();
+    let mut lines: BTreeMap<String, Value> = metrics.into_iter().map(|(name, value)| (name, Value::from(value))).collect();
+    // Customizadas não-float entram com o valor original
+    lines.extend(values);
+
+    println!("🔎 Amostra coletada:");
+    for (name, value) in &lines {
+        if value.as_f64().is_some_and(|value| is_temperature_unavailable(name, value)) {
+            continue;
+        }
+        println!("   {}", describe_metric(name, value));
+    }
+    if let Some(top) = &sample.top_process {
+        println!("   🔝 Maior consumo: {} ({:.1}% CPU)", top.name, top.cpu);
+    }
+}
+
+/// Identifica interfaces de loopback pelo nome (`lo`, `lo0`, "Loopback ...")
+/// ou por possuírem apenas endereços de loopback
+fn is_loopback_interface(name: &str, data: &NetworkData) -> bool {
+    let by_name = name == "lo"
+        || (name.starts_with("lo") && name[2..].chars().all(|c| c.is_ascii_digit()))
+        || name.to_ascii_lowercase().contains("loopback");
+
+    let networks = data.ip_networks();
+    let by_address = !networks.is_empty() && networks.iter().all(|net| net.addr.is_loopback());
+
+    by_name || by_address
+}
+
+/// Escolhe a temperatura reportada a partir de pares (rótulo, °C):
+/// o sensor do pacote da CPU tem prioridade; na ausência dele, vale a maior leitura
+fn select_temperature<'a>(readings: impl Iterator<Item = (&'a str, f32)>) -> Option<f32> {
+    let mut package: Option<f32> = None;
+    let mut max: Option<f32> = None;
+
+    for (label, temp) in readings.filter(|(_, temp)| temp.is_finite()) {
+        let label = label.to_ascii_lowercase();
+        if label.contains("package") || label.contains("tctl") {
+            package = Some(package.map_or(temp, |current| current.max(temp)));
+        }
+        max = Some(max.map_or(temp, |current| current.max(temp)));
+    }
+
+    package.or(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{collector, queue};
+
+    #[test]
+    fn json_contains_only_selected_metrics() {
+        let args = ["--metrics", "UPTIME,mem,inexistente"].map(String::from);
+        let config = ClientConfig::parse(args.into_iter()).unwrap();
+        let (collectors, metrics) = collector::from_config(&config);
+        let mut sampler = SampleCollector::new(&config, collectors, metrics);
+
+        let mut json = String::new();
+        sampler.build_telemetry_json(&mut json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["MEM", "UPTIME", "timestamp"]);
+    }
+
+    #[test]
+    fn temperature_prefers_cpu_package_over_max() {
+        let readings = [("acpitz temp1", 35.0), ("Package id 0", 61.5), ("nvme Composite", 70.0)];
+        assert_eq!(select_temperature(readings.into_iter()), Some(61.5));
+
+        let readings = [("acpitz temp1", 35.0), ("nvme Composite", 70.0), ("gpu", f32::NAN)];
+        assert_eq!(select_temperature(readings.into_iter()), Some(70.0));
+
+        assert_eq!(select_temperature(std::iter::empty()), None);
+    }
+
+    /// Com o envio travado (ninguém lê a fila), a coleta não bloqueia e só as
+    /// amostras mais novas ficam guardadas
+    #[test]
+    fn stuck_sending_keeps_only_newest_samples() {
+        let args = ["--metrics", "uptime"].map(String::from);
+        let config = ClientConfig::parse(args.into_iter()).unwrap();
+        let (collectors, metrics) = collector::from_config(&config);
+        let mut sampler = SampleCollector::new(&config, collectors, metrics);
+        let (sender, receiver) = queue::bounded(3);
+
+        for _ in 0..5 {
+            assert!(sampler.collect_into(&sender));
+        }
+        assert_eq!(receiver.take_dropped(), 2);
+        assert_eq!(receiver.drain().len(), 3);
+
+        drop(receiver);
+        assert!(!sampler.collect_into(&sender));
+    }
+}
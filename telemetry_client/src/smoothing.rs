@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use telemetry_common::{is_temperature_unavailable, sample::TelemetrySample};
+
+/// Média móvel exponencial por métrica: `s = alpha * x + (1 - alpha) * s_anterior`.
+/// Com `alpha = 1.0` o valor instantâneo passa direto (comportamento original)
+pub struct Smoother {
+    alpha: f32,
+    last: HashMap<&'static str, f32>,
+}
+
+impl Smoother {
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha,
+            last: HashMap::new(),
+        }
+    }
+
+    /// Suaviza as métricas ruidosas da amostra. Contadores e valores absolutos
+    /// (memória, uptime, processos) seguem sem alteração
+    pub fn apply(&mut self, sample: &mut TelemetrySample) {
+        if self.alpha >= 1.0 {
+            return;
+        }
+
+        // A sentinela de temperatura indisponível não pode entrar na média
+        if sample.temp.is_some_and(|temp| is_temperature_unavailable("TEMP", f64::from(temp))) {
+            self.last.remove("TEMP");
+        } else {
+            self.smooth("TEMP", &mut sample.temp);
+        }
+        self.smooth("CPU", &mut sample.cpu);
+        self.smooth("NET", &mut sample.net);
+        self.smooth("GPU_UTIL", &mut sample.gpu_util);
+    }
+
+    fn smooth(&mut self, name: &'static str, value: &mut Option<f32>) {
+        let Some(current) = value.filter(|v| v.is_finite()) else {
+            return;
+        };
+
+        let smoothed = match self.last.get(name) {
+            Some(previous) => self.alpha * current + (1.0 - self.alpha) * previous,
+            None => current,
+        };
+        self.last.insert(name, smoothed);
+        *value = Some(smoothed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smoothed_cpu(alpha: f32, values: &[f32]) -> Vec<f32> {
+        let mut smoother = Smoother::new(alpha);
+        values.iter().map(|value| {
+            let mut sample = TelemetrySample { cpu: Some(*value), ..TelemetrySample::default() };
+            smoother.apply(&mut sample);
+            sample.cpu.unwrap()
+        }).collect()
+    }
+
+    #[test]
+    fn ema_over_known_sequence_and_alpha_one_is_identity() {
+        assert_eq!(smoothed_cpu(0.5, &[0.0, 10.0, 10.0]), [0.0, 5.0, 7.5]);
+        assert_eq!(smoothed_cpu(1.0, &[0.0, 10.0, 3.0]), [0.0, 10.0, 3.0]);
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(target_os = "linux")]
+use std::{fs, path::Path};
+
+use crate::{collector::MetricCollector, config::TcpState};
+
+/// Tabelas de sockets TCP do kernel, IPv4 e IPv6
+#[cfg(target_os = "linux")]
+const PROC_NET_TCP: [&str; 2] = ["/proc/net/tcp", "/proc/net/tcp6"];
+
+/// Código de estado `ESTABLISHED` na coluna `st` de `/proc/net/tcp`
+#[cfg(any(target_os = "linux", test))]
+const ESTABLISHED: &str = "01";
+
+/// Conexões TCP abertas na máquina, em `TCP_CONNS`. Lê `/proc/net/tcp` no
+/// Linux; nas demais plataformas a ausência é avisada uma vez e a métrica
+/// deixa de ser enviada
+pub struct TcpConnectionsCollector {
+    state: TcpState,
+    available: bool,
+}
+
+impl TcpConnectionsCollector {
+    pub fn new(state: TcpState) -> Self {
+        Self { state, available: true }
+    }
+}
+
+impl MetricCollector for TcpConnectionsCollector {
+    fn name(&self) -> &str {
+        "TCP_CONNS"
+    }
+
+    fn collect(&mut self) -> Option<f32> {
+        if !self.available {
+            return None;
+        }
+
+        let count = count_platform(self.state);
+        if count.is_none() {
+            println!("ℹ️ Tabela de conexões TCP indisponível: métrica TCP_CONNS desativada");
+            self.available = false;
+        }
+        count.map(|count| count as f32)
+    }
+}
+
+/// Soma das tabelas IPv4 e IPv6; basta uma existir (kernel sem IPv6)
+#[cfg(target_os = "linux")]
+fn count_platform(state: TcpState) -> Option<usize> {
+    let counts: Vec<usize> = PROC_NET_TCP.iter()
+        .filter_map(|path| fs::read_to_string(Path::new(path)).ok())
+        .map(|table| count_connections(&table, state))
+        .collect();
+    (!counts.is_empty()).then(|| counts.iter().sum())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn count_platform(_state: TcpState) -> Option<usize> {
+    None
+}
+
+/// Linhas de uma tabela no formato de `/proc/net/tcp` no estado pedido; a
+/// primeira linha é o cabeçalho e o estado fica na quarta coluna, em hexa
+#[cfg(any(target_os = "linux", test))]
+fn count_connections(table: &str, state: TcpState) -> usize {
+    table.lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(3))
+        .filter(|st| state == TcpState::All || st.eq_ignore_ascii_case(ESTABLISHED))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 12345 1
+   1: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 12346 1
+   2: 0100007F:C350 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 12347 1
+   3: 0100007F:C352 0100007F:1F90 06 00000000:00000000 03:00001770 00000000     0        0 0 3
+";
+
+    #[test]
+    fn counts_established_or_all_connections() {
+        assert_eq!(count_connections(TABLE, TcpState::Established), 2);
+        assert_eq!(count_connections(TABLE, TcpState::All), 4);
+        assert_eq!(count_connections(TABLE.lines().next().unwrap(), TcpState::All), 0);
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    io::{self, Read, Result as IoResult, Write},
+    net::{SocketAddr, TcpStream, UdpSocket},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use ring::digest::{digest, SHA256};
+use telemetry_common::UNIX_ADDRESS_PREFIX;
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    client::WebPkiServerVerifier,
+    crypto::{verify_tls12_signature, verify_tls13_signature, WebPkiSupportedAlgorithms},
+    pki_types::{pem::PemObject, CertificateDer, ServerName, UnixTime},
+    ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme, StreamOwned,
+};
+
+/// Conexão com o servidor: TCP puro (padrão), TLS sobre TCP, UDP ou Unix domain socket
+pub enum Transport {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+    /// Socket UDP conectado: cada escrita vira um datagrama
+    Udp(UdpSocket),
+    /// Servidor na mesma máquina (`unix:/caminho`), com o mesmo protocolo do TCP
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Read for Transport {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        match self {
+            Transport::Plain(stream) => stream.read(buf),
+            Transport::Tls(stream) => stream.read(buf),
+            Transport::Udp(socket) => socket.recv(buf),
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Transport {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        match self {
+            Transport::Plain(stream) => stream.write(buf),
+            Transport::Tls(stream) => stream.write(buf),
+            Transport::Udp(socket) => socket.send(buf),
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        match self {
+            Transport::Plain(stream) => stream.flush(),
+            Transport::Tls(stream) => stream.flush(),
+            Transport::Udp(_) => Ok(()),
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+impl Transport {
+    /// Limita quanto uma leitura pode bloquear (usado para escutar comandos do servidor)
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> IoResult<()> {
+        match self {
+            Transport::Plain(stream) => stream.set_read_timeout(timeout),
+            Transport::Tls(stream) => stream.sock.set_read_timeout(timeout),
+            Transport::Udp(socket) => socket.set_read_timeout(timeout),
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+}
+
+/// Parâmetros TLS do cliente, montados uma vez e reaproveitados nas reconexões
+pub struct TlsSettings {
+    config: Arc<rustls::ClientConfig>,
+    server_name: ServerName<'static>,
+}
+
+impl TlsSettings {
+    /// Valida o servidor contra a CA informada e/ou contra o fingerprint SHA-256
+    /// do certificado (pinning). Ao menos um dos dois é obrigatório.
+    pub fn new(ca_path: Option<&Path>, pin: Option<&str>, server_name: &str) -> IoResult<Self> {
+        let server_name = ServerName::try_from(server_name.to_string())
+            .map_err(|e| invalid_input(format!("nome TLS inválido '{}': {}", server_name, e)))?;
+
+        let webpki = match ca_path {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in CertificateDer::pem_file_iter(path).map_err(io::Error::other)? {
+                    roots.add(cert.map_err(io::Error::other)?).map_err(io::Error::other)?;
+                }
+                Some(WebPkiServerVerifier::builder(Arc::new(roots)).build().map_err(io::Error::other)?)
+            }
+            None => None,
+        };
+
+        let builder = rustls::ClientConfig::builder();
+        let config = match (pin, webpki) {
+            (Some(pin), webpki) => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinnedVerifier {
+                    inner: webpki,
+                    pin: parse_fingerprint(pin)?,
+                    algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+                }))
+                .with_no_client_auth(),
+            (None, Some(webpki)) => builder.with_webpki_verifier(webpki).with_no_client_auth(),
+            (None, None) => return Err(invalid_input("TLS exige --tls-ca ou --tls-pin".to_string())),
+        };
+
+        Ok(Self {
+            config: Arc::new(config),
+            server_name,
+        })
+    }
+}
+
+/// Abre a conexão TCP e, se configurado, a envolve numa sessão TLS. Um servidor
+/// que não responde (firewall descartando pacotes) falha com `TimedOut` após
+/// `timeout`, tanto no TCP quanto no handshake TLS. O mesmo limite vale para cada
+/// escrita depois, para um servidor que parou de ler derrubar a conexão em vez de
+/// travar o envio. Endereços `unix:/caminho` abrem um Unix domain socket
+pub fn connect(address: &str, tls: Option<&TlsSettings>, timeout: Duration) -> IoResult<Transport> {
+    if let Some(path) = address.strip_prefix(UNIX_ADDRESS_PREFIX) {
+        return connect_unix(path, timeout);
+    }
+    let target: SocketAddr = address.parse()
+        .map_err(|e| invalid_input(format!("endereço inválido: {}", e)))?;
+    let stream = TcpStream::connect_timeout(&target, timeout).map_err(|e| timeout_error(e, timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let Some(tls) = tls else {
+        return Ok(Transport::Plain(stream));
+    };
+
+    stream.set_read_timeout(Some(timeout))?;
+
+    let connection = ClientConnection::new(Arc::clone(&tls.config), tls.server_name.clone())
+        .map_err(io::Error::other)?;
+    let mut stream = StreamOwned::new(connection, stream);
+
+    // Conclui o handshake já na conexão para que falhas de certificado apareçam aqui
+    while stream.conn.is_handshaking() {
+        stream.conn.complete_io(&mut stream.sock).map_err(|e| timeout_error(e, timeout))?;
+    }
+    stream.sock.set_read_timeout(None)?;
+
+    Ok(Transport::Tls(Box::new(stream)))
+}
+
+#[cfg(unix)]
+fn connect_unix(path: &str, timeout: Duration) -> IoResult<Transport> {
+    let stream = UnixStream::connect(path)?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(Transport::Unix(stream))
+}
+
+#[cfg(not(unix))]
+fn connect_unix(_path: &str, _timeout: Duration) -> IoResult<Transport> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Unix domain sockets não são suportados nesta plataforma"))
+}
+
+/// "Conecta" um socket UDP ao servidor: só fixa o destino, nada é trocado na rede
+pub fn connect_udp(address: &str) -> IoResult<Transport> {
+    let target: SocketAddr = address.parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("endereço inválido: {}", e)))?;
+    let local = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(target)?;
+    Ok(Transport::Udp(socket))
+}
+
+/// Aceita o certificado somente se o SHA-256 do certificado final bater com o pin
+#[derive(Debug)]
+struct PinnedVerifier {
+    /// Validação de cadeia adicional quando uma CA também foi informada
+    inner: Option<Arc<WebPkiServerVerifier>>,
+    pin: [u8; 32],
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Some(inner) = &self.inner {
+            inner.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+        }
+
+        if digest(&SHA256, end_entity.as_ref()).as_ref() == self.pin {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General("fingerprint do certificado não confere com o pin".to_string()))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// Aceita o fingerprint em hex, com ou sem `:` separando os bytes
+fn parse_fingerprint(raw: &str) -> IoResult<[u8; 32]> {
+    let hex: String = raw.chars().filter(|c| *c != ':').collect();
+    let invalid = || invalid_input(format!("pin SHA-256 inválido: {}", raw));
+
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut pin = [0u8; 32];
+    for (byte, chunk) in pin.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(chunk).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(pin)
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Troca o erro genérico de timeout por um que diz quanto se esperou
+fn timeout_error(e: io::Error, timeout: Duration) -> io::Error {
+    match e.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("sem resposta do servidor em {:.1}s (inacessível ou pacotes descartados)", timeout.as_secs_f32()),
+        ),
+        _ => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn unroutable_address_fails_within_timeout() {
+        let timeout = Duration::from_millis(300);
+        let started = Instant::now();
+
+        // Faixa reservada para documentação (TEST-NET-1): nada responde
+        let result = connect("192.0.2.1:8080", None, timeout);
+        assert!(result.is_err());
+        assert!(started.elapsed() < timeout + Duration::from_secs(2), "levou {:?}", started.elapsed());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn connects_to_unix_socket() {
+        let path = std::env::temp_dir().join(format!("telemetry-client-{}.sock", std::process::id()));
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+        let mut transport = connect(&format!("unix:{}", path.display()), None, Duration::from_millis(100)).unwrap();
+        transport.write_all(b"{}\n").unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        let mut received = [0u8; 3];
+        server.read_exact(&mut received).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(&received, b"{}\n");
+    }
+
+    #[test]
+    fn rejects_unparseable_address() {
+        let error = connect("servidor:8080", None, Duration::from_millis(100)).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
+
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::sample::core_index;
+
+/// Visão consolidada da frota a partir da última amostra de cada cliente
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FleetSummary {
+    /// Clientes considerados
+    pub clients: usize,
+    /// Média da CPU entre os clientes que a reportam
+    pub cpu_avg: Option<f32>,
+    /// Memória em uso somada, em bytes (clientes com `MEM`)
+    pub mem_total: Option<f32>,
+    /// Média da memória em % (clientes com `--mem-mode percent`)
+    pub mem_percent_avg: Option<f32>,
+    /// Clientes com CPU acima do limiar
+    pub above_threshold: usize,
+}
+
+impl FleetSummary {
+    pub fn from_samples<'a>(samples: impl IntoIterator<Item = &'a HashMap<String, f32>>, cpu_threshold: f32) -> Self {
+        let mut summary = Self::default();
+        let mut cpus = Vec::new();
+        let mut mem_percents = Vec::new();
+
+        for metrics in samples {
+            summary.clients += 1;
+            if let Some(cpu) = client_cpu(metrics) {
+                if cpu > cpu_threshold {
+                    summary.above_threshold += 1;
+                }
+                cpus.push(cpu);
+            }
+            if let Some(mem) = metrics.get("MEM").filter(|mem| mem.is_finite()) {
+                *summary.mem_total.get_or_insert(0.0) += mem;
+            }
+            if let Some(percent) = metrics.get("MEM_PERCENT") {
+                mem_percents.push(*percent);
+            }
+        }
+
+        summary.cpu_avg = average(&cpus);
+        summary.mem_percent_avg = average(&mem_percents);
+        summary
+    }
+}
+
+/// `CPU` do cliente ou, em `--cpu-mode per-core`, a média dos núcleos
+fn client_cpu(metrics: &HashMap<String, f32>) -> Option<f32> {
+    if let Some(cpu) = metrics.get("CPU") {
+        return Some(*cpu);
+    }
+    let cores: Vec<f32> = metrics.iter()
+        .filter(|(name, _)| core_index(name).is_some())
+        .map(|(_, value)| *value)
+        .collect();
+    average(&cores)
+}
+
+/// Média dos valores finitos; NaN/Inf não contaminam o resultado
+fn average(values: &[f32]) -> Option<f32> {
+    let finite: Vec<f32> = values.iter().copied().filter(|value| value.is_finite()).collect();
+    if finite.is_empty() {
+        return None;
+    }
+    Some(finite.iter().sum::<f32>() / finite.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(pairs: &[(&str, f32)]) -> HashMap<String, f32> {
+        pairs.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+    }
+
+    #[test]
+    fn consolidates_cpu_memory_and_threshold() {
+        let samples = [
+            metrics(&[("CPU", 90.0), ("MEM", 1_073_741_824.0)]),
+            metrics(&[("CPU", 30.0), ("MEM", 2_147_483_648.0)]),
+            metrics(&[("CPU_0", 100.0), ("CPU_1", 80.0), ("MEM_PERCENT", 50.0)]),
+        ];
+        let summary = FleetSummary::from_samples(&samples, 80.0);
+
+        assert_eq!(summary.clients, 3);
+        assert_eq!(summary.cpu_avg, Some(70.0));
+        assert_eq!(summary.mem_total, Some(3_221_225_472.0));
+        assert_eq!(summary.mem_percent_avg, Some(50.0));
+        assert_eq!(summary.above_threshold, 2);
+    }
+
+    #[test]
+    fn ignores_non_finite_values() {
+        let samples = [
+            metrics(&[("CPU", f32::NAN), ("MEM", f32::INFINITY)]),
+            metrics(&[("CPU", 40.0), ("MEM", 1024.0), ("MEM_PERCENT", f32::NEG_INFINITY)]),
+        ];
+        let summary = FleetSummary::from_samples(&samples, 80.0);
+
+        assert_eq!(summary.cpu_avg, Some(40.0));
+        assert_eq!(summary.mem_total, Some(1024.0));
+        assert_eq!(summary.mem_percent_avg, None);
+    }
+
+    #[test]
+    fn empty_fleet_has_no_averages() {
+        let summary = FleetSummary::from_samples(&[metrics(&[("DISK", 10.0)])], 80.0);
+        assert_eq!(summary.clients, 1);
+        assert_eq!(summary.cpu_avg, None);
+        assert_eq!(summary.mem_total, None);
+        assert_eq!(summary.above_threshold, 0);
+    }
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+
+/// Maior linha pendente aceita antes de descartar o que chegou do servidor
+const MAX_PENDING_BYTES: usize = 64 * 1024;
+
+/// Comandos de controle enviados pelo servidor na mesma conexão, uma linha JSON
+/// cada, com o nome em `cmd` e os parâmetros no mesmo nível. Clientes ignoram
+/// (com um aviso) nomes que não conhecem e campos extras, então comandos novos
+/// não quebram versões antigas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ServerCommand {
+    /// `{"cmd":"collect_now"}`: envia uma amostra imediatamente
+    CollectNow,
+    /// `{"cmd":"set_interval","interval_ms":500}`: muda o intervalo de coleta
+    SetInterval { interval_ms: u64 },
+    /// `{"cmd":"set_gpu","enabled":false}`: liga ou desliga a coleta de GPU
+    SetGpu { enabled: bool },
+    /// `{"cmd":"accept_batch","max_bytes":65536}`: resposta ao handshake com
+    /// `"batch": true`; a partir dela o cliente pode mandar várias amostras num
+    /// array JSON de até `max_bytes` (servidores antigos não informam o limite)
+    AcceptBatch {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_bytes: Option<usize>,
+    },
+    /// `{"cmd":"pong","nonce":7}`: eco do `Ping` de mesmo nonce, para o cliente medir o RTT
+    Pong { nonce: u64 },
+    /// `{"cmd":"clock","server_time":...,"offset_ms":...}`: resposta ao handshake com
+    /// `"clock"`, com o relógio do servidor e a diferença estimada (positiva quando o
+    /// cliente está adiantado)
+    Clock { server_time: u64, offset_ms: i64 },
+    /// Comando que esta versão do cliente não conhece
+    #[serde(skip)]
+    Unknown(String),
+}
+
+/// Nomes em `cmd` dos comandos conhecidos
+const KNOWN_COMMANDS: &[&str] = &["collect_now", "set_interval", "set_gpu", "accept_batch", "pong", "clock"];
+
+impl ServerCommand {
+    pub fn name(&self) -> &str {
+        match self {
+            ServerCommand::CollectNow => "collect_now",
+            ServerCommand::SetInterval { .. } => "set_interval",
+            ServerCommand::SetGpu { .. } => "set_gpu",
+            ServerCommand::AcceptBatch { .. } => "accept_batch",
+            ServerCommand::Pong { .. } => "pong",
+            ServerCommand::Clock { .. } => "clock",
+            ServerCommand::Unknown(name) => name,
+        }
+    }
+
+    /// Linha JSON (sem o `\n`) enviada ao cliente
+    pub fn to_json(&self) -> String {
+        match self {
+            ServerCommand::Unknown(name) => serde_json::json!({ "cmd": name }).to_string(),
+            command => serde_json::to_string(command).unwrap_or_default(),
+        }
+    }
+
+    /// Interpreta uma linha já sabendo o nome em `cmd`; parâmetros ausentes
+    /// ou inválidos num comando conhecido são erro
+    fn parse(line: &str, cmd: String) -> Result<Self, String> {
+        if !KNOWN_COMMANDS.contains(&cmd.as_str()) {
+            return Ok(ServerCommand::Unknown(cmd));
+        }
+        serde_json::from_str(line).map_err(|e| format!("Comando '{}' com parâmetros inválidos ({}): {}", cmd, e, line))
+    }
+}
+
+/// Ping de aplicação do cliente: `{"ping": nonce, "rtt_ms": último RTT medido}`.
+/// Só é enviado a servidores que responderam o nonce do handshake com `pong`;
+/// o servidor ecoa o nonce e exibe `rtt_ms` como a métrica sintética `RTT_MS`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ping {
+    pub ping: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rtt_ms: Option<f32>,
+}
+
+impl Ping {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Reconhece um ping pelo início da mensagem, sem tentar o parse de cada amostra
+    pub fn parse(message: &str) -> Option<Self> {
+        if !message.trim_start().starts_with("{\"ping\"") {
+            return None;
+        }
+        serde_json::from_str(message).ok()
+    }
+}
+
+/// Linha JSON (sem o `\n`) com que o servidor recusa uma conexão
+pub fn rejection_json(reason: &str) -> String {
+    serde_json::json!({ "error": reason }).to_string()
+}
+
+/// O que uma linha do servidor significa para o cliente
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    Command(ServerCommand),
+    /// `{"error": ...}`: o servidor recusou a conexão
+    Rejected(String),
+    /// Linha que não é um comando válido, com a descrição do problema
+    Invalid(String),
+}
+
+/// `{"cmd": ...}` ou, quando o servidor recusa a conexão, `{"error": ...}`
+#[derive(Deserialize)]
+struct CommandMessage {
+    #[serde(default)]
+    cmd: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Acumula os bytes recebidos do servidor e separa as mensagens completas
+#[derive(Default)]
+pub struct CommandInbox {
+    pending: Vec<u8>,
+}
+
+impl CommandInbox {
+    /// Descarta o resto de uma conexão anterior
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Acrescenta `data` e devolve as mensagens das linhas que ficaram completas
+    pub fn push(&mut self, data: &[u8]) -> Vec<ServerMessage> {
+        self.pending.extend_from_slice(data);
+
+        let mut messages = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            messages.push(match serde_json::from_str::<CommandMessage>(line) {
+                Ok(CommandMessage { error: Some(error), .. }) => ServerMessage::Rejected(error),
+                Ok(CommandMessage { cmd: Some(cmd), .. }) => match ServerCommand::parse(line, cmd) {
+                    Ok(command) => ServerMessage::Command(command),
+                    Err(description) => ServerMessage::Invalid(description),
+                },
+                Ok(_) => ServerMessage::Invalid(format!("Mensagem do servidor sem comando: {}", line)),
+                Err(e) => ServerMessage::Invalid(format!("Mensagem inválida do servidor ({}): {}", e, line)),
+            });
+        }
+
+        if self.pending.len() > MAX_PENDING_BYTES {
+            messages.push(ServerMessage::Invalid(format!(
+                "Mensagem do servidor sem fim de linha descartada ({} bytes)",
+                self.pending.len()
+            )));
+            self.pending.clear();
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_commands_across_reads() {
+        let mut inbox = CommandInbox::default();
+        let line = format!("{}\n", ServerCommand::CollectNow.to_json());
+        let (head, tail) = line.split_at(5);
+
+        assert!(inbox.push(head.as_bytes()).is_empty());
+        assert_eq!(
+            inbox.push(format!("{}{{\"cmd\":\"reboot\"}}\n", tail).as_bytes()),
+            vec![
+                ServerMessage::Command(ServerCommand::CollectNow),
+                ServerMessage::Command(ServerCommand::Unknown("reboot".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn control_commands_roundtrip() {
+        let commands = [
+            ServerCommand::SetInterval { interval_ms: 500 },
+            ServerCommand::SetGpu { enabled: false },
+            ServerCommand::AcceptBatch { max_bytes: Some(65536) },
+            ServerCommand::Pong { nonce: 7 },
+            ServerCommand::Unknown("reboot".to_string()),
+        ];
+        let data: String = commands.iter().map(|command| format!("{}\n", command.to_json())).collect();
+        assert!(data.starts_with(r#"{"cmd":"set_interval","interval_ms":500}"#));
+
+        let mut inbox = CommandInbox::default();
+        let parsed: Vec<_> = commands.into_iter().map(ServerMessage::Command).collect();
+        assert_eq!(inbox.push(data.as_bytes()), parsed);
+
+        // Servidor anterior ao limite anunciado
+        assert_eq!(
+            inbox.push(b"{\"cmd\":\"accept_batch\"}\n"),
+            [ServerMessage::Command(ServerCommand::AcceptBatch { max_bytes: None })]
+        );
+    }
+
+    #[test]
+    fn ignores_extra_fields_and_rejects_missing_parameters() {
+        let mut inbox = CommandInbox::default();
+        let messages = inbox.push(b"{\"cmd\":\"collect_now\",\"since\":\"v2\"}\n{\"cmd\":\"set_interval\"}\n");
+        assert_eq!(messages[0], ServerMessage::Command(ServerCommand::CollectNow));
+        assert!(matches!(messages[1], ServerMessage::Invalid(_)));
+    }
+
+    #[test]
+    fn ping_roundtrip_and_detection() {
+        let ping = Ping { ping: 42, rtt_ms: Some(1.5) };
+        assert_eq!(Ping::parse(&ping.to_json()), Some(ping));
+        assert_eq!(Ping::parse(r#"{"ping": 1}"#), Some(Ping { ping: 1, rtt_ms: None }));
+        assert_eq!(Ping::parse(r#"{"CPU": 1.0, "ping": 1}"#), None);
+        assert_eq!(Ping::parse(r#"{"ping": "x"}"#), None);
+    }
+
+    #[test]
+    fn reports_rejections_and_garbage() {
+        let mut inbox = CommandInbox::default();
+        let data = format!("{}\n\nnão é json\n{{}}\n", rejection_json("limite atingido"));
+        let messages = inbox.push(data.as_bytes());
+
+        assert_eq!(messages[0], ServerMessage::Rejected("limite atingido".to_string()));
+        assert!(matches!(messages[1], ServerMessage::Invalid(_)));
+        assert!(matches!(messages[2], ServerMessage::Invalid(_)));
+        assert_eq!(messages.len(), 3);
+    }
+
+    #[test]
+    fn discards_unterminated_line_above_limit() {
+        let mut inbox = CommandInbox::default();
+        let messages = inbox.push(&vec![b'x'; MAX_PENDING_BYTES + 1]);
+        assert!(matches!(messages.as_slice(), [ServerMessage::Invalid(_)]));
+
+        inbox.push(b"{\"cmd\":");
+        inbox.clear();
+        assert!(inbox.push(b"\n").is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+use std::io::{self, Read, Result as IoResult, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+/// Byte enviado antes do handshake por clientes que compactam cada frame com gzip
+pub const MODE_GZIP: u8 = 0x01;
+
+pub fn compress(payload: &[u8]) -> IoResult<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(payload.len() / 2), Compression::default());
+    encoder.write_all(payload)?;
+    encoder.finish()
+}
+
+/// Descompacta um frame, recusando payloads que expandam além de `limit` bytes
+pub fn decompress(payload: &[u8], limit: usize) -> IoResult<String> {
+    let mut text = String::new();
+    GzDecoder::new(payload)
+        .take(limit as u64 + 1)
+        .read_to_string(&mut text)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("gzip inválido: {}", e)))?;
+
+    if text.len() > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame descompactado excede o limite de {} bytes", limit),
+        ));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let message = r#"{"CPU":42.5,"MEM":1024}"#.repeat(10);
+        let compressed = compress(message.as_bytes()).unwrap();
+        assert!(compressed.len() < message.len());
+        assert_eq!(decompress(&compressed, 1024).unwrap(), message);
+    }
+
+    #[test]
+    fn rejects_payload_above_limit() {
+        let compressed = compress(&[b'a'; 4096]).unwrap();
+        let err = decompress(&compressed, 1024).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_invalid_gzip() {
+        assert!(decompress(b"not gzip", 1024).is_err());
+    }
+}
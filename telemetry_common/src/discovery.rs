@@ -0,0 +1,79 @@
+use std::net::{IpAddr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+/// Porta UDP conhecida em que os servidores escutam pedidos de descoberta
+pub const DISCOVERY_PORT: u16 = 8099;
+
+/// Datagrama enviado em broadcast pelo cliente sem endereço configurado
+pub const DISCOVERY_REQUEST: &str = r#"{"discover":"telemetry"}"#;
+
+/// Resposta do servidor ao pedido de descoberta, com a porta e o modo do listener
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveryReply {
+    /// IP do listener quando ele não escuta em todas as interfaces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<IpAddr>,
+    pub port: u16,
+    /// `"tcp"` ou `"udp"`, como o `transport` do servidor
+    pub transport: String,
+    #[serde(default)]
+    pub tls: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplyMessage {
+    telemetry_server: DiscoveryReply,
+}
+
+impl DiscoveryReply {
+    /// `{"telemetry_server":{"port":8080,"transport":"tcp","tls":false}}`
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&ReplyMessage { telemetry_server: self.clone() }).unwrap_or_default()
+    }
+
+    /// Endereço do servidor: o IP anunciado ou, sem ele, o de origem da resposta
+    pub fn server_address(&self, from: SocketAddr) -> SocketAddr {
+        SocketAddr::new(self.ip.unwrap_or(from.ip()), self.port)
+    }
+
+    /// `None` para qualquer datagrama que não seja uma resposta de descoberta
+    pub fn parse(datagram: &[u8]) -> Option<Self> {
+        serde_json::from_slice::<ReplyMessage>(datagram).ok().map(|message| message.telemetry_server)
+    }
+}
+
+/// Datagrama recebido pelo servidor é um pedido de descoberta
+pub fn is_request(datagram: &[u8]) -> bool {
+    std::str::from_utf8(datagram).is_ok_and(|text| text.trim() == DISCOVERY_REQUEST)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reply_round_trips() {
+        let reply = DiscoveryReply { ip: None, port: 8080, transport: "tcp".to_string(), tls: true };
+        assert_eq!(DiscoveryReply::parse(reply.to_json().as_bytes()), Some(reply));
+        assert_eq!(DiscoveryReply::parse(br#"{"CPU": 1.0}"#), None);
+    }
+
+    #[test]
+    fn announced_ip_takes_precedence_over_sender() {
+        let from: SocketAddr = "192.168.0.10:40000".parse().unwrap();
+        let mut reply = DiscoveryReply { ip: None, port: 8080, transport: "tcp".to_string(), tls: false };
+        assert_eq!(reply.server_address(from), "192.168.0.10:8080".parse().unwrap());
+
+        reply.ip = Some("10.0.0.5".parse().unwrap());
+        assert_eq!(reply.server_address(from), "10.0.0.5:8080".parse().unwrap());
+    }
+
+    #[test]
+    fn recognizes_only_the_request() {
+        assert!(is_request(DISCOVERY_REQUEST.as_bytes()));
+        assert!(is_request(format!("{}\n", DISCOVERY_REQUEST).as_bytes()));
+        assert!(!is_request(br#"{"discover":"outro"}"#));
+        assert!(!is_request(b"\xff"));
+    }
+}
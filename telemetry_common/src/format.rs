@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{labels, sample::{core_index, socket_index}, value::Value};
+
+/// Formatação de uma métrica customizada declarada em `[units.NOME]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricUnit {
+    /// Texto após o valor, ex.: `"RPM"` ou `"%"`
+    #[serde(default)]
+    pub suffix: String,
+    #[serde(default = "default_decimals")]
+    pub decimals: usize,
+    /// Rótulo exibido no painel no lugar do nome cru
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+fn default_decimals() -> usize {
+    2
+}
+
+impl MetricUnit {
+    pub fn format(&self, value: f32) -> String {
+        match self.suffix.as_str() {
+            "" => format!("{:.*}", self.decimals, value),
+            // Sufixos que se leem colados ao número
+            "%" | "°C" | "°F" => format!("{:.*}{}", self.decimals, value, self.suffix),
+            suffix => format!("{:.*} {}", self.decimals, value, suffix),
+        }
+    }
+}
+
+/// Formata o valor de uma métrica já com a unidade
+pub type FormatFn = Arc<dyn Fn(f32) -> String + Send + Sync>;
+
+/// Rótulo e formatação de uma métrica conhecida
+#[derive(Clone)]
+pub struct Formatter {
+    /// Rótulo com emoji exibido antes do valor; sem rótulo, `📈` e o nome cru
+    pub label: Option<String>,
+    pub format: FormatFn,
+}
+
+/// Formatadores por nome de métrica em maiúsculas. Uma métrica nova ganha rótulo
+/// e unidade com `register`, sem mexer no fallback genérico; o servidor monta a
+/// sua no startup com as embutidas e as de `[units]` (`with_units`)
+#[derive(Clone, Default)]
+pub struct FormatterTable {
+    entries: HashMap<String, Formatter>,
+}
+
+impl FormatterTable {
+    /// Métricas coletadas pelo cliente e sintéticas do servidor
+    pub fn builtin() -> Self {
+        let mut table = Self::default();
+        table.register(&["CPU"], "🖥️  CPU", percent_1);
+        table.register(&["CPU_FREQ"], "⚡ Clock", |value| format!("{:.0} MHz", value));
+        table.register(&["MEM", "MEMORY"], "💾 Memória", format_bytes);
+        table.register(&["MEM_PERCENT"], "💾 Memória", percent_1);
+        table.register(&["SWAP"], "🔁 Swap", percent_1);
+        table.register(&["DISK", "STORAGE"], "💿 Disco", percent_1);
+        table.register(&["DISK_READ"], "📖 Leitura de disco", megabytes_per_second);
+        table.register(&["DISK_WRITE"], "📝 Escrita de disco", megabytes_per_second);
+        table.register(&["NETWORK", "NET"], "🌐 Rede", megabytes_per_second);
+        table.register(&["TEMPERATURE", "TEMP"], "🌡️  Temperatura", |value| format!("{:.1}°C", value));
+        table.register(&["PROC_COUNT"], "⚙️  Processos", |value| format!("{:.0}", value));
+        table.register(&["TCP_CONNS"], "🔗 Conexões TCP", |value| format!("{:.0}", value));
+        table.register(&["UPTIME"], "⏱️  Uptime", format_uptime);
+        table.register(&["GPU_UTIL"], "🎮 GPU", percent_0);
+        // nvidia-smi reporta a memória de vídeo em MiB
+        table.register(&["GPU_MEM"], "🎮 Memória GPU", |value| match value {
+            value if value >= 1_024.0 => format!("{:.2} GB", value / 1_024.0),
+            value => format!("{:.0} MB", value),
+        });
+        table.register(&["BATTERY"], "🔋 Bateria", percent_0);
+        table.register(&["BATTERY_CHARGING"], "🔌 Carregando", |value| {
+            if value > 0.0 { "sim" } else { "não" }.to_string()
+        });
+        table.register(&["RTT_MS"], "📶 RTT", |value| format!("{:.1} ms", value));
+        table
+    }
+
+    /// Embutidas mais as métricas de `[units]`; as embutidas têm precedência
+    pub fn with_units(units: &HashMap<String, MetricUnit>) -> Self {
+        let mut table = Self::builtin();
+        for (name, unit) in units {
+            let upper = name.to_uppercase();
+            if table.entries.contains_key(&upper) {
+                continue;
+            }
+            let label = unit.label.as_ref().map(|label| format!("📈 {}", label));
+            let unit = unit.clone();
+            table.entries.insert(upper, Formatter { label, format: Arc::new(move |value| unit.format(value)) });
+        }
+        table
+    }
+
+    /// Registra (ou substitui) o formatador de uma métrica e seus apelidos
+    pub fn register(&mut self, names: &[&str], label: &str, format: impl Fn(f32) -> String + Send + Sync + 'static) {
+        let formatter = Formatter { label: Some(label.to_string()), format: Arc::new(format) };
+        for name in names {
+            self.entries.insert(name.to_uppercase(), formatter.clone());
+        }
+    }
+
+    pub fn get(&self, upper: &str) -> Option<&Formatter> {
+        self.entries.get(upper)
+    }
+
+    /// Rótulo exibido antes do valor; `upper` é o nome já em maiúsculas
+    pub fn label(&self, upper: &str, name: &str) -> String {
+        if let Some(label) = self.get(upper).and_then(|formatter| formatter.label.as_ref()) {
+            return label.clone();
+        }
+        match socket_index(upper) {
+            Some(socket) => format!("🖥️  CPU socket {}", socket),
+            None => format!("📈 {}", name),
+        }
+    }
+
+    /// Valor com a unidade. Cores e sockets (`CPU_3`, `CPU_SOCKET_1`) seguem a `CPU`;
+    /// métricas sem formatador usam o formato genérico
+    pub fn format(&self, upper: &str, value: f32) -> String {
+        let formatter = self.get(upper)
+            .or_else(|| core_index(upper).or(socket_index(upper)).and_then(|_| self.get("CPU")));
+        match formatter {
+            Some(formatter) => (formatter.format)(value),
+            None => format!("{:.2}", value),
+        }
+    }
+
+    /// Valor original de uma métrica customizada: inteiros sem arredondamento (a não
+    /// ser que haja formatador, como os de `[units]`), booleanos como sim/não e texto
+    /// como veio
+    pub fn format_raw(&self, upper: &str, value: &Value) -> String {
+        match value {
+            Value::Number(number) => self.format(upper, *number as f32),
+            Value::Int(int) if self.get(upper).is_some() => self.format(upper, *int as f32),
+            other => other.to_string(),
+        }
+    }
+
+    /// Linha `rótulo: valor` de uma métrica, como o painel do servidor e o `--print`
+    /// do cliente exibem; texto vem com o nome cru. Séries com labels usam o rótulo e
+    /// a unidade da métrica pai, com as dimensões entre colchetes
+    pub fn describe(&self, name: &str, value: &Value) -> String {
+        if let Value::Text(text) = value {
+            return format!("📝 {}: {}", name, text);
+        }
+        let series = labels::split_series(name);
+        let base = series.as_ref().map_or(name, |series| series.name);
+        let upper = base.to_uppercase();
+        let label = match &series {
+            Some(series) => format!("{} [{}]", self.label(&upper, base), series.describe_labels()),
+            None => self.label(&upper, base),
+        };
+        format!("{}: {}", label, self.format_raw(&upper, value))
+    }
+}
+
+/// Tabela embutida, montada no primeiro uso
+pub fn builtin_formatters() -> &'static FormatterTable {
+    static BUILTIN: OnceLock<FormatterTable> = OnceLock::new();
+    BUILTIN.get_or_init(FormatterTable::builtin)
+}
+
+fn percent_0(value: f32) -> String {
+    format!("{:.0}%", value)
+}
+
+fn percent_1(value: f32) -> String {
+    format!("{:.1}%", value)
+}
+
+fn megabytes_per_second(value: f32) -> String {
+    format!("{:.2} MB/s", value)
+}
+
+/// Rótulo com emoji exibido antes do valor, pela tabela embutida
+pub fn metric_label(upper: &str, name: &str) -> String {
+    builtin_formatters().label(upper, name)
+}
+
+/// Valor formatado com a unidade da métrica, pela tabela embutida
+pub fn format_value(upper: &str, value: f32) -> String {
+    builtin_formatters().format(upper, value)
+}
+
+/// Linha `rótulo: valor` pela tabela embutida (`FormatterTable::describe`)
+pub fn describe_metric(name: &str, value: &Value) -> String {
+    builtin_formatters().describe(name, value)
+}
+
+/// Bytes em GB/MB/KB (base 1024); o `sysinfo` reporta memória em bytes desde a 0.30
+pub fn format_bytes(bytes: f32) -> String {
+    if bytes >= 1_073_741_824.0 {
+        format!("{:.2} GB", bytes / 1_073_741_824.0)
+    } else if bytes >= 1_048_576.0 {
+        format!("{:.2} MB", bytes / 1_048_576.0)
+    } else if bytes >= 1_024.0 {
+        format!("{:.2} KB", bytes / 1_024.0)
+    } else {
+        format!("{:.0} B", bytes)
+    }
+}
+
+/// Uptime legível (`2d 3h 14m`). O `f32` do protocolo perde precisão em uptimes
+/// altos, então o valor é arredondado para o minuto mais próximo
+pub fn format_uptime(seconds: f32) -> String {
+    let minutes = (seconds.max(0.0) / 60.0).round() as u64;
+    let (days, hours, minutes) = (minutes / 1440, minutes % 1440 / 60, minutes % 60);
+
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpm_units() -> FormatterTable {
+        let unit = MetricUnit { suffix: "RPM".to_string(), decimals: 0, label: Some("Ventoinha".to_string()) };
+        FormatterTable::with_units(&HashMap::from([("FAN_RPM".to_string(), unit)]))
+    }
+
+    #[test]
+    fn formats_known_metrics() {
+        assert_eq!(format_value("CPU", 12.345), "12.3%");
+        assert_eq!(format_value("CPU_7", 99.0), "99.0%");
+        assert_eq!(format_value("CPU_SOCKET_1", 42.25), "42.2%");
+        assert_eq!(metric_label("CPU_SOCKET_1", "CPU_SOCKET_1"), "🖥️  CPU socket 1");
+        assert_eq!(format_value("TEMP", 45.0), "45.0°C");
+        assert_eq!(format_value("GPU_MEM", 2048.0), "2.00 GB");
+        assert_eq!(format_value("GPU_MEM", 512.0), "512 MB");
+        assert_eq!(format_value("CPU_FREQ", 3200.0), "3200 MHz");
+        assert_eq!(format_value("DISK_WRITE", 12.5), "12.50 MB/s");
+        assert_eq!(format_value("BATTERY", 85.0), "85%");
+        assert_eq!(format_value("BATTERY_CHARGING", 1.0), "sim");
+        assert_eq!(format_value("TCP_CONNS", 42.0), "42");
+        assert_eq!(builtin_formatters().format_raw("RX_BYTES", &Value::Int(123_456_789_012)), "123456789012");
+        assert_eq!(builtin_formatters().format_raw("STATE", &Value::from("idle")), "idle");
+    }
+
+    #[test]
+    fn memory_in_bytes_and_percent() {
+        assert_eq!(format_value("MEM", 512.0), "512 B");
+        assert_eq!(format_value("MEM", 2048.0), "2.00 KB");
+        assert_eq!(format_value("MEM", 1.5 * 1_048_576.0), "1.50 MB");
+        assert_eq!(format_value("MEM", 8.0 * 1_073_741_824.0), "8.00 GB");
+        assert_eq!(format_value("MEM_PERCENT", 42.25), "42.2%");
+        assert_eq!(metric_label("MEM_PERCENT", "MEM_PERCENT"), "💾 Memória");
+    }
+
+    #[test]
+    fn custom_metrics_use_configured_units() {
+        let table = rpm_units();
+        assert_eq!(table.format("FAN_RPM", 1234.6), "1235 RPM");
+        assert_eq!(table.label("FAN_RPM", "fan_rpm"), "📈 Ventoinha");
+        assert_eq!(table.format("QUEUE", 1.5), "1.50");
+        assert_eq!(table.label("QUEUE", "queue"), "📈 queue");
+
+        // Sem rótulo em [units], o nome como chegou; [units] não sobrepõe as embutidas
+        let units = HashMap::from([
+            ("QUEUE".to_string(), MetricUnit { suffix: "itens".to_string(), decimals: 0, label: None }),
+            ("CPU".to_string(), MetricUnit { suffix: "x".to_string(), decimals: 0, label: None }),
+        ]);
+        let table = FormatterTable::with_units(&units);
+        assert_eq!(table.format("QUEUE", 3.0), "3 itens");
+        assert_eq!(table.label("QUEUE", "queue"), "📈 queue");
+        assert_eq!(table.format("CPU", 12.345), "12.3%");
+        assert_eq!(format_value("FAN_RPM", 1234.6), "1234.60");
+    }
+
+    #[test]
+    fn unit_suffix_spacing() {
+        let percent = MetricUnit { suffix: "%".to_string(), decimals: 1, label: None };
+        assert_eq!(percent.format(50.0), "50.0%");
+        let bare = MetricUnit { suffix: String::new(), decimals: 3, label: None };
+        assert_eq!(bare.format(1.0), "1.000");
+    }
+
+    #[test]
+    fn uptime_rounds_to_minutes() {
+        assert_eq!(format_uptime(0.0), "0m");
+        assert_eq!(format_uptime(89.0), "1m");
+        assert_eq!(format_uptime(3.0 * 3600.0 + 14.0 * 60.0), "3h 14m");
+        assert_eq!(format_uptime(2.0 * 86_400.0 + 3600.0), "2d 1h 0m");
+        assert_eq!(format_uptime(-5.0), "0m");
+    }
+
+    #[test]
+    fn describes_metric_lines() {
+        let table = rpm_units();
+        assert_eq!(table.describe("cpu", &Value::Number(42.0)), "🖥️  CPU: 42.0%");
+        assert_eq!(table.describe("FAN_RPM", &Value::Int(1200)), "📈 Ventoinha: 1200 RPM");
+        assert_eq!(table.describe("STATE", &Value::Text("idle".to_string())), "📝 STATE: idle");
+        assert_eq!(table.describe(r#"DISK{mount="/home"}"#, &Value::Number(40.0)), "💿 Disco [mount=/home]: 40.0%");
+        assert_eq!(describe_metric("FAN_RPM", &Value::Int(1200)), "📈 FAN_RPM: 1200");
+    }
+
+    #[test]
+    fn registered_formatter_takes_precedence_over_units() {
+        let mut table = rpm_units();
+        table.register(&["fan_rpm", "FAN"], "🌀 Ventoinha", |value| format!("{:.0} rpm", value));
+        assert_eq!(table.format("FAN_RPM", 1234.6), "1235 rpm");
+        assert_eq!(table.label("FAN", "fan"), "🌀 Ventoinha");
+        assert_eq!(table.format("CPU_2", 50.0), "50.0%");
+        assert_eq!(table.format("QUEUE", 1.5), "1.50");
+    }
+}
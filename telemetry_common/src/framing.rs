@@ -0,0 +1,115 @@
+use std::io::{self, Read, Result as IoResult};
+
+use serde::{Deserialize, Serialize};
+
+/// Maior frame aceito no framing por comprimento (também o limite após descompactar)
+pub const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// Como as mensagens são delimitadas no stream TCP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Framing {
+    /// Uma mensagem JSON por linha, terminada em `\n` (protocolo original)
+    #[default]
+    Line,
+    /// Cada mensagem é prefixada por um `u32` big-endian com seu comprimento
+    Length,
+}
+
+impl Framing {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        match raw.to_ascii_lowercase().as_str() {
+            "line" => Ok(Framing::Line),
+            "length" => Ok(Framing::Length),
+            _ => Err(format!("Framing inválido '{}' (use line ou length)", raw)),
+        }
+    }
+}
+
+/// Timeouts de leitura aparecem como `WouldBlock` no Unix e `TimedOut` no Windows
+pub fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+/// Monta `[u32 BE comprimento][payload]` para ser enviado numa única escrita
+pub fn encode_frame(payload: &[u8]) -> IoResult<Vec<u8>> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "payload grande demais para um frame"))?;
+
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+    Ok(frame)
+}
+
+/// Lê um frame `[u32 BE comprimento][payload]`; `Ok(None)` indica EOF.
+/// Frames acima de `limit` são descartados para manter o stream sincronizado
+pub fn read_frame<R: Read>(reader: &mut R, limit: usize) -> IoResult<Option<Vec<u8>>> {
+    let mut header = [0u8; 4];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_be_bytes(header) as usize;
+    if len > limit {
+        io::copy(&mut reader.take(len as u64), &mut io::sink())?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame de {} bytes excede o limite de {}", len, limit),
+        ));
+    }
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).map_err(|e| {
+        // Timeout no meio do payload deixa o stream dessincronizado: não dá para retomar
+        if is_timeout(&e) {
+            io::Error::other("frame incompleto após timeout de leitura")
+        } else {
+            e
+        }
+    })?;
+    Ok(Some(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_is_case_insensitive() {
+        assert_eq!(Framing::parse("LENGTH"), Ok(Framing::Length));
+        assert_eq!(Framing::parse("line"), Ok(Framing::Line));
+        assert!(Framing::parse("xml").is_err());
+    }
+
+    #[test]
+    fn encodes_and_reads_back_frames() {
+        let mut stream = encode_frame(b"{\"CPU\":1}").unwrap();
+        stream.extend(encode_frame(b"").unwrap());
+
+        let mut reader = stream.as_slice();
+        assert_eq!(read_frame(&mut reader, 64).unwrap(), Some(b"{\"CPU\":1}".to_vec()));
+        assert_eq!(read_frame(&mut reader, 64).unwrap(), Some(Vec::new()));
+        assert_eq!(read_frame(&mut reader, 64).unwrap(), None);
+    }
+
+    #[test]
+    fn oversized_frame_is_skipped_and_stream_stays_in_sync() {
+        let mut stream = encode_frame(&[b'x'; 100]).unwrap();
+        stream.extend(encode_frame(b"ok").unwrap());
+
+        let mut reader = stream.as_slice();
+        let err = read_frame(&mut reader, 10).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read_frame(&mut reader, 10).unwrap(), Some(b"ok".to_vec()));
+    }
+
+    #[test]
+    fn truncated_payload_is_an_error() {
+        let stream = encode_frame(b"incompleto").unwrap();
+        let mut reader = &stream[..8];
+        assert!(read_frame(&mut reader, 64).is_err());
+    }
+}
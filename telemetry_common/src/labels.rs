@@ -0,0 +1,105 @@
+//! Séries com dimensões. Uma métrica com labels viaja no mesmo mapa plano das
+//! demais, com a chave no formato do Prometheus: `DISK{mount="/home"}`. Várias
+//! séries da mesma métrica pai convivem na amostra, e servidores antigos as
+//! tratam como métricas customizadas comuns
+
+/// Uma chave com labels já separada
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Series<'a> {
+    /// Métrica pai (`DISK`)
+    pub name: &'a str,
+    /// Pares `(label, valor)` na ordem da chave
+    pub labels: Vec<(String, String)>,
+}
+
+impl Series<'_> {
+    /// Labels para exibição: `mount=/home, fs=ext4`
+    pub fn describe_labels(&self) -> String {
+        self.labels.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// Monta a chave `NOME{label="valor",...}`; sem labels, devolve o próprio nome
+pub fn series_key(name: &str, labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return name.to_string();
+    }
+    let labels: Vec<String> = labels.iter().map(|(key, value)| format!("{}=\"{}\"", key, escape(value))).collect();
+    format!("{}{{{}}}", name, labels.join(","))
+}
+
+/// Separa uma chave com labels; `None` para chaves sem labels ou malformadas,
+/// que seguem valendo como um nome de métrica qualquer
+pub fn split_series(key: &str) -> Option<Series<'_>> {
+    let (name, rest) = key.split_once('{')?;
+    let body = rest.strip_suffix('}')?;
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut chars = body.chars().peekable();
+    while chars.peek().is_some() {
+        let label: String = chars.by_ref().take_while(|&c| c != '=').collect();
+        let label = label.trim();
+        if label.is_empty() || chars.next() != Some('"') {
+            return None;
+        }
+
+        let mut value = String::new();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => match chars.next()? {
+                    'n' => value.push('\n'),
+                    other => value.push(other),
+                },
+                c => value.push(c),
+            }
+        }
+        labels.push((label.to_string(), value));
+
+        match chars.next() {
+            None => break,
+            Some(',') => {}
+            Some(_) => return None,
+        }
+    }
+
+    (!labels.is_empty()).then_some(Series { name, labels })
+}
+
+/// Nome da métrica pai: `DISK` para `DISK{mount="/"}`, a própria chave nas demais.
+/// Formatação, faixas e severidade de uma série seguem as da métrica pai
+pub fn base_name(key: &str) -> &str {
+    split_series(key).map_or(key, |series| series.name)
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn series_key_roundtrip() {
+        let key = series_key("DISK", &[("mount", "/home"), ("label", "say \"hi\"\\")]);
+        assert_eq!(key, r#"DISK{mount="/home",label="say \"hi\"\\"}"#);
+
+        let series = split_series(&key).unwrap();
+        assert_eq!(series.name, "DISK");
+        assert_eq!(series.labels, [("mount".to_string(), "/home".to_string()), ("label".to_string(), "say \"hi\"\\".to_string())]);
+        assert_eq!(base_name(&key), "DISK");
+        assert_eq!(series_key("CPU", &[]), "CPU");
+    }
+
+    #[test]
+    fn plain_and_malformed_keys_are_not_series() {
+        for key in ["CPU", "DISK{}", "{mount=\"/\"}", "DISK{mount=/}", "DISK{mount=\"/\"", "DISK{mount=\"/\"x}"] {
+            assert_eq!(split_series(key), None, "{}", key);
+            assert_eq!(base_name(key), key);
+        }
+    }
+}
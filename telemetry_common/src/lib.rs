@@ -0,0 +1,47 @@
+//! Protocolo e lógica compartilhados entre `telemetry_client` e `telemetry_server`:
+//! amostras, framing, compressão, comandos remotos, descoberta, formatação, agregação, valores, labels de métricas e versão do protocolo.
+//! Nada aqui faz I/O de rede diretamente, então tudo é testável com buffers em memória.
+
+pub mod aggregate;
+pub mod commands;
+pub mod compression;
+pub mod discovery;
+pub mod format;
+pub mod framing;
+pub mod labels;
+pub mod protocol;
+pub mod reader;
+pub mod sample;
+pub mod timefmt;
+pub mod value;
+
+/// Prefixo de endereços de Unix domain socket (`unix:/run/telemetry.sock`)
+pub const UNIX_ADDRESS_PREFIX: &str = "unix:";
+
+/// Valor enviado quando não há sensores de temperatura (zero absoluto, impossível na prática)
+pub const TEMPERATURE_UNAVAILABLE: f32 = -273.15;
+
+/// `value` é a sentinela de [`TEMPERATURE_UNAVAILABLE`] numa métrica de temperatura,
+/// inclusive séries com labels (`TEMP{sensor="gpu"}`). Histórico, alertas e
+/// exportadores descartam esses pontos em vez de registrar -273,15 °C
+pub fn is_temperature_unavailable(name: &str, value: f64) -> bool {
+    let base = labels::base_name(name);
+    (base.eq_ignore_ascii_case("TEMP") || base.eq_ignore_ascii_case("TEMPERATURE"))
+        && value <= f64::from(TEMPERATURE_UNAVAILABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temperature_sentinel_matches_labeled_series_only_for_temperature() {
+        let sentinel = f64::from(TEMPERATURE_UNAVAILABLE);
+        assert!(is_temperature_unavailable("TEMP", sentinel));
+        assert!(is_temperature_unavailable("temp", -273.15));
+        assert!(is_temperature_unavailable("TEMPERATURE", sentinel));
+        assert!(is_temperature_unavailable("TEMP{sensor=\"gpu\"}", sentinel));
+        assert!(!is_temperature_unavailable("TEMP", 45.0));
+        assert!(!is_temperature_unavailable("CPU", sentinel));
+    }
+}
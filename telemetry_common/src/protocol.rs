@@ -0,0 +1,60 @@
+//! Versão do protocolo de telemetria, negociada no handshake. A v1 é o protocolo
+//! de linha original, só com métricas float; a v2 acrescenta valores mistos,
+//! séries com labels e batches. Clientes sem `protocol_version` são v1
+
+/// Versão que este build fala
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Versão mais antiga que o servidor ainda aceita
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Assumida quando o handshake não traz o campo (clientes anteriores à negociação)
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+
+/// Primeira versão com batches e séries com labels
+const EXTENDED_VERSION: u32 = 2;
+
+/// Batches (arrays de amostras) e o `accept_batch` só existem a partir da v2
+pub fn supports_batch(version: u32) -> bool {
+    version >= EXTENDED_VERSION
+}
+
+/// Séries com labels (`DISK{mount="/"}`) só existem a partir da v2
+pub fn supports_labels(version: u32) -> bool {
+    version >= EXTENDED_VERSION
+}
+
+/// Versão efetiva de um handshake, ou o motivo da recusa
+pub fn negotiate(declared: Option<u32>) -> Result<u32, String> {
+    let version = declared.unwrap_or(LEGACY_PROTOCOL_VERSION);
+    if (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+        Ok(version)
+    } else {
+        Err(format!(
+            "versão de protocolo {} não suportada (servidor aceita {}..={})",
+            version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_supported_versions() {
+        assert_eq!(negotiate(None), Ok(LEGACY_PROTOCOL_VERSION));
+        assert_eq!(negotiate(Some(1)), Ok(1));
+        assert_eq!(negotiate(Some(PROTOCOL_VERSION)), Ok(PROTOCOL_VERSION));
+        assert!(!supports_batch(LEGACY_PROTOCOL_VERSION) && !supports_labels(LEGACY_PROTOCOL_VERSION));
+        assert!(supports_batch(PROTOCOL_VERSION) && supports_labels(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        for version in [0, PROTOCOL_VERSION + 1, u32::MAX] {
+            let reason = negotiate(Some(version)).unwrap_err();
+            assert!(reason.contains(&version.to_string()), "{}", reason);
+        }
+    }
+}
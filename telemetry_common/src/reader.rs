@@ -0,0 +1,275 @@
+use std::io::{self, BufRead, Read, Result as IoResult};
+
+/// Leitor com buffer próprio para o stream do cliente. Extrai várias linhas
+/// completas de uma única leitura do socket e retém o resto parcial até a
+/// próxima, inclusive entre timeouts. Também implementa `BufRead` para o
+/// framing por comprimento e para a detecção do byte de modo.
+pub struct MessageReader<R> {
+    inner: R,
+    buffer: Vec<u8>,
+    /// Início dos bytes ainda não consumidos
+    pos: usize,
+    /// Fim dos bytes válidos no buffer
+    filled: usize,
+    /// Maior linha aceita (com o `\n`); acima disso o buffer para de crescer
+    max_line: usize,
+    /// Bytes já descartados da linha grande demais, enquanto busca o próximo `\n`
+    discarding: Option<usize>,
+    /// Posição no buffer da última linha entregue, válida até a próxima leitura
+    last_line: (usize, usize),
+}
+
+impl<R: Read> MessageReader<R> {
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner,
+            buffer: vec![0; capacity.max(1)],
+            pos: 0,
+            filled: 0,
+            max_line: usize::MAX,
+            discarding: None,
+            last_line: (0, 0),
+        }
+    }
+
+    /// Limita o tamanho das linhas, para um cliente sem `\n` não esgotar a memória
+    pub fn with_max_line(mut self, max_line: usize) -> Self {
+        self.max_line = max_line.max(1);
+        self
+    }
+
+    /// Stream subjacente, para responder na mesma conexão
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Acrescenta a próxima linha completa (com o `\n`) a `out`; `Ok(false)` indica EOF.
+    /// Um resto sem `\n` no EOF é entregue como última linha. Linhas com UTF-8
+    /// inválido são consumidas e geram `InvalidData`, assim como linhas acima de
+    /// `max_line`, descartadas até o próximo `\n` sem ficarem inteiras no buffer.
+    pub fn read_line(&mut self, out: &mut String) -> IoResult<bool> {
+        self.last_line = (0, 0);
+        loop {
+            let pending = &self.buffer[self.pos..self.filled];
+            let newline = pending.iter().position(|&byte| byte == b'\n');
+
+            match (self.discarding, newline) {
+                // Ressincronizado: o erro sai só agora, com o stream já no início da próxima linha
+                (Some(discarded), Some(newline)) => {
+                    self.pos += newline + 1;
+                    self.discarding = None;
+                    return Err(self.line_too_long(discarded + newline + 1));
+                }
+                (Some(discarded), None) => {
+                    self.discarding = Some(discarded + pending.len());
+                    self.pos = self.filled;
+                }
+                (None, Some(newline)) if newline + 1 > self.max_line => {
+                    self.pos += newline + 1;
+                    return Err(self.line_too_long(newline + 1));
+                }
+                (None, Some(newline)) => return self.take_line(newline + 1, out).map(|_| true),
+                (None, None) if pending.len() >= self.max_line => {
+                    self.discarding = Some(pending.len());
+                    self.pos = self.filled;
+                }
+                (None, None) => {}
+            }
+
+            // Sem linha completa: lê mais. Erros (inclusive timeouts) preservam o resto parcial
+            if self.read_more()? == 0 {
+                if let Some(discarded) = self.discarding.take() {
+                    return Err(self.line_too_long(discarded));
+                }
+                let remaining = self.filled - self.pos;
+                if remaining == 0 {
+                    return Ok(false);
+                }
+                return self.take_line(remaining, out).map(|_| true);
+            }
+        }
+    }
+
+    /// Bytes crus da linha lida pelo último `read_line`, inclusive quando ela
+    /// tinha UTF-8 inválido; vazio após EOF, timeout ou linha grande demais
+    pub fn last_line(&self) -> &[u8] {
+        &self.buffer[self.last_line.0..self.last_line.1]
+    }
+
+    fn line_too_long(&self, len: usize) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("linha de {} bytes excede o limite de {}", len, self.max_line),
+        )
+    }
+
+    fn take_line(&mut self, len: usize, out: &mut String) -> IoResult<()> {
+        self.last_line = (self.pos, self.pos + len);
+        let line = &self.buffer[self.pos..self.pos + len];
+        self.pos += len;
+
+        let text = std::str::from_utf8(line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        out.push_str(text);
+        Ok(())
+    }
+
+    /// Lê do stream para o fim do buffer, compactando ou crescendo quando ele enche
+    fn read_more(&mut self) -> IoResult<usize> {
+        if self.pos == self.filled {
+            self.pos = 0;
+            self.filled = 0;
+        } else if self.filled == self.buffer.len() {
+            if self.pos > 0 {
+                self.buffer.copy_within(self.pos..self.filled, 0);
+                self.filled -= self.pos;
+                self.pos = 0;
+            } else {
+                self.buffer.resize(self.buffer.len() * 2, 0);
+            }
+        }
+
+        let read = self.inner.read(&mut self.buffer[self.filled..])?;
+        self.filled += read;
+        Ok(read)
+    }
+}
+
+impl<R: Read> Read for MessageReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: Read> BufRead for MessageReader<R> {
+    fn fill_buf(&mut self) -> IoResult<&[u8]> {
+        if self.pos == self.filled {
+            self.read_more()?;
+        }
+        Ok(&self.buffer[self.pos..self.filled])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.pos = (self.pos + amount).min(self.filled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Entrega os dados em pedaços fixos, como leituras parciais de um socket
+    struct Chunked<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+            let len = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    fn lines<R: Read>(reader: &mut MessageReader<R>) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(true) => lines.push(line),
+                Ok(false) => return lines,
+                Err(e) => lines.push(format!("erro: {}", e.kind())),
+            }
+        }
+    }
+
+    #[test]
+    fn splits_several_lines_from_one_read() {
+        let mut reader = MessageReader::with_capacity(64, &b"{\"CPU\":1}\n{\"CPU\":2}\n"[..]);
+        assert_eq!(lines(&mut reader), ["{\"CPU\":1}\n", "{\"CPU\":2}\n"]);
+    }
+
+    /// Linhas em branco em sequência (heartbeats, cliente com bug) passam sem
+    /// fazer o buffer do leitor nem o da linha crescerem
+    #[test]
+    fn blank_lines_do_not_grow_buffers() {
+        let mut data = vec![b'\n'; 100_000];
+        data.extend_from_slice(b"{\"CPU\":1}\n");
+        let mut reader = MessageReader::with_capacity(64, data.as_slice());
+        let mut line = String::with_capacity(16);
+
+        let mut blank = 0;
+        while reader.read_line(&mut line).unwrap() && line.trim().is_empty() {
+            blank += 1;
+            line.clear();
+        }
+        assert_eq!(blank, 100_000);
+        assert_eq!(line, "{\"CPU\":1}\n");
+        assert_eq!(reader.buffer.len(), 64);
+        assert_eq!(line.capacity(), 16);
+    }
+
+    #[test]
+    fn joins_partial_reads_and_grows_buffer() {
+        let data = b"primeira linha bem maior que o buffer\nsegunda\n";
+        let mut reader = MessageReader::with_capacity(4, Chunked { data, chunk: 3 });
+        assert_eq!(lines(&mut reader), ["primeira linha bem maior que o buffer\n", "segunda\n"]);
+    }
+
+    #[test]
+    fn delivers_remainder_at_eof() {
+        let mut reader = MessageReader::with_capacity(16, &b"a\nsem fim"[..]);
+        assert_eq!(lines(&mut reader), ["a\n", "sem fim"]);
+    }
+
+    #[test]
+    fn invalid_utf8_line_is_consumed() {
+        let mut reader = MessageReader::with_capacity(16, &b"\xff\xfe\nok\n"[..]);
+        assert_eq!(lines(&mut reader), ["erro: invalid data", "ok\n"]);
+    }
+
+    #[test]
+    fn last_line_keeps_raw_bytes_even_when_invalid() {
+        let mut reader = MessageReader::with_capacity(16, &b"\xff\xfe\nok\n"[..]);
+        let mut out = String::new();
+        assert!(reader.read_line(&mut out).is_err());
+        assert_eq!(reader.last_line(), b"\xff\xfe\n");
+        assert!(reader.read_line(&mut out).unwrap());
+        assert_eq!(reader.last_line(), b"ok\n");
+        assert!(!reader.read_line(&mut out).unwrap());
+        assert_eq!(reader.last_line(), b"");
+    }
+
+    #[test]
+    fn oversized_line_is_discarded_until_next_newline() {
+        let mut data = vec![b'x'; 1024 * 1024];
+        data.extend_from_slice(b"\n{\"CPU\":1}\n");
+        let mut reader = MessageReader::with_capacity(16, Chunked { data: &data, chunk: 4096 }).with_max_line(64);
+
+        assert_eq!(lines(&mut reader), ["erro: invalid data", "{\"CPU\":1}\n"]);
+        assert!(reader.buffer.len() <= 2 * 4096);
+    }
+
+    #[test]
+    fn oversized_line_without_newline_is_rejected_at_eof() {
+        let data = vec![b'x'; 1024 * 1024];
+        let mut reader = MessageReader::with_capacity(16, Chunked { data: &data, chunk: 4096 }).with_max_line(64 * 1024);
+
+        assert_eq!(lines(&mut reader), ["erro: invalid data"]);
+        assert!(reader.buffer.len() <= 2 * 64 * 1024);
+    }
+
+    #[test]
+    fn bufread_sees_pending_bytes() {
+        let mut reader = MessageReader::with_capacity(16, &b"\x01resto\n"[..]);
+        assert_eq!(reader.fill_buf().unwrap()[0], 0x01);
+        reader.consume(1);
+        assert_eq!(lines(&mut reader), ["resto\n"]);
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::value::Value;
+
+/// Amostra trocada entre cliente e servidor: métricas conhecidas tipadas e as customizadas em `extra`.
+/// JSONs antigos (um mapa plano `{"CPU": 1.0, ...}`) continuam válidos
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetrySample {
+    /// Epoch em milissegundos do momento da coleta no cliente
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(default, rename = "CPU", alias = "cpu", skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<f32>,
+    /// Clock da CPU em MHz (média ou máxima dos núcleos), para detectar throttling
+    #[serde(default, rename = "CPU_FREQ", alias = "cpu_freq", skip_serializing_if = "Option::is_none")]
+    pub cpu_freq: Option<f32>,
+    /// Memória em uso, em bytes
+    #[serde(default, rename = "MEM", alias = "mem", skip_serializing_if = "Option::is_none")]
+    pub mem: Option<f32>,
+    /// Memória em uso como % do total, enviada no lugar de `MEM` com `--mem-mode percent`
+    #[serde(default, rename = "MEM_PERCENT", alias = "mem_percent", skip_serializing_if = "Option::is_none")]
+    pub mem_percent: Option<f32>,
+    #[serde(default, rename = "SWAP", alias = "swap", skip_serializing_if = "Option::is_none")]
+    pub swap: Option<f32>,
+    #[serde(default, rename = "DISK", alias = "disk", skip_serializing_if = "Option::is_none")]
+    pub disk: Option<f32>,
+    /// Taxas de leitura e escrita em disco, em MB/s, somadas entre os dispositivos
+    #[serde(default, rename = "DISK_READ", alias = "disk_read", skip_serializing_if = "Option::is_none")]
+    pub disk_read: Option<f32>,
+    #[serde(default, rename = "DISK_WRITE", alias = "disk_write", skip_serializing_if = "Option::is_none")]
+    pub disk_write: Option<f32>,
+    #[serde(default, rename = "NET", alias = "net", skip_serializing_if = "Option::is_none")]
+    pub net: Option<f32>,
+    #[serde(default, rename = "TEMP", alias = "temp", alias = "TEMPERATURE", skip_serializing_if = "Option::is_none")]
+    pub temp: Option<f32>,
+    #[serde(default, rename = "PROC_COUNT", alias = "proc_count", skip_serializing_if = "Option::is_none")]
+    pub proc_count: Option<f32>,
+    #[serde(default, rename = "UPTIME", alias = "uptime", skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<f32>,
+    /// Ausentes em máquinas sem GPU NVIDIA
+    #[serde(default, rename = "GPU_UTIL", alias = "gpu_util", skip_serializing_if = "Option::is_none")]
+    pub gpu_util: Option<f32>,
+    #[serde(default, rename = "GPU_MEM", alias = "gpu_mem", skip_serializing_if = "Option::is_none")]
+    pub gpu_mem: Option<f32>,
+    /// Carga da bateria em %; ausente em máquinas sem bateria
+    #[serde(default, rename = "BATTERY", alias = "battery", skip_serializing_if = "Option::is_none")]
+    pub battery: Option<f32>,
+    /// 1 enquanto a bateria carrega, 0 caso contrário
+    #[serde(default, rename = "BATTERY_CHARGING", alias = "battery_charging", skip_serializing_if = "Option::is_none")]
+    pub battery_charging: Option<f32>,
+    /// Conexões TCP do cliente (só as estabelecidas, ou todas com `--tcp-state all`)
+    #[serde(default, rename = "TCP_CONNS", alias = "tcp_conns", skip_serializing_if = "Option::is_none")]
+    pub tcp_conns: Option<f32>,
+    /// Processo de maior consumo, enviado à parte por ser textual
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_process: Option<TopProcess>,
+    /// Métricas customizadas, no mesmo nível das conhecidas: números, inteiros exatos, texto ou booleano
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopProcess {
+    pub name: String,
+    pub cpu: f32,
+}
+
+impl TelemetrySample {
+    pub fn new(timestamp: u64) -> Self {
+        Self { timestamp: Some(timestamp), ..Self::default() }
+    }
+
+    /// Métricas conhecidas pelo nome canônico, com o campo de cada uma. Única
+    /// lista dos campos para `sanitize`, `set` e `take_metrics`: uma métrica
+    /// nova só precisa do campo na struct e de uma linha aqui
+    fn known_metrics_mut(&mut self) -> [(&'static str, &mut Option<f32>); 17] {
+        [
+            ("CPU", &mut self.cpu),
+            ("CPU_FREQ", &mut self.cpu_freq),
+            ("MEM", &mut self.mem),
+            ("MEM_PERCENT", &mut self.mem_percent),
+            ("SWAP", &mut self.swap),
+            ("DISK", &mut self.disk),
+            ("DISK_READ", &mut self.disk_read),
+            ("DISK_WRITE", &mut self.disk_write),
+            ("NET", &mut self.net),
+            ("TEMP", &mut self.temp),
+            ("PROC_COUNT", &mut self.proc_count),
+            ("UPTIME", &mut self.uptime),
+            ("GPU_UTIL", &mut self.gpu_util),
+            ("GPU_MEM", &mut self.gpu_mem),
+            ("BATTERY", &mut self.battery),
+            ("BATTERY_CHARGING", &mut self.battery_charging),
+            ("TCP_CONNS", &mut self.tcp_conns),
+        ]
+    }
+
+    /// Remove valores `NaN`/`inf`, que não têm representação em JSON: as métricas
+    /// conhecidas viram ausentes, as customizadas são descartadas. Devolve os
+    /// nomes das métricas removidas, em ordem alfabética
+    pub fn sanitize(&mut self) -> Vec<String> {
+        let mut removed = Vec::new();
+        for (name, slot) in self.known_metrics_mut() {
+            if slot.is_some_and(|value| !value.is_finite()) {
+                *slot = None;
+                removed.push(name.to_string());
+            }
+        }
+        self.extra.retain(|name, value| {
+            if !value.is_finite() {
+                removed.push(name.clone());
+            }
+            value.is_finite()
+        });
+        removed.sort();
+
+        if let Some(top) = &mut self.top_process {
+            if !top.cpu.is_finite() {
+                top.cpu = 0.0;
+            }
+        }
+        removed
+    }
+
+    /// Grava uma métrica pelo nome canônico: as conhecidas vão para o próprio
+    /// campo, as demais para `extra`
+    pub fn set(&mut self, name: &str, value: f32) {
+        if let Some((_, slot)) = self.known_metrics_mut().into_iter().find(|(known, _)| *known == name) {
+            *slot = Some(value);
+            return;
+        }
+        self.extra.insert(name.to_string(), Value::from(value));
+    }
+
+    /// Métricas numa visão plana por nome canônico, como usam histórico, alertas e
+    /// exportações. As customizadas que não são float (inteiros, texto e booleanos)
+    /// voltam também no original, para exibição sem perda
+    pub fn take_metrics(&mut self) -> (HashMap<String, f32>, BTreeMap<String, Value>) {
+        let extra = std::mem::take(&mut self.extra);
+        let known = self.known_metrics_mut();
+
+        let mut metrics = HashMap::with_capacity(extra.len() + known.len());
+        let mut values = BTreeMap::new();
+        for (name, value) in extra {
+            if let Some(number) = value.as_f32() {
+                metrics.insert(name.clone(), number);
+            }
+            if !matches!(value, Value::Number(_)) {
+                values.insert(name, value);
+            }
+        }
+        for (name, slot) in known {
+            if let Some(value) = *slot {
+                metrics.insert(name.to_string(), value);
+            }
+        }
+        (metrics, values)
+    }
+}
+
+/// Uma mensagem de telemetria: um objeto com uma amostra ou, em clientes que
+/// negociaram batch, um array com várias
+pub fn parse_samples(message: &str) -> serde_json::Result<Vec<TelemetrySample>> {
+    let message = message.trim();
+    if message.starts_with('[') {
+        serde_json::from_str(message)
+    } else {
+        serde_json::from_str(message).map(|sample| vec![sample])
+    }
+}
+
+/// `used` como porcentagem de `total`; 0 quando o total é desconhecido
+pub fn percent_of(used: u64, total: u64) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    (used as f64 / total as f64 * 100.0) as f32
+}
+
+/// Índice do núcleo em métricas por core (`CPU_0`, `CPU_1`, ...), enviadas
+/// como customizadas pelo cliente em `--cpu-mode per-core|both`
+pub fn core_index(name: &str) -> Option<usize> {
+    let suffix = name.strip_prefix("CPU_").or_else(|| name.strip_prefix("cpu_"))?;
+    suffix.parse().ok()
+}
+
+/// Índice do socket em médias por socket físico/nó NUMA (`CPU_SOCKET_0`, ...),
+/// enviadas pelo cliente com `--cpu-sockets`
+pub fn socket_index(name: &str) -> Option<usize> {
+    let suffix = name.strip_prefix("CPU_SOCKET_").or_else(|| name.strip_prefix("cpu_socket_"))?;
+    suffix.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_legacy_flat_map_and_aliases() {
+        let mut sample: TelemetrySample =
+            serde_json::from_str(r#"{"cpu": 12.5, "TEMPERATURE": 40.0, "FAN_RPM": 900}"#).unwrap();
+        assert_eq!(sample.timestamp, None);
+        assert_eq!(sample.cpu, Some(12.5));
+        assert_eq!(sample.temp, Some(40.0));
+
+        let (metrics, values) = sample.take_metrics();
+        assert_eq!(metrics.get("CPU"), Some(&12.5));
+        assert_eq!(metrics.get("TEMP"), Some(&40.0));
+        assert_eq!(metrics.get("FAN_RPM"), Some(&900.0));
+        assert_eq!(metrics.len(), 3);
+        // Inteiro cru de cliente antigo: continua exato no original
+        assert_eq!(values.get("FAN_RPM"), Some(&Value::Int(900)));
+        assert_eq!(values.len(), 1);
+    }
+
+    #[test]
+    fn keeps_ints_text_and_bools_alongside_numeric_view() {
+        let mut sample: TelemetrySample =
+            serde_json::from_str(r#"{"CPU": 1.0, "RX_BYTES": 123456789012, "STATE": "idle", "ON_AC": true}"#).unwrap();
+
+        let (metrics, values) = sample.take_metrics();
+        assert_eq!(metrics.get("ON_AC"), Some(&1.0));
+        assert!(!metrics.contains_key("STATE"));
+        assert_eq!(values.get("RX_BYTES"), Some(&Value::Int(123_456_789_012)));
+        assert_eq!(values.get("STATE"), Some(&Value::from("idle")));
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn roundtrips_through_json() {
+        let mut sample = TelemetrySample::new(1_700_000_000_000);
+        sample.mem = Some(2048.0);
+        sample.top_process = Some(TopProcess { name: "cargo".to_string(), cpu: 80.0 });
+        sample.extra.insert("CPU_0".to_string(), 10.0f32.into());
+
+        let json = serde_json::to_string(&sample).unwrap();
+        assert!(!json.contains("CPU\""), "métricas ausentes não são serializadas: {}", json);
+
+        let parsed: TelemetrySample = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.timestamp, Some(1_700_000_000_000));
+        assert_eq!(parsed.mem, Some(2048.0));
+        assert_eq!(parsed.top_process, sample.top_process);
+        assert_eq!(parsed.extra.get("CPU_0"), Some(&Value::Number(10.0)));
+    }
+
+    #[test]
+    fn parses_single_sample_and_batch() {
+        let single = parse_samples(r#" {"CPU": 1.0} "#).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].cpu, Some(1.0));
+
+        let batch = parse_samples(r#"[{"timestamp": 1, "CPU": 1.0}, {"timestamp": 2, "DISK": 2.0}]"#).unwrap();
+        assert_eq!(batch.iter().map(|sample| sample.timestamp).collect::<Vec<_>>(), vec![Some(1), Some(2)]);
+        assert_eq!(batch[1].disk, Some(2.0));
+
+        assert!(parse_samples("[]").unwrap().is_empty());
+        assert!(parse_samples(r#"[{"CPU": 1.0}"#).is_err());
+    }
+
+    #[test]
+    fn sanitize_drops_non_finite_values() {
+        let mut sample = TelemetrySample::new(0);
+        sample.cpu = Some(f32::NAN);
+        sample.disk = Some(50.0);
+        sample.extra.insert("BAD".to_string(), f32::INFINITY.into());
+        sample.top_process = Some(TopProcess { name: "x".to_string(), cpu: f32::NAN });
+        sample.mem = Some(f32::NEG_INFINITY);
+
+        assert_eq!(sample.sanitize(), ["BAD", "CPU", "MEM"]);
+        assert_eq!(sample.cpu, None);
+        assert_eq!(sample.mem, None);
+        assert_eq!(sample.disk, Some(50.0));
+        assert!(sample.extra.is_empty());
+        assert_eq!(sample.top_process.as_ref().unwrap().cpu, 0.0);
+
+        // O JSON enviado é aceito pelo parser do servidor
+        let parsed = parse_samples(&serde_json::to_string(&sample).unwrap()).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!((parsed[0].cpu, parsed[0].mem, parsed[0].disk), (None, None, Some(50.0)));
+        assert_eq!(parsed[0].top_process.as_ref().unwrap().cpu, 0.0);
+    }
+
+    #[test]
+    fn set_fills_known_fields_and_extra() {
+        let mut sample = TelemetrySample::new(0);
+        sample.set("CPU", 12.0);
+        sample.set("FAN_RPM", 900.0);
+        assert_eq!(sample.cpu, Some(12.0));
+        assert_eq!(sample.extra.get("FAN_RPM"), Some(&Value::Number(900.0)));
+        assert!(!sample.extra.contains_key("CPU"));
+    }
+
+    /// Cada nome da tabela de métricas conhecidas é o mesmo do JSON do campo: a
+    /// amostra lida de volta não tem nada em `extra` e devolve todas em `take_metrics`
+    #[test]
+    fn known_metrics_match_their_json_names() {
+        let mut sample = TelemetrySample::new(0);
+        let names: Vec<&str> = sample.known_metrics_mut().iter().map(|(name, _)| *name).collect();
+        for (index, name) in names.iter().enumerate() {
+            sample.set(name, index as f32);
+        }
+        assert!(sample.extra.is_empty());
+
+        let mut parsed: TelemetrySample = serde_json::from_str(&serde_json::to_string(&sample).unwrap()).unwrap();
+        assert!(parsed.extra.is_empty());
+        let (metrics, _) = parsed.take_metrics();
+        assert_eq!(metrics.len(), names.len());
+        for (index, name) in names.iter().enumerate() {
+            assert_eq!(metrics.get(*name), Some(&(index as f32)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn percent_of_known_values() {
+        assert_eq!(percent_of(4 * 1_073_741_824, 16 * 1_073_741_824), 25.0);
+        assert_eq!(percent_of(1, 0), 0.0);
+    }
+
+    #[test]
+    fn core_index_accepts_both_cases() {
+        assert_eq!(core_index("CPU_3"), Some(3));
+        assert_eq!(core_index("cpu_12"), Some(12));
+        assert_eq!(core_index("CPU"), None);
+        assert_eq!(core_index("CPU_X"), None);
+        assert_eq!(core_index("CPU_SOCKET_1"), None);
+        assert_eq!(socket_index("CPU_SOCKET_1"), Some(1));
+        assert_eq!(socket_index("cpu_socket_0"), Some(0));
+        assert_eq!(socket_index("CPU_1"), None);
+    }
+}
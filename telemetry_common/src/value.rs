@@ -0,0 +1,119 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Valor de uma métrica customizada. No JSON é o valor cru: inteiros exatos
+/// (contadores grandes), floats, texto ou booleano. Floats antigos continuam
+/// chegando como `Number`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    /// Tentado antes de `Number`: `5` vira `Int`, `5.0` vira `Number`
+    Int(i64),
+    Number(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl Value {
+    /// Visão numérica usada por histórico, alertas e exportações; booleanos
+    /// viram 1/0 e texto não tem representação
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Value::Int(value) => Some(*value as f32),
+            Value::Number(value) => Some(*value as f32),
+            Value::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
+            Value::Text(_) => None,
+        }
+    }
+
+    /// Visão numérica das exportações: como `as_f32`, mas com inteiros exatos até 2^53
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(value) => Some(*value as f64),
+            Value::Number(value) => Some(*value),
+            Value::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
+            Value::Text(_) => None,
+        }
+    }
+
+    /// `NaN`/`inf` não têm representação em JSON; só `Number` pode carregá-los
+    pub fn is_finite(&self) -> bool {
+        match self {
+            Value::Number(value) => value.is_finite(),
+            _ => true,
+        }
+    }
+}
+
+/// Preserva a representação curta do `f32` no JSON (`0.1`, não `0.10000000149011612`)
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Number(value.to_string().parse().unwrap_or(value as f64))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Number(value) => write!(f, "{}", value),
+            Value::Bool(true) => write!(f, "sim"),
+            Value::Bool(false) => write!(f, "não"),
+            Value::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_variant_from_raw_json() {
+        let parse = |raw: &str| serde_json::from_str::<Value>(raw).unwrap();
+        assert_eq!(parse("12"), Value::Int(12));
+        assert_eq!(parse("9007199254740993"), Value::Int(9_007_199_254_740_993));
+        assert_eq!(parse("12.5"), Value::Number(12.5));
+        assert_eq!(parse("true"), Value::Bool(true));
+        assert_eq!(parse("\"Charging\""), Value::Text("Charging".to_string()));
+        assert_eq!(parse("123456789012").as_f64(), Some(123_456_789_012.0));
+        assert_ne!(parse("123456789013").as_f32().map(f64::from), Some(123_456_789_013.0));
+    }
+
+    #[test]
+    fn serializes_f32_without_float_noise() {
+        assert_eq!(serde_json::to_string(&Value::from(0.1f32)).unwrap(), "0.1");
+        assert_eq!(serde_json::to_string(&Value::from(i64::MAX)).unwrap(), i64::MAX.to_string());
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    process::Command,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use serde::{Deserialize, Serialize};
+use telemetry_common::{format::FormatterTable, is_temperature_unavailable};
+
+use crate::{log, notify, LogLevel};
+
+/// Sentido da violação do limiar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertCondition {
+    Above,
+    Below,
+}
+
+/// Regra de alerta declarada em `[[alerts]]` no `telemetry.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Nome da métrica (comparação sem diferenciar maiúsculas)
+    pub metric: String,
+    pub threshold: f32,
+    #[serde(default = "default_condition")]
+    pub condition: AlertCondition,
+    /// Tempo mínimo em violação contínua antes de disparar
+    #[serde(default)]
+    pub duration_secs: u64,
+    /// Margem que o valor precisa recuar além do limiar para o alerta ser encerrado
+    #[serde(default)]
+    pub hysteresis: f32,
+    /// Comando externo executado no disparo, recebendo métrica, valor e cliente
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Mostra uma notificação nativa do desktop no disparo
+    #[serde(default)]
+    pub notify: bool,
+}
+
+fn default_condition() -> AlertCondition {
+    AlertCondition::Above
+}
+
+impl AlertRule {
+    fn is_violated(&self, value: f32) -> bool {
+        match self.condition {
+            AlertCondition::Above => value > self.threshold,
+            AlertCondition::Below => value < self.threshold,
+        }
+    }
+
+    /// Título e texto da notificação de desktop: cliente, métrica e valor, com a
+    /// unidade da métrica (embutida ou de `[units]`)
+    fn notification(&self, value: f32, client: &str, formatters: &FormatterTable) -> (String, String) {
+        let metric = self.metric.to_uppercase();
+        let title = format!("⚠️ Alerta de telemetria: {}", metric);
+        let body = format!(
+            "{}: {} = {} (limiar {})",
+            client, metric, formatters.format(&metric, value), formatters.format(&metric, self.threshold)
+        );
+        (title, body)
+    }
+
+    /// Só considera normalizado quando o valor sai da faixa de histerese
+    fn is_cleared(&self, value: f32) -> bool {
+        match self.condition {
+            AlertCondition::Above => value <= self.threshold - self.hysteresis,
+            AlertCondition::Below => value >= self.threshold + self.hysteresis,
+        }
+    }
+}
+
+/// Estado de uma regra para um cliente
+#[derive(Debug, Default)]
+struct AlertState {
+    /// Início da violação contínua atual
+    violating_since: Option<Instant>,
+    firing: bool,
+}
+
+/// Avalia as regras de alerta sobre as amostras de um cliente
+pub struct AlertMonitor {
+    rules: Arc<[AlertRule]>,
+    states: Vec<AlertState>,
+    client: SocketAddr,
+    /// Nome do cliente nas notificações; o endereço até o handshake
+    client_name: String,
+}
+
+impl AlertMonitor {
+    pub fn new(rules: Arc<[AlertRule]>, client: SocketAddr) -> Self {
+        let states = rules.iter().map(|_| AlertState::default()).collect();
+        Self { rules, states, client, client_name: client.to_string() }
+    }
+
+    /// Passa a identificar o cliente pelo nome declarado no handshake
+    pub fn identify(&mut self, name: String) {
+        self.client_name = name;
+    }
+
+    pub fn evaluate(&mut self, metrics: &HashMap<String, f32>, formatters: &FormatterTable) {
+        let now = Instant::now();
+
+        for (rule, state) in self.rules.iter().zip(self.states.iter_mut()) {
+            let Some(value) = metrics.iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&rule.metric))
+                .map(|(_, value)| *value)
+                .filter(|value| !is_temperature_unavailable(&rule.metric, f64::from(*value)))
+            else {
+                continue;
+            };
+
+            if state.firing {
+                if rule.is_cleared(value) {
+                    state.firing = false;
+                    state.violating_since = None;
+                    log(LogLevel::Info, &format!(
+                        "Alerta encerrado: {} = {:.2} em {}", rule.metric, value, self.client
+                    ));
+                }
+                continue;
+            }
+
+            if !rule.is_violated(value) {
+                state.violating_since = None;
+                continue;
+            }
+
+            let since = *state.violating_since.get_or_insert(now);
+            if now.duration_since(since) >= Duration::from_secs(rule.duration_secs) {
+                state.firing = true;
+                log(LogLevel::Warning, &format!(
+                    "ALERTA: {} = {:.2} (limiar {:.2}) em {}",
+                    rule.metric, value, rule.threshold, self.client
+                ));
+                run_alert_command(rule, value, self.client);
+                if rule.notify {
+                    let (title, body) = rule.notification(value, &self.client_name, formatters);
+                    notify::notify(&title, &body);
+                }
+            }
+        }
+    }
+}
+
+/// Executa o comando do alerta sem bloquear a thread do cliente
+fn run_alert_command(rule: &AlertRule, value: f32, client: SocketAddr) {
+    let Some(program) = &rule.command else {
+        return;
+    };
+
+    let spawned = Command::new(program)
+        .args(&rule.args)
+        .arg(&rule.metric)
+        .arg(value.to_string())
+        .arg(client.to_string())
+        .spawn();
+
+    match spawned {
+        Ok(mut child) => {
+            // Aguarda em outra thread para não deixar processos zumbis
+            thread::spawn(move || {
+                let _ = child.wait();
+            });
+        }
+        Err(e) => log(LogLevel::Error, &format!("Falha ao executar '{}': {}", program, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use telemetry_common::format::MetricUnit;
+
+    use super::*;
+
+    #[test]
+    fn notification_names_client_metric_and_value() {
+        let rule: AlertRule = toml::from_str("metric = \"cpu\"\nthreshold = 90.0\nnotify = true").unwrap();
+        assert!(rule.notify);
+
+        let (title, body) = rule.notification(97.25, "web-01 (abc)", &FormatterTable::builtin());
+        assert_eq!(title, "⚠️ Alerta de telemetria: CPU");
+        assert_eq!(body, "web-01 (abc): CPU = 97.2% (limiar 90.0%)");
+
+        let rule: AlertRule = toml::from_str("metric = \"fan_rpm\"\nthreshold = 3000.0").unwrap();
+        let unit = MetricUnit { suffix: "RPM".to_string(), decimals: 0, label: None };
+        let formatters = FormatterTable::with_units(&HashMap::from([("FAN_RPM".to_string(), unit)]));
+        let (_, body) = rule.notification(3120.4, "web-01", &formatters);
+        assert_eq!(body, "web-01: FAN_RPM = 3120 RPM (limiar 3000 RPM)");
+    }
+}
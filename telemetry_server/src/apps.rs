@@ -0,0 +1,238 @@
+use std::{
+    env,
+    io::{self, Result as IoResult},
+    path::{Path, PathBuf},
+    process::{Child, Command as Process},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::{Command, CommandContext},
+    log, LogLevel,
+};
+
+/// Aplicativo externo mapeado para uma tecla em `[apps.<TECLA>]` no `telemetry.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppLaunch {
+    /// Executável: caminho ou nome procurado no PATH
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Texto exibido na ajuda (padrão: o próprio comando)
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Comando do console que abre um aplicativo configurado
+pub struct OpenAppCommand {
+    key: String,
+    usage: String,
+    help: String,
+    app: AppLaunch,
+}
+
+impl OpenAppCommand {
+    pub fn new(key: &str, app: AppLaunch) -> Self {
+        Self {
+            key: key.to_string(),
+            usage: key.to_uppercase(),
+            help: app.description.clone().unwrap_or_else(|| format!("abre {}", app.command)),
+            app,
+        }
+    }
+}
+
+impl Command for OpenAppCommand {
+    fn name(&self) -> &str {
+        "open_app"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &[]
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key)
+    }
+
+    fn usage(&self) -> &str {
+        &self.usage
+    }
+
+    fn help(&self) -> &str {
+        &self.help
+    }
+
+    fn execute(&self, _context: &CommandContext<'_>, _args: &[&str]) -> IoResult<()> {
+        let Some(program) = find_executable(&self.app.command) else {
+            log(LogLevel::Error, &format!("Aplicativo '{}' não encontrado", self.app.command));
+            return Ok(());
+        };
+
+        match spawn(&program, &self.app.args) {
+            Ok(mut child) => {
+                log(LogLevel::Info, &format!("Aplicativo '{}' aberto", self.app.command));
+                // Aguarda em outra thread para não deixar processos zumbis
+                thread::spawn(move || {
+                    let _ = child.wait();
+                });
+            }
+            Err(e) => log(LogLevel::Error, &format!("Falha ao abrir '{}': {}", self.app.command, e)),
+        }
+        Ok(())
+    }
+}
+
+/// Navegadores tentados em ordem: o padrão do sistema primeiro, depois
+/// alternativas comuns. Cada entrada é o executável e os argumentos antes da URL
+#[cfg(target_os = "windows")]
+const BROWSERS: &[(&str, &[&str])] = &[
+    // `start` é interno do cmd; o título vazio evita que a URL seja lida como título
+    ("cmd", &["/C", "start", ""]),
+    ("msedge", &[]),
+    ("chrome", &[]),
+    ("firefox", &[]),
+];
+
+#[cfg(target_os = "macos")]
+const BROWSERS: &[(&str, &[&str])] = &[
+    ("open", &[]),
+    ("open", &["-a", "Safari"]),
+    ("open", &["-a", "Google Chrome"]),
+    ("open", &["-a", "Firefox"]),
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const BROWSERS: &[(&str, &[&str])] = &[
+    ("xdg-open", &[]),
+    ("sensible-browser", &[]),
+    ("x-www-browser", &[]),
+    ("firefox", &[]),
+    ("google-chrome", &[]),
+    ("chromium", &[]),
+    ("microsoft-edge", &[]),
+];
+
+/// Lançadores como `xdg-open` saem logo com erro quando não há navegador associado
+const LAUNCH_CHECK: Duration = Duration::from_millis(1500);
+
+/// Considera aberto o processo que continua rodando ou sai com sucesso dentro de
+/// `LAUNCH_CHECK`; quem segue rodando é aguardado em outra thread, sem virar zumbi
+fn check_launch(mut child: Child) -> IoResult<()> {
+    let deadline = Instant::now() + LAUNCH_CHECK;
+    while Instant::now() < deadline {
+        match child.try_wait()? {
+            Some(status) if status.success() => return Ok(()),
+            Some(status) => return Err(io::Error::other(format!("encerrou com {}", status))),
+            None => thread::sleep(Duration::from_millis(50)),
+        }
+    }
+
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Comando do console que abre o navegador padrão numa URL: a passada como
+/// argumento, a de `browser_url` ou o dashboard web
+pub struct OpenBrowserCommand;
+
+impl OpenBrowserCommand {
+    /// `browser_url`, senão o dashboard web (com `0.0.0.0` trocado por `localhost`)
+    fn default_url(context: &CommandContext<'_>) -> Option<String> {
+        let config = &context.state.config;
+        config.browser_url.clone().or_else(|| {
+            config.web_address.as_ref()
+                .map(|address| format!("http://{}/", address.replace("0.0.0.0", "localhost")))
+        })
+    }
+}
+
+impl Command for OpenBrowserCommand {
+    fn name(&self) -> &str {
+        "open_browser"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["W", "browser", "open_browser"]
+    }
+
+    fn usage(&self) -> &str {
+        "W [url]"
+    }
+
+    fn help(&self) -> &str {
+        "abre o navegador (padrão: browser_url ou o dashboard web)"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, args: &[&str]) -> IoResult<()> {
+        let Some(url) = args.first().map(|url| url.to_string()).or_else(|| Self::default_url(context)) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "sem URL: use W <url> ou configure browser_url (ou web_address)",
+            ));
+        };
+
+        for (browser, prefix) in BROWSERS {
+            let Some(program) = find_executable(browser) else {
+                continue;
+            };
+            let mut process_args: Vec<String> = prefix.iter().map(|arg| arg.to_string()).collect();
+            process_args.push(url.clone());
+
+            match spawn(&program, &process_args).and_then(check_launch) {
+                Ok(()) => {
+                    log(LogLevel::Success, &format!("Navegador aberto com '{}' em {}", browser, url));
+                    return Ok(());
+                }
+                Err(e) => log(LogLevel::Warning, &format!("Falha ao abrir '{}': {}; tentando o próximo", browser, e)),
+            }
+        }
+
+        Err(io::Error::new(io::ErrorKind::NotFound, "nenhum navegador disponível"))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn spawn(program: &Path, args: &[String]) -> io::Result<Child> {
+    // `.cmd`/`.bat` não são executáveis diretos no Windows: passam pelo interpretador
+    let is_script = program.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cmd") || ext.eq_ignore_ascii_case("bat"));
+    if is_script {
+        Process::new("cmd").arg("/C").arg(program).args(args).spawn()
+    } else {
+        Process::new(program).args(args).spawn()
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn spawn(program: &Path, args: &[String]) -> io::Result<Child> {
+    // Pacotes `.app` são diretórios: abertos via `open`
+    if program.extension().is_some_and(|ext| ext == "app") {
+        Process::new("open").arg("-a").arg(program).arg("--args").args(args).spawn()
+    } else {
+        Process::new(program).args(args).spawn()
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn spawn(program: &Path, args: &[String]) -> io::Result<Child> {
+    Process::new(program).args(args).spawn()
+}
+
+/// Resolve o executável: caminhos são checados direto, nomes simples no PATH
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.exists().then(|| path.to_path_buf());
+    }
+
+    let extensions: &[&str] = if cfg!(target_os = "windows") { &["", ".exe", ".cmd", ".bat"] } else { &[""] };
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| extensions.iter().map(move |ext| dir.join(format!("{}{}", command, ext))))
+        .find(|candidate| candidate.is_file())
+}
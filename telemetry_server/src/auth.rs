@@ -0,0 +1,41 @@
+use std::env;
+
+/// Variável de ambiente com o token pré-compartilhado (mesmo nome no cliente)
+pub const AUTH_TOKEN_ENV: &str = "TELEMETRY_AUTH_TOKEN";
+
+/// Token exigido dos clientes; `None` (variável ausente ou vazia) desativa a autenticação
+pub fn token_from_env() -> Option<String> {
+    env::var(AUTH_TOKEN_ENV).ok().filter(|token| !token.is_empty())
+}
+
+/// Compara o token recebido com o esperado em tempo constante em relação ao conteúdo
+pub fn verify_token(expected: &str, received: Option<&str>) -> bool {
+    let Some(received) = received else {
+        return false;
+    };
+
+    let (expected, received) = (expected.as_bytes(), received.as_bytes());
+    if expected.len() != received.len() {
+        return false;
+    }
+
+    // Acumula as diferenças sem sair cedo, para não vazar a posição do primeiro erro
+    expected.iter()
+        .zip(received)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_token_content_length_and_presence() {
+        assert!(verify_token("segredo", Some("segredo")));
+        assert!(!verify_token("segredo", Some("segreda")));
+        assert!(!verify_token("segredo", Some("segredo-mais-longo")));
+        assert!(!verify_token("segredo", Some("")));
+        assert!(!verify_token("segredo", None));
+    }
+}
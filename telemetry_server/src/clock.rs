@@ -0,0 +1,66 @@
+/// Diferença abaixo da qual os relógios são considerados sincronizados: ela não
+/// se distingue do atraso da rede entre o envio do handshake e a chegada
+const TOLERANCE_MS: i64 = 1_000;
+
+/// Diferença que gera aviso sugerindo NTP
+const WARNING_MS: i64 = 2 * 60 * 1_000;
+
+/// Quanto o relógio de um cliente está adiantado (positivo) ou atrasado em relação
+/// ao do servidor, estimado pelo relógio informado no handshake
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClockOffset {
+    offset_ms: i64,
+}
+
+impl ClockOffset {
+    /// O atraso da rede até o handshake chegar entra como erro da estimativa
+    pub fn estimate(client_clock: u64, server_clock: u64) -> Self {
+        Self { offset_ms: client_clock as i64 - server_clock as i64 }
+    }
+
+    pub fn millis(&self) -> i64 {
+        self.offset_ms
+    }
+
+    /// Grande o bastante para ser corrigido nos timestamps
+    pub fn is_significant(&self) -> bool {
+        self.offset_ms.abs() >= TOLERANCE_MS
+    }
+
+    /// Grande o bastante para indicar relógio sem sincronização
+    pub fn is_suspicious(&self) -> bool {
+        self.offset_ms.abs() >= WARNING_MS
+    }
+
+    /// Timestamp do cliente convertido para o relógio do servidor
+    pub fn normalize(&self, timestamp: u64) -> u64 {
+        if !self.is_significant() {
+            return timestamp;
+        }
+        timestamp.saturating_add_signed(-self.offset_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrects_artificial_offset() {
+        let server_now = 1_700_000_000_000;
+        let ahead = ClockOffset::estimate(server_now + 5 * 60 * 1_000, server_now);
+        assert!(ahead.is_suspicious());
+        assert_eq!(ahead.normalize(server_now + 5 * 60 * 1_000 + 250), server_now + 250);
+
+        let behind = ClockOffset::estimate(server_now - 30_000, server_now);
+        assert!(behind.is_significant() && !behind.is_suspicious());
+        assert_eq!(behind.normalize(server_now - 29_000), server_now + 1_000);
+    }
+
+    #[test]
+    fn small_offsets_are_left_alone() {
+        let offset = ClockOffset::estimate(1_000_120, 1_000_000);
+        assert_eq!(offset.millis(), 120);
+        assert_eq!(offset.normalize(5_000), 5_000);
+    }
+}
@@ -0,0 +1,709 @@
+use std::{
+    io::{self, BufRead, Result as IoResult},
+    path::Path,
+    sync::{atomic::Ordering, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use telemetry_common::{aggregate::FleetSummary, commands::ServerCommand, format::format_bytes};
+
+use crate::{apps::{OpenAppCommand, OpenBrowserCommand}, epoch_millis, health::MemoryProbe, log, protocol_debug, LogLevel, ServerState};
+
+/// Nomes dos comandos embutidos, aceitos na lista `commands` do `telemetry.toml`:
+/// os de `builtins()` mais `open_app`, que registra um comando por tecla de `[apps]`
+pub fn builtin_commands() -> Vec<String> {
+    let mut names: Vec<String> = builtins().iter().map(|command| command.name().to_string()).collect();
+    names.push("open_app".to_string());
+    names
+}
+
+fn builtins() -> Vec<Box<dyn Command>> {
+    vec![
+        Box::new(HelpCommand),
+        Box::new(QuitCommand),
+        Box::new(ListClientsCommand),
+        Box::new(CollectNowCommand),
+        Box::new(BroadcastCommand),
+        Box::new(AggregateCommand),
+        Box::new(OpenBrowserCommand),
+        Box::new(SaveConfigCommand),
+        Box::new(StatsCommand::default()),
+        Box::new(QueryCommand),
+        Box::new(DebugProtocolCommand),
+    ]
+}
+
+/// Comando do console do servidor. Novos comandos implementam a trait e são
+/// registrados no `CommandRegistry`, sem tocar no laço do console
+pub trait Command: Send + Sync {
+    /// Nome usado na lista `commands` do `telemetry.toml`
+    fn name(&self) -> &str;
+    /// Entradas que disparam o comando (comparadas sem diferenciar maiúsculas)
+    fn keys(&self) -> &[&str];
+    fn matches(&self, key: &str) -> bool {
+        self.keys().iter().any(|k| k.eq_ignore_ascii_case(key))
+    }
+    /// Forma de uso exibida na ajuda
+    fn usage(&self) -> &str {
+        self.keys().first().copied().unwrap_or_default()
+    }
+    fn help(&self) -> &str;
+    /// Executa com os argumentos que seguem a tecla na linha digitada
+    fn execute(&self, context: &CommandContext<'_>, args: &[&str]) -> IoResult<()>;
+}
+
+/// O que um comando pode acessar ao executar
+pub struct CommandContext<'a> {
+    pub state: &'a ServerState,
+    pub registry: &'a CommandRegistry,
+}
+
+/// Mapeia as entradas do console para os comandos registrados
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<Box<dyn Command>>,
+}
+
+impl CommandRegistry {
+    /// Comandos embutidos habilitados na config (`help` sempre entra) e os
+    /// aplicativos de `[apps]`, quando `open_app` está habilitado
+    pub fn builtin(state: &ServerState) -> Self {
+        let enabled = |name: &str| {
+            name == "help" || state.config.commands.iter().any(|enabled| enabled.eq_ignore_ascii_case(name))
+        };
+
+        let mut registry = Self::default();
+        for command in builtins() {
+            if enabled(command.name()) {
+                registry.register(command);
+            }
+        }
+
+        if enabled("open_app") {
+            for (key, app) in &state.config.apps {
+                if registry.find(key).is_some() {
+                    log(LogLevel::Warning, &format!("Tecla '{}' de [apps] já é usada por outro comando", key));
+                    continue;
+                }
+                registry.register(Box::new(OpenAppCommand::new(key, app.clone())));
+            }
+        }
+        registry
+    }
+
+    pub fn register(&mut self, command: Box<dyn Command>) {
+        self.commands.push(command);
+    }
+
+    pub fn find(&self, key: &str) -> Option<&dyn Command> {
+        self.commands.iter()
+            .find(|command| command.matches(key))
+            .map(|command| command.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Command> {
+        self.commands.iter().map(|command| command.as_ref())
+    }
+
+    /// Interpreta e executa uma linha digitada; falhas só são logadas
+    pub fn dispatch(&self, state: &ServerState, line: &str) {
+        let mut parts = line.split_whitespace();
+        let Some(key) = parts.next() else {
+            return;
+        };
+        let args: Vec<&str> = parts.collect();
+
+        let Some(command) = self.find(key) else {
+            match disabled_command(state, key) {
+                Some(name) => log(LogLevel::Warning, &format!("Comando '{}' desabilitado (inclua '{}' em commands no telemetry.toml)", key, name)),
+                None => log(LogLevel::Warning, &format!("Comando desconhecido '{}' (H para ajuda)", key)),
+            }
+            return;
+        };
+
+        let context = CommandContext { state, registry: self };
+        if let Err(e) = command.execute(&context, &args) {
+            log(LogLevel::Warning, &format!("Comando '{}' falhou: {}", command.name(), e));
+        }
+    }
+}
+
+/// Nome em `commands` do comando embutido (ou app de `[apps]`) que responderia a
+/// `key` se estivesse habilitado
+fn disabled_command(state: &ServerState, key: &str) -> Option<String> {
+    if state.config.apps.keys().any(|app| app.eq_ignore_ascii_case(key)) {
+        return Some("open_app".to_string());
+    }
+    builtins().into_iter()
+        .find(|command| command.matches(key))
+        .map(|command| command.name().to_string())
+}
+
+/// Lê comandos do stdin numa thread própria até o EOF
+pub fn spawn_console(state: Arc<ServerState>, registry: CommandRegistry) -> io::Result<()> {
+    thread::Builder::new()
+        .name("console".to_string())
+        .spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                registry.dispatch(&state, &line);
+            }
+        })?;
+    Ok(())
+}
+
+struct HelpCommand;
+
+impl Command for HelpCommand {
+    fn name(&self) -> &str {
+        "help"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["H", "help"]
+    }
+
+    fn help(&self) -> &str {
+        "mostra esta ajuda"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, _args: &[&str]) -> IoResult<()> {
+        println!("⌨️  Comandos:");
+        for command in context.registry.iter() {
+            println!("   {:<8} {}", command.usage(), command.help());
+        }
+        Ok(())
+    }
+}
+
+struct QuitCommand;
+
+impl Command for QuitCommand {
+    fn name(&self) -> &str {
+        "quit"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["Q", "quit"]
+    }
+
+    fn help(&self) -> &str {
+        "encerra o servidor"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, _args: &[&str]) -> IoResult<()> {
+        log(LogLevel::Info, "Encerramento solicitado pelo console");
+        context.state.request_shutdown();
+        Ok(())
+    }
+}
+
+struct ListClientsCommand;
+
+impl Command for ListClientsCommand {
+    fn name(&self) -> &str {
+        "list_clients"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["L", "list", "list_clients"]
+    }
+
+    fn help(&self) -> &str {
+        "lista os clientes conectados"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, _args: &[&str]) -> IoResult<()> {
+        let clients = context.state.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if clients.is_empty() {
+            println!("👥 Nenhum cliente conectado");
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        println!("👥 {} cliente(s) conectado(s):", clients.len());
+        println!(
+            "   {:<38} {:<22} {:<20} {:<12} {:<14} ÚLTIMA AMOSTRA",
+            "ID", "ENDEREÇO", "HOSTNAME", "CONECTADO", "JSON INVÁLIDO"
+        );
+        for (addr, info) in clients.iter() {
+            let last_sample = match info.last_sample {
+                Some(at) => format!("há {}", format_elapsed(now - at)),
+                None => "-".to_string(),
+            };
+            let stats = &info.parse_stats;
+            let rejected = match stats.rejected {
+                0 => "-".to_string(),
+                rejected => format!("{} ({:.1}%)", rejected, stats.error_rate()),
+            };
+            println!(
+                "   {:<38} {:<22} {:<20} {:<12} {:<14} {}",
+                info.client_id,
+                addr.to_string(),
+                info.hostname.as_deref().unwrap_or("-"),
+                format_elapsed(now - info.connected_at),
+                rejected,
+                last_sample
+            );
+        }
+        Ok(())
+    }
+}
+
+struct CollectNowCommand;
+
+impl Command for CollectNowCommand {
+    fn name(&self) -> &str {
+        "collect_now"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["C", "collect_now"]
+    }
+
+    fn usage(&self) -> &str {
+        "C <id>"
+    }
+
+    fn help(&self) -> &str {
+        "pede uma amostra imediata ao cliente"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, args: &[&str]) -> IoResult<()> {
+        let [target] = args else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "uso: C <id do cliente ou endereço>"));
+        };
+
+        let addr = context.state.send_to_client(target, &ServerCommand::CollectNow.to_json())
+            .map_err(io::Error::other)?;
+        log(LogLevel::Info, &format!("Coleta imediata solicitada a {} ({})", target, addr));
+        Ok(())
+    }
+}
+
+struct BroadcastCommand;
+
+impl BroadcastCommand {
+    const USAGE: &'static str = "uso: B collect | B interval <ms> | B gpu on|off";
+
+    fn parse(args: &[&str]) -> Option<ServerCommand> {
+        match args {
+            ["collect"] => Some(ServerCommand::CollectNow),
+            ["interval", ms] => ms.parse().ok().map(|interval_ms| ServerCommand::SetInterval { interval_ms }),
+            ["gpu", "on"] => Some(ServerCommand::SetGpu { enabled: true }),
+            ["gpu", "off"] => Some(ServerCommand::SetGpu { enabled: false }),
+            _ => None,
+        }
+    }
+}
+
+impl Command for BroadcastCommand {
+    fn name(&self) -> &str {
+        "broadcast"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["B", "broadcast"]
+    }
+
+    fn usage(&self) -> &str {
+        "B <cmd>"
+    }
+
+    fn help(&self) -> &str {
+        "envia a todos os clientes: collect, interval <ms> ou gpu on|off"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, args: &[&str]) -> IoResult<()> {
+        let command = Self::parse(args)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, Self::USAGE))?;
+
+        let delivered = context.state.broadcast_to_clients(&command.to_json());
+        log(LogLevel::Info, &format!("Comando '{}' enviado a {} cliente(s)", command.name(), delivered));
+        Ok(())
+    }
+}
+
+struct AggregateCommand;
+
+impl Command for AggregateCommand {
+    fn name(&self) -> &str {
+        "aggregate"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["A", "aggregate"]
+    }
+
+    fn usage(&self) -> &str {
+        "A [lim]"
+    }
+
+    fn help(&self) -> &str {
+        "visão da frota: CPU média, memória somada e clientes acima do limiar de CPU"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, args: &[&str]) -> IoResult<()> {
+        let config = &context.state.config;
+        let threshold = match args {
+            [] => config.aggregate_cpu_threshold,
+            [raw] => raw.parse::<f32>()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("limiar inválido: {}", raw)))?,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "uso: A [limiar de CPU]")),
+        };
+        let ttl = Duration::from_secs(config.aggregate_ttl_secs);
+
+        // Só entram clientes cuja última amostra ainda está dentro do TTL
+        let now = Instant::now();
+        let fresh: Vec<_> = context.state.clients.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(_, info)| info.last_sample.is_some_and(|at| now - at <= ttl))
+            .map(|(addr, _)| *addr)
+            .collect();
+        let (summary, stale) = {
+            let samples = context.state.samples.lock().unwrap_or_else(|e| e.into_inner());
+            let summary = FleetSummary::from_samples(fresh.iter().filter_map(|addr| samples.get(addr)).map(|sample| &sample.metrics), threshold);
+            let stale = samples.len().saturating_sub(summary.clients);
+            (summary, stale)
+        };
+
+        let formatters = &context.state.formatters;
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        println!(
+            "🌐 Frota: {} cliente(s) ativo(s) ({} ignorado(s) sem amostra há mais de {}s)",
+            summary.clients, stale, ttl.as_secs()
+        );
+        println!("   CPU média:             {}", or_dash(summary.cpu_avg.map(|cpu| formatters.format("CPU", cpu))));
+        println!("   Memória somada:        {}", or_dash(summary.mem_total.map(|mem| formatters.format("MEM", mem))));
+        if let Some(percent) = summary.mem_percent_avg {
+            println!("   Memória média (%):     {}", formatters.format("MEM_PERCENT", percent));
+        }
+        println!("   {:<23}{}", format!("CPU acima de {:.0}%:", threshold), summary.above_threshold);
+        Ok(())
+    }
+}
+
+struct SaveConfigCommand;
+
+impl Command for SaveConfigCommand {
+    fn name(&self) -> &str {
+        "save_config"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["P", "save", "save_config"]
+    }
+
+    fn usage(&self) -> &str {
+        "P [arq]"
+    }
+
+    fn help(&self) -> &str {
+        "salva a configuração em vigor em TOML (padrão: save_config_path)"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, args: &[&str]) -> IoResult<()> {
+        let path = match args {
+            [] => context.state.config.save_config_path.as_str(),
+            [path] => path,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "uso: P [arquivo]")),
+        };
+
+        // O que foi alternado pelo console desde o boot também entra no arquivo
+        let mut config = context.state.config.clone();
+        config.debug_protocol = protocol_debug::enabled();
+        config.save(Path::new(path))
+            .map_err(|e| io::Error::new(e.kind(), format!("não foi possível gravar {}: {}", path, e)))?;
+        log(LogLevel::Success, &format!("Configuração salva em {} (use como telemetry.toml no próximo boot)", path));
+        Ok(())
+    }
+}
+
+/// Diagnóstico do próprio servidor. Guarda a leitura anterior do contador de
+/// amostras para a taxa entre dois `S`
+#[derive(Default)]
+struct StatsCommand {
+    last: Mutex<Option<(Instant, u64)>>,
+}
+
+/// Números do `S`, calculados só quando o comando roda
+#[derive(Debug, PartialEq)]
+struct ServerStats {
+    uptime: Duration,
+    processed: u64,
+    clients: usize,
+    connections: usize,
+    /// Pontos nos históricos das sessões ativas
+    active_points: usize,
+    /// Históricos retidos de clientes desconectados e seus pontos
+    parked: usize,
+    parked_points: usize,
+    /// Amostras/s desde o início e desde o `S` anterior
+    total_rate: f64,
+    recent_rate: Option<f64>,
+}
+
+impl StatsCommand {
+    fn snapshot(&self, state: &ServerState, now: Instant) -> ServerStats {
+        let uptime = now - state.started_at;
+        let processed = state.samples_processed.load(Ordering::Relaxed);
+
+        let (clients, active_points) = {
+            let clients = state.clients.lock().unwrap_or_else(|e| e.into_inner());
+            let points = clients.values()
+                .map(|info| info.history.lock().unwrap_or_else(|e| e.into_inner()).retained_points())
+                .sum();
+            (clients.len(), points)
+        };
+        let connections = state.connections.lock().unwrap_or_else(|e| e.into_inner()).len();
+        let (parked, parked_points) = {
+            let histories = state.histories.lock().unwrap_or_else(|e| e.into_inner());
+            (histories.len(), histories.values().map(|history| history.retained_points()).sum())
+        };
+
+        let rate = |samples: u64, elapsed: Duration| samples as f64 / elapsed.as_secs_f64().max(0.001);
+        let recent_rate = self.last.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace((now, processed))
+            .map(|(at, count)| rate(processed.saturating_sub(count), now - at));
+
+        ServerStats {
+            uptime,
+            processed,
+            clients,
+            connections,
+            active_points,
+            parked,
+            parked_points,
+            total_rate: rate(processed, uptime),
+            recent_rate,
+        }
+    }
+}
+
+impl Command for StatsCommand {
+    fn name(&self) -> &str {
+        "stats"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["S", "stats"]
+    }
+
+    fn help(&self) -> &str {
+        "diagnóstico do servidor: memória, threads, históricos e amostras/s"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, _args: &[&str]) -> IoResult<()> {
+        let stats = self.snapshot(context.state, Instant::now());
+
+        let usage = MemoryProbe::new().usage();
+        println!("🩺 Diagnóstico do servidor (no ar há {})", format_elapsed(stats.uptime));
+        println!(
+            "   Memória do processo:   {}",
+            usage.as_ref().map_or_else(|| "-".to_string(), |usage| format_bytes(usage.memory_bytes as f32))
+        );
+        println!(
+            "   Threads:               {}",
+            usage.and_then(|usage| usage.threads).map_or_else(|| "-".to_string(), |threads| threads.to_string())
+        );
+        println!("   Clientes:              {} ({} conexão(ões) TCP)", stats.clients, stats.connections);
+        println!(
+            "   Históricos:            {} ponto(s) em {} sessão(ões) ativa(s), {} em {} retido(s) de clientes desconectados",
+            stats.active_points, stats.clients, stats.parked_points, stats.parked
+        );
+        match stats.recent_rate {
+            Some(recent) => println!(
+                "   Amostras processadas:  {} ({:.1}/s desde o início, {:.1}/s desde o último S)",
+                stats.processed, stats.total_rate, recent
+            ),
+            None => println!("   Amostras processadas:  {} ({:.1}/s desde o início)", stats.processed, stats.total_rate),
+        }
+        Ok(())
+    }
+}
+
+/// Consulta ao banco SQLite: média, mínimo e máximo de uma métrica numa janela
+struct QueryCommand;
+
+impl QueryCommand {
+    const USAGE: &'static str = "uso: D <cliente|*> <métrica> [janela: 30m, 1h, 7d...]";
+    const DEFAULT_WINDOW: &'static str = "1h";
+
+    /// `30s`, `15m`, `1h` ou `7d`
+    fn parse_window(raw: &str) -> Option<Duration> {
+        let split = raw.len().checked_sub(1)?;
+        let (amount, unit) = raw.split_at(split);
+        let seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86_400,
+            _ => return None,
+        };
+        let amount: u64 = amount.parse().ok().filter(|amount| *amount > 0)?;
+        Some(Duration::from_secs(amount.checked_mul(seconds)?))
+    }
+}
+
+impl Command for QueryCommand {
+    fn name(&self) -> &str {
+        "query"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["D", "query"]
+    }
+
+    fn usage(&self) -> &str {
+        "D <...>"
+    }
+
+    fn help(&self) -> &str {
+        "histórico no SQLite: D <cliente|*> <métrica> [janela], com média, mínimo e máximo"
+    }
+
+    fn execute(&self, context: &CommandContext<'_>, args: &[&str]) -> IoResult<()> {
+        let Some(store) = &context.state.sqlite else {
+            return Err(io::Error::other("banco SQLite desativado (configure [sqlite] ou use --sqlite)"));
+        };
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, Self::USAGE);
+        let (client, metric, raw_window) = match args {
+            [client, metric] => (*client, *metric, Self::DEFAULT_WINDOW),
+            [client, metric, window] => (*client, *metric, *window),
+            _ => return Err(invalid()),
+        };
+        let window = Self::parse_window(raw_window).ok_or_else(invalid)?;
+
+        let since = epoch_millis().saturating_sub(window.as_millis() as u64);
+        let metric = metric.to_uppercase();
+        let label = if client == "*" { "todos os clientes" } else { client };
+        let Some(summary) = store.summarize(client, &metric, since)? else {
+            println!("🗄️  Nenhuma amostra de {} para {} na janela de {}", metric, label, raw_window);
+            return Ok(());
+        };
+
+        let formatters = &context.state.formatters;
+        let format = |value: f64| formatters.format(&metric, value as f32);
+        println!("🗄️  {} de {} na janela de {} ({} amostra(s))", metric, label, raw_window, summary.count);
+        println!("   Média:   {}", format(summary.avg));
+        println!("   Mínimo:  {}", format(summary.min));
+        println!("   Máximo:  {}", format(summary.max));
+        Ok(())
+    }
+}
+
+/// Liga ou desliga em runtime o log cru do protocolo (`--debug-protocol`)
+struct DebugProtocolCommand;
+
+impl Command for DebugProtocolCommand {
+    fn name(&self) -> &str {
+        "debug_protocol"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["V", "debug"]
+    }
+
+    fn usage(&self) -> &str {
+        "V [on|off]"
+    }
+
+    fn help(&self) -> &str {
+        "alterna o log cru do protocolo (hexa, texto e resultado do parse) no stderr"
+    }
+
+    fn execute(&self, _context: &CommandContext<'_>, args: &[&str]) -> IoResult<()> {
+        let enabled = match args {
+            [] => !protocol_debug::enabled(),
+            [arg] if arg.eq_ignore_ascii_case("on") => true,
+            [arg] if arg.eq_ignore_ascii_case("off") => false,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "uso: V [on|off]")),
+        };
+        protocol_debug::set_enabled(enabled);
+        if enabled {
+            println!("🔬 Debug do protocolo ligado: cada mensagem recebida aparece no stderr como PROTO");
+        } else {
+            println!("🔬 Debug do protocolo desligado");
+        }
+        Ok(())
+    }
+}
+
+/// Duração compacta para a tabela: `2h 05m`, `3m 12s` ou `8s`
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{apps::AppLaunch, config::ServerConfig, history::MetricHistory, output::MemorySink, session::ClientSession, TelemetryServer};
+
+    fn history_with(samples: u64) -> MetricHistory {
+        let mut history = MetricHistory::new(10, &[]);
+        for timestamp in 0..samples {
+            history.record(timestamp * 1_000, &HashMap::from([("CPU".to_string(), 10.0), ("MEM".to_string(), 1.0)]));
+        }
+        history
+    }
+
+    /// Os pontos somam sessões ativas e históricos retidos, lidos só na hora do `S`,
+    /// e a taxa recente usa a leitura do `S` anterior
+    #[test]
+    fn stats_aggregate_histories_and_rates() {
+        let config = ServerConfig { address: "127.0.0.1:0".to_string(), discovery: false, ..ServerConfig::default() };
+        let server = TelemetryServer::with_output(config, Arc::new(MemorySink::default())).unwrap();
+        let state = &server.state;
+
+        let session = ClientSession::new("127.0.0.1:5000".parse().unwrap(), Arc::clone(state));
+        *session.history.lock().unwrap() = history_with(3);
+        state.histories.lock().unwrap().insert("antigo".to_string(), history_with(2));
+        state.histories.lock().unwrap().insert("outro".to_string(), history_with(1));
+
+        let command = StatsCommand::default();
+        state.samples_processed.store(10, Ordering::Relaxed);
+        let first = command.snapshot(state, state.started_at + Duration::from_secs(5));
+        assert_eq!((first.clients, first.active_points), (1, 6));
+        assert_eq!((first.parked, first.parked_points), (2, 6));
+        assert_eq!(first.total_rate, 2.0);
+        assert_eq!(first.recent_rate, None);
+
+        // A sessão segue gravando depois do primeiro `S`
+        session.history.lock().unwrap().record(5_000, &HashMap::from([("CPU".to_string(), 20.0)]));
+        state.samples_processed.store(19, Ordering::Relaxed);
+        let second = command.snapshot(state, state.started_at + Duration::from_secs(8));
+        assert_eq!(second.active_points, 7);
+        assert_eq!(second.recent_rate, Some(3.0));
+        assert_eq!(second.uptime, Duration::from_secs(8));
+    }
+
+    /// Tecla de comando fora de `commands` é reconhecida como desabilitada, não desconhecida
+    #[test]
+    fn disabled_commands_are_told_apart_from_unknown() {
+        let mut config = ServerConfig { address: "127.0.0.1:0".to_string(), discovery: false, ..ServerConfig::default() };
+        config.commands = vec!["quit".to_string()];
+        config.apps.insert("E".to_string(), AppLaunch { command: "editor".to_string(), args: Vec::new(), description: None });
+        let server = TelemetryServer::with_output(config, Arc::new(MemorySink::default())).unwrap();
+        let state = &server.state;
+
+        let registry = CommandRegistry::builtin(state);
+        assert!(registry.find("q").is_some());
+        assert!(registry.find("S").is_none());
+        assert_eq!(disabled_command(state, "S").as_deref(), Some("stats"));
+        assert_eq!(disabled_command(state, "e").as_deref(), Some("open_app"));
+        assert_eq!(disabled_command(state, "Z"), None);
+        assert!(builtin_commands().iter().all(|name| ServerConfig::default().commands.contains(name)));
+    }
+}
@@ -0,0 +1,565 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    fs,
+    io::{self, Result as IoResult, Write},
+    net::SocketAddr,
+    path::Path,
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+use telemetry_common::{format::MetricUnit, framing::Framing, UNIX_ADDRESS_PREFIX};
+
+use crate::{
+    alerts::AlertRule, apps::AppLaunch, commands::builtin_commands, history::HistoryTier, influx::InfluxConfig, logger::LogLevel,
+    ranges::MetricRange, replay::ReplaySpeed, severity::SeverityThresholds, sqlite::SqliteConfig, statsd::StatsdConfig,
+};
+
+/// Arquivo de configuração procurado no diretório de trabalho
+pub const CONFIG_PATH: &str = "telemetry.toml";
+/// Destino padrão do comando `P`: ao lado do `telemetry.toml`, sem sobrescrevê-lo
+const DEFAULT_SAVE_CONFIG_PATH: &str = "telemetry.saved.toml";
+
+// LocalHost IP for Tests
+// const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+const DEFAULT_ADDRESS: &str = "0.0.0.0:8080";
+const DEFAULT_BUFFER_SIZE: usize = 4096;
+const DEFAULT_MAX_LINE_SIZE: usize = 64 * 1024;
+const DEFAULT_HISTORY_SIZE: usize = 60;
+/// O cliente envia heartbeat a cada 5s, então 10s sem dados já indica atraso
+const DEFAULT_READ_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_MAX_TIMEOUTS: u32 = 3;
+const DEFAULT_MAX_CONNECTIONS: usize = 256;
+/// Folga para um cliente em backoff, mas barra loops de reconexão sem espera
+const DEFAULT_MAX_CONNECTIONS_PER_IP: u32 = 60;
+const DEFAULT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const DEFAULT_PID_FILE: &str = "telemetry_server.pid";
+/// Log do modo daemon quando `log_file` não foi configurado: sem terminal, é o único destino
+const DEFAULT_DAEMON_LOG_FILE: &str = "telemetry_server.log";
+/// Clientes sem amostra há mais que isso ficam fora da visão da frota
+const DEFAULT_AGGREGATE_TTL_SECS: u64 = 30;
+const DEFAULT_AGGREGATE_CPU_THRESHOLD: f32 = 80.0;
+const DEFAULT_PARSE_ERROR_THRESHOLD: f32 = 10.0;
+/// Variável de ambiente com o nível mínimo de log (`error`, `warn` ou `info`)
+const LOG_LEVEL_ENV: &str = "TELEMETRY_LOG";
+
+/// Arquivo e velocidade do modo `--replay`
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    pub path: String,
+    pub speed: ReplaySpeed,
+}
+
+/// Protocolo de transporte em que o servidor escuta
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    /// Conexões persistentes, com handshake, framing e TLS opcionais
+    #[default]
+    Tcp,
+    /// Cada datagrama é uma amostra independente; perdas ocasionais são aceitas
+    Udp,
+}
+
+/// Formato da telemetria exibida no stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// Painel com emojis (ou uma linha por amostra fora de um TTY)
+    #[default]
+    Pretty,
+    /// Uma linha JSON por amostra, para `jq`, Logstash ou Vector; logs vão para o stderr
+    Json,
+}
+
+impl OutputMode {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(OutputMode::Pretty),
+            "json" => Ok(OutputMode::Json),
+            _ => Err(format!("Saída inválida '{}' (use pretty ou json)", raw)),
+        }
+    }
+}
+
+/// Configuração do servidor carregada de `telemetry.toml`.
+/// Campos ausentes no arquivo assumem os valores padrão.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Endereço de bind do listener TCP, ou `unix:/caminho` para um Unix domain socket
+    pub address: String,
+    /// Transporte: `tcp` (padrão) ou `udp`
+    pub transport: TransportKind,
+    /// Capacidade do `BufReader` de cada conexão
+    pub buffer_size: usize,
+    /// Maior linha aceita no framing por linha; linhas maiores são descartadas
+    pub max_line_size: usize,
+    /// Limpa a tela a cada atualização da telemetria
+    pub clear_screen: bool,
+    /// Formato da telemetria no stdout: `pretty` (padrão) ou `json`
+    pub output: OutputMode,
+    /// Delimitação das mensagens: `line` (padrão) ou `length`
+    pub framing: Framing,
+    /// Arquivo CSV onde cada amostra recebida é gravada (desativado se ausente)
+    pub csv_path: Option<String>,
+    /// Grava cada amostra recebida em JSON Lines, reproduzível com `--replay`
+    pub record_path: Option<String>,
+    /// Reproduz uma gravação em vez de abrir o socket (só pela linha de comando)
+    #[serde(skip)]
+    pub replay: Option<ReplayOptions>,
+    /// Diretório dos arquivos `telemetry-AAAA-MM-DD.jsonl`, um por dia (desativado se ausente)
+    pub jsonl_dir: Option<String>,
+    /// Banco SQLite com todas as amostras, para consultas históricas (`[sqlite]`), desativado se ausente
+    pub sqlite: Option<SqliteConfig>,
+    /// Endereço HTTP do endpoint Prometheus `/metrics` (desativado se ausente)
+    pub metrics_address: Option<String>,
+    /// Endereço do dashboard web com WebSocket (desativado se ausente)
+    pub web_address: Option<String>,
+    /// URL aberta pelo comando `W` (padrão: o dashboard web)
+    pub browser_url: Option<String>,
+    /// Responde aos broadcasts de descoberta dos clientes na porta UDP 8099
+    pub discovery: bool,
+    /// Endereço HTTP do healthcheck `/health` (desativado se ausente)
+    pub health_address: Option<String>,
+    /// Métricas exibidas na tela (`--show cpu,mem`); vazio exibe todas
+    pub show: Vec<String>,
+    /// Quantidade de amostras por métrica na janela de mín/média/máx
+    pub history_size: usize,
+    /// Camadas de downsampling das amostras que saem da janela (`[[history_tiers]]`);
+    /// lista vazia descarta as antigas
+    pub history_tiers: Vec<HistoryTier>,
+    /// Certificado PEM do servidor; com `tls_key`, habilita TLS
+    pub tls_cert: Option<String>,
+    /// Chave privada PEM do certificado
+    pub tls_key: Option<String>,
+    /// Timeout de leitura por conexão em segundos (`0` desativa)
+    pub read_timeout_secs: u64,
+    /// Timeouts consecutivos até o cliente ser considerado morto
+    pub max_timeouts: u32,
+    /// Conexões TCP simultâneas aceitas; as excedentes são fechadas na hora (0 desativa)
+    pub max_connections: usize,
+    /// Conexões novas aceitas do mesmo IP por minuto (0 desativa)
+    pub max_connections_per_ip_per_minute: u32,
+    /// Roda em background como serviço (Unix), sem console de comandos
+    pub daemon: bool,
+    /// Arquivo com o PID do processo no modo daemon, removido no encerramento
+    pub pid_file: String,
+    /// Arquivo que recebe uma cópia de cada linha de log (desativado se ausente)
+    pub log_file: Option<String>,
+    /// Tamanho a partir do qual o arquivo de log é rotacionado para `<arquivo>.1`
+    pub log_max_bytes: u64,
+    /// Nível mínimo exibido: `info`, `warning` ou `error`
+    pub log_level: LogLevel,
+    /// Prefixa as linhas de log com emojis
+    pub log_emojis: bool,
+    /// Loga cada mensagem crua recebida (texto e hexa) e o resultado do parse, no stderr
+    pub debug_protocol: bool,
+    /// Comandos do console habilitados (`help` está sempre disponível)
+    pub commands: Vec<String>,
+    /// Idade máxima da última amostra para o cliente entrar na visão da frota (`A`)
+    pub aggregate_ttl_secs: u64,
+    /// CPU (%) acima da qual o cliente é contado na visão da frota
+    pub aggregate_cpu_threshold: f32,
+    /// Taxa de JSON inválido (%) de um cliente que gera aviso de protocolo; `0` desativa
+    pub parse_error_threshold: f32,
+    /// Aplicativos abertos por tecla no console (`[apps.E]`)
+    pub apps: BTreeMap<String, AppLaunch>,
+    /// Faixas plausíveis por métrica (`[ranges.CPU]`), somadas às embutidas
+    pub ranges: HashMap<String, MetricRange>,
+    /// Limiares de cor no painel (`[severity.CPU]`), somados aos embutidos
+    pub severity: HashMap<String, SeverityThresholds>,
+    /// Unidade e casas decimais de métricas customizadas (`[units.FAN_RPM]`), por nome em maiúsculas
+    pub units: HashMap<String, MetricUnit>,
+    /// Seções do painel e as métricas de cada uma (`Energia = ["BATTERY"]`), somadas às embutidas
+    pub categories: HashMap<String, Vec<String>>,
+    /// Regras de alerta por limiar (`[[alerts]]`)
+    pub alerts: Vec<AlertRule>,
+    /// Exportação para o InfluxDB (`[influx]`), desativada se ausente
+    pub influx: Option<InfluxConfig>,
+    /// Encaminhamento de cada métrica ao StatsD/DogStatsD (`[statsd]`), desativado se ausente
+    pub statsd: Option<StatsdConfig>,
+    /// Arquivo escrito pelo comando `P` do console quando ele vem sem caminho
+    pub save_config_path: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_ADDRESS.to_string(),
+            transport: TransportKind::Tcp,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            max_line_size: DEFAULT_MAX_LINE_SIZE,
+            clear_screen: true,
+            output: OutputMode::Pretty,
+            framing: Framing::Line,
+            csv_path: None,
+            record_path: None,
+            jsonl_dir: None,
+            sqlite: None,
+            replay: None,
+            metrics_address: None,
+            web_address: None,
+            health_address: None,
+            discovery: true,
+            browser_url: None,
+            show: Vec::new(),
+            history_size: DEFAULT_HISTORY_SIZE,
+            history_tiers: HistoryTier::defaults(),
+            tls_cert: None,
+            tls_key: None,
+            read_timeout_secs: DEFAULT_READ_TIMEOUT_SECS,
+            max_timeouts: DEFAULT_MAX_TIMEOUTS,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_connections_per_ip_per_minute: DEFAULT_MAX_CONNECTIONS_PER_IP,
+            daemon: false,
+            pid_file: DEFAULT_PID_FILE.to_string(),
+            log_file: None,
+            log_max_bytes: DEFAULT_LOG_MAX_BYTES,
+            log_level: LogLevel::Info,
+            log_emojis: true,
+            debug_protocol: false,
+            commands: builtin_commands(),
+            aggregate_ttl_secs: DEFAULT_AGGREGATE_TTL_SECS,
+            aggregate_cpu_threshold: DEFAULT_AGGREGATE_CPU_THRESHOLD,
+            parse_error_threshold: DEFAULT_PARSE_ERROR_THRESHOLD,
+            apps: BTreeMap::new(),
+            ranges: HashMap::new(),
+            severity: HashMap::new(),
+            units: HashMap::new(),
+            categories: HashMap::new(),
+            alerts: Vec::new(),
+            influx: None,
+            statsd: None,
+            save_config_path: DEFAULT_SAVE_CONFIG_PATH.to_string(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Carrega a configuração do arquivo; se ele não existir, usa os padrões
+    pub fn load(path: &Path) -> IoResult<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+
+        let mut config: Self = toml::from_str(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        // As métricas são comparadas sem diferenciar maiúsculas
+        config.units = config.units.into_iter()
+            .map(|(name, unit)| (name.to_uppercase(), unit))
+            .collect();
+
+        if config.buffer_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "buffer_size deve ser maior que zero",
+            ));
+        }
+
+        if config.max_line_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "max_line_size deve ser maior que zero",
+            ));
+        }
+
+        if config.history_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "history_size deve ser maior que zero",
+            ));
+        }
+
+        if config.history_tiers.iter().any(|tier| tier.bucket_secs == 0 || tier.buckets == 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "history_tiers exige bucket_secs e buckets maiores que zero",
+            ));
+        }
+
+        // Cada camada agrega a anterior, então a resolução só pode diminuir
+        if config.history_tiers.windows(2).any(|pair| pair[1].bucket_secs <= pair[0].bucket_secs) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "history_tiers deve ter bucket_secs crescente",
+            ));
+        }
+
+        if config.max_timeouts == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "max_timeouts deve ser maior que zero",
+            ));
+        }
+
+        if config.log_max_bytes == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "log_max_bytes deve ser maior que zero",
+            ));
+        }
+
+        let builtins = builtin_commands();
+        if let Some(unknown) = config.commands.iter()
+            .find(|name| !builtins.iter().any(|known| known.eq_ignore_ascii_case(name)))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("comando desconhecido em commands: {} (use {})", unknown, builtins.join(", ")),
+            ));
+        }
+
+        if let Some(key) = config.apps.keys().find(|key| key.is_empty() || key.contains(char::is_whitespace)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("tecla inválida em apps: '{}'", key),
+            ));
+        }
+
+        if let Some((name, _)) = config.severity.iter().find(|(_, value)| value.warning > value.critical) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("severidade de {}: warning maior que critical", name),
+            ));
+        }
+
+        if let Some((name, _)) = config.ranges.iter().find(|(_, range)| range.min > range.max) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("faixa de {}: min maior que max", name),
+            ));
+        }
+
+        if let Some(influx) = &config.influx {
+            influx.validate().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+        if let Some(sqlite) = &config.sqlite {
+            sqlite.validate().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+        if let Some(statsd) = &config.statsd {
+            statsd.validate().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+
+        if config.tls_cert.is_some() != config.tls_key.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "tls_cert e tls_key devem ser informados juntos",
+            ));
+        }
+
+        Ok(config)
+    }
+
+    /// Grava a configuração em vigor (arquivo, ambiente e linha de comando) em TOML,
+    /// pronta para ser usada como `telemetry.toml` no próximo boot. Escreve num
+    /// temporário e renomeia, então uma falha nunca deixa o destino pela metade.
+    /// O arquivo pode conter tokens (`influx`), por isso nasce só com permissão
+    /// do dono (`0600`) no unix
+    pub fn save(&self, path: &Path) -> IoResult<()> {
+        let content = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        // Um temporário antigo manteria as permissões de quando foi criado
+        match fs::remove_file(&temporary) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&temporary)?;
+        write!(file, "# Gerado pelo comando save_config do telemetry_server\n\n{}", content)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temporary, path)
+    }
+
+    /// Sobrescreve a configuração com variáveis de ambiente: `TELEMETRY_LOG`
+    /// define o nível mínimo de log sem editar o arquivo
+    pub fn apply_env(&mut self) -> Result<(), String> {
+        if let Ok(raw) = env::var(LOG_LEVEL_ENV) {
+            self.log_level = LogLevel::parse(&raw).map_err(|e| format!("{}: {}", LOG_LEVEL_ENV, e))?;
+        }
+        Ok(())
+    }
+
+    /// Aplica a linha de comando sobre o arquivo:
+    /// `telemetry_server [ENDEREÇO] [--no-clear] [--no-discovery] [--output pretty|json] [--udp] [--show cpu,mem]
+    /// [--record ARQUIVO] [--jsonl-dir DIR] [--sqlite ARQUIVO] [--replay ARQUIVO [--replay-speed 1x|2x|instant]]
+    /// [--daemon] [--pid-file ARQUIVO] [--log-file ARQUIVO] [--debug-protocol]`
+    pub fn apply_args(&mut self, mut args: impl Iterator<Item = String>) -> Result<(), String> {
+        let mut address: Option<String> = None;
+        let mut replay_speed: Option<ReplaySpeed> = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-clear" => self.clear_screen = false,
+                "--no-discovery" => self.discovery = false,
+                "--udp" => self.transport = TransportKind::Udp,
+                "--output" => {
+                    self.output = OutputMode::parse(&args.next().ok_or("--output exige pretty ou json")?)?;
+                }
+                "--show" => {
+                    let list = args.next().ok_or("--show exige a lista de métricas (ex.: cpu,mem)")?;
+                    self.show = list.split(',').map(str::trim).filter(|name| !name.is_empty()).map(str::to_string).collect();
+                    if self.show.is_empty() {
+                        return Err("--show exige ao menos uma métrica".to_string());
+                    }
+                }
+                "--record" => {
+                    self.record_path = Some(args.next().ok_or("--record exige o caminho do arquivo")?);
+                }
+                "--jsonl-dir" => {
+                    self.jsonl_dir = Some(args.next().ok_or("--jsonl-dir exige o diretório de saída")?);
+                }
+                "--sqlite" => {
+                    let path = args.next().ok_or("--sqlite exige o caminho do banco")?;
+                    self.sqlite.get_or_insert_with(SqliteConfig::default).path = path;
+                }
+                "--daemon" => self.daemon = true,
+                "--pid-file" => {
+                    self.pid_file = args.next().ok_or("--pid-file exige o caminho do arquivo")?;
+                }
+                "--log-file" => {
+                    self.log_file = Some(args.next().ok_or("--log-file exige o caminho do arquivo")?);
+                }
+                "--debug-protocol" => self.debug_protocol = true,
+                "--replay" => {
+                    let path = args.next().ok_or("--replay exige o caminho da gravação")?;
+                    self.replay = Some(ReplayOptions { path, speed: ReplaySpeed::Factor(1.0) });
+                }
+                "--replay-speed" => {
+                    replay_speed = Some(ReplaySpeed::parse(&args.next().ok_or("--replay-speed exige 1x, 2x, instant...")?)?);
+                }
+                _ if arg.starts_with('-') => return Err(format!("Opção desconhecida: {}", arg)),
+                _ if address.is_none() => address = Some(arg),
+                _ => return Err(format!("Argumento inesperado: {}", arg)),
+            }
+        }
+
+        match (&mut self.replay, replay_speed) {
+            (Some(replay), Some(speed)) => replay.speed = speed,
+            (None, Some(_)) => return Err("--replay-speed exige --replay".to_string()),
+            _ => {}
+        }
+
+        // Sem terminal, o log precisa de um arquivo para ser visto
+        if self.daemon && self.log_file.is_none() {
+            self.log_file = Some(DEFAULT_DAEMON_LOG_FILE.to_string());
+        }
+
+        let raw = address.unwrap_or_else(|| self.address.clone());
+        if let Some(path) = raw.strip_prefix(UNIX_ADDRESS_PREFIX) {
+            if path.is_empty() {
+                return Err(format!("Endereço inválido '{}': falta o caminho do socket (ex.: unix:/run/telemetry.sock)", raw));
+            }
+            self.address = raw;
+            return Ok(());
+        }
+        let address = raw.parse::<SocketAddr>()
+            .map_err(|e| format!("Endereço inválido '{}': {} (ex.: 0.0.0.0:8080)", raw, e))?;
+        self.address = address.to_string();
+        Ok(())
+    }
+
+    /// Timeout aplicado ao socket de cada cliente
+    pub fn read_timeout(&self) -> Option<Duration> {
+        (self.read_timeout_secs > 0).then(|| Duration::from_secs(self.read_timeout_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_toml_file_and_falls_back_to_defaults() {
+        let path = env::temp_dir().join(format!("telemetry-load-{}.toml", std::process::id()));
+        fs::write(&path, "address = \"127.0.0.1:7000\"\nbuffer_size = 8192\nclear_screen = false\ncommands = [\"quit\"]\n").unwrap();
+        let loaded = ServerConfig::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.address, "127.0.0.1:7000");
+        assert_eq!(loaded.buffer_size, 8192);
+        assert!(!loaded.clear_screen);
+        assert_eq!(loaded.commands, ["quit"]);
+        assert_eq!(loaded.max_line_size, DEFAULT_MAX_LINE_SIZE);
+
+        let missing = ServerConfig::load(&path).unwrap();
+        assert_eq!(missing.buffer_size, DEFAULT_BUFFER_SIZE);
+        assert_eq!(missing.address, DEFAULT_ADDRESS);
+
+        fs::write(&path, "buffer_size = 0\n").unwrap();
+        let invalid = ServerConfig::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn saved_config_loads_back() {
+        let mut config: ServerConfig = toml::from_str(
+            r#"
+            output = "json"
+            log_level = "warn"
+            show = ["cpu", "mem"]
+
+            [ranges.CPU]
+            max = 100.0
+
+            [severity.TEMP]
+            warning = 70.0
+            critical = 85.0
+
+            [units.FAN_RPM]
+            suffix = "RPM"
+            decimals = 0
+
+            [categories]
+            Energia = ["BATTERY", "POWER"]
+
+            [statsd]
+            address = "127.0.0.1:8125"
+            tags = false
+
+            [[alerts]]
+            metric = "CPU"
+            threshold = 90.0
+            "#,
+        )
+        .unwrap();
+        config.apply_args(["127.0.0.1:9000".to_string(), "--no-clear".to_string()].into_iter()).unwrap();
+
+        let path = env::temp_dir().join(format!("telemetry-saved-{}.toml", std::process::id()));
+        fs::write(&path, "antigo").unwrap();
+        config.save(&path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let loaded = ServerConfig::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.address, "127.0.0.1:9000");
+        assert!(!loaded.clear_screen);
+        assert_eq!(loaded.output, OutputMode::Json);
+        assert_eq!(loaded.log_level, LogLevel::Warning);
+        assert_eq!(loaded.show, ["cpu", "mem"]);
+        assert_eq!(loaded.history_tiers, HistoryTier::defaults());
+        assert_eq!(loaded.ranges["CPU"].min, f32::NEG_INFINITY);
+        assert_eq!(loaded.severity["TEMP"].critical, 85.0);
+        assert_eq!(loaded.units["FAN_RPM"].suffix, "RPM");
+        assert_eq!(loaded.categories["Energia"], ["BATTERY", "POWER"]);
+        assert_eq!(loaded.alerts[0].threshold, 90.0);
+        let statsd = loaded.statsd.unwrap();
+        assert!(!statsd.tags);
+        assert_eq!(statsd.prefix, "telemetry");
+        assert_eq!(loaded.commands, config.commands);
+    }
+}
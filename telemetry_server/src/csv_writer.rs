@@ -0,0 +1,172 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Result as IoResult, Write},
+    net::SocketAddr,
+    path::Path,
+};
+
+use telemetry_common::is_temperature_unavailable;
+
+/// Colunas fixas que antecedem as métricas
+const TIMESTAMP_COLUMN: &str = "timestamp";
+const CLIENT_COLUMN: &str = "client";
+/// Coluna final com métricas que não existiam quando o cabeçalho foi escrito
+const EXTRA_COLUMN: &str = "extra";
+
+/// Grava amostras de telemetria em CSV, uma linha por amostra.
+///
+/// O cabeçalho é definido pelas chaves da primeira amostra; métricas que
+/// aparecerem depois vão para a coluna `extra` como um objeto JSON.
+pub struct CsvWriter {
+    writer: BufWriter<File>,
+    metric_columns: Option<Vec<String>>,
+}
+
+impl CsvWriter {
+    /// Abre o arquivo em modo append. Se ele já tiver conteúdo, o cabeçalho
+    /// existente é reaproveitado para manter as colunas consistentes.
+    pub fn open(path: &Path) -> IoResult<Self> {
+        let metric_columns = match File::open(path) {
+            Ok(file) => {
+                let mut header = String::new();
+                BufReader::new(file).read_line(&mut header)?;
+                parse_header(&header)
+            }
+            Err(_) => None,
+        };
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            metric_columns,
+        })
+    }
+
+    pub fn write_sample(
+        &mut self,
+        timestamp: u64,
+        client: SocketAddr,
+        metrics: &HashMap<String, f32>,
+    ) -> IoResult<()> {
+        let columns = match &self.metric_columns {
+            Some(columns) => columns,
+            None => {
+                let mut columns: Vec<String> = metrics.keys().cloned().collect();
+                columns.sort();
+                self.write_header(&columns)?;
+                self.metric_columns.insert(columns)
+            }
+        };
+
+        let mut row = Vec::with_capacity(columns.len() + 3);
+        row.push(timestamp.to_string());
+        row.push(escape_field(&client.to_string()));
+        for column in columns {
+            let value = metrics.get(column).filter(|value| !is_temperature_unavailable(column, f64::from(**value)));
+            row.push(value.map(f32::to_string).unwrap_or_default());
+        }
+
+        let extra: HashMap<&String, &f32> = metrics.iter()
+            .filter(|(name, value)| !columns.contains(name) && !is_temperature_unavailable(name, f64::from(**value)))
+            .collect();
+        if extra.is_empty() {
+            row.push(String::new());
+        } else {
+            let json = serde_json::to_string(&extra).unwrap_or_default();
+            row.push(escape_field(&json));
+        }
+
+        writeln!(self.writer, "{}", row.join(","))?;
+        // Flush por amostra: não perde dados se o servidor cair
+        self.writer.flush()
+    }
+
+    fn write_header(&mut self, metric_columns: &[String]) -> IoResult<()> {
+        let mut header = vec![TIMESTAMP_COLUMN.to_string(), CLIENT_COLUMN.to_string()];
+        header.extend(metric_columns.iter().map(|column| escape_field(column)));
+        header.push(EXTRA_COLUMN.to_string());
+        writeln!(self.writer, "{}", header.join(","))
+    }
+}
+
+/// Extrai as colunas de métricas de um cabeçalho já gravado, com as mesmas
+/// regras de aspas do `escape_field`
+fn parse_header(header: &str) -> Option<Vec<String>> {
+    let columns = split_fields(header.trim_end_matches(['\r', '\n']));
+    match columns.as_slice() {
+        [timestamp, client, metrics @ .., extra]
+            if timestamp == TIMESTAMP_COLUMN && client == CLIENT_COLUMN && extra == EXTRA_COLUMN =>
+        {
+            Some(metrics.to_vec())
+        }
+        _ => None,
+    }
+}
+
+/// Campos de uma linha CSV (RFC 4180): vírgulas entre aspas não separam e `""` vira `"`
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Aplica as regras de escape do CSV (RFC 4180) quando necessário
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    #[test]
+    fn writes_two_samples_and_reuses_quoted_header() {
+        let path = env::temp_dir().join(format!("telemetry-csv-{}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let client: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let labeled = "DISK{mount=\"/a,b\"}".to_string();
+
+        let mut writer = CsvWriter::open(&path).unwrap();
+        writer.write_sample(1, client, &HashMap::from([("CPU".to_string(), 10.5), (labeled.clone(), 70.0)])).unwrap();
+        writer.write_sample(2, client, &HashMap::from([("CPU".to_string(), 20.0), ("TEMP".to_string(), 50.0)])).unwrap();
+        drop(writer);
+
+        // Reaberto, o arquivo mantém as colunas do cabeçalho original
+        let mut writer = CsvWriter::open(&path).unwrap();
+        assert_eq!(writer.metric_columns.as_deref(), Some(&["CPU".to_string(), labeled][..]));
+        writer.write_sample(3, client, &HashMap::from([("CPU".to_string(), 30.0)])).unwrap();
+        drop(writer);
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines, [
+            "timestamp,client,CPU,\"DISK{mount=\"\"/a,b\"\"}\",extra",
+            "1,127.0.0.1:5000,10.5,70,",
+            "2,127.0.0.1:5000,20,,\"{\"\"TEMP\"\":50.0}\"",
+            "3,127.0.0.1:5000,30,,",
+        ]);
+    }
+}
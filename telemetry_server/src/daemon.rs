@@ -0,0 +1,104 @@
+use std::{
+    ffi::CString,
+    fs,
+    io::{self, Result as IoResult},
+    os::raw::{c_int, c_void},
+    path::Path,
+    process,
+};
+
+use crate::{log, LogLevel};
+
+/// Ponta de escrita do pipe pelo qual o daemon avisa o processo que o iniciou
+/// se subiu. O código enviado vira o código de saída do comando no terminal; se
+/// o daemon morrer antes, o pipe fecha vazio e o comando sai com falha
+pub struct Readiness {
+    fd: c_int,
+}
+
+impl Readiness {
+    /// `0` quando o servidor subiu; o código de saída do erro caso contrário
+    pub fn report(self, code: u8) {
+        unsafe {
+            libc::write(self.fd, (&code as *const u8).cast::<c_void>(), 1);
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Desacopla o servidor do terminal: fork duplo com `setsid`, stdin/stdout/stderr
+/// apontando para `/dev/null` e o PID gravado em `pid_file`. Precisa rodar antes
+/// de qualquer thread ser criada, já que o fork só copia a thread atual. O
+/// processo original só sai quando o daemon chama `Readiness::report`, com o
+/// código informado, para que falhas na inicialização cheguem a quem o chamou
+pub fn daemonize(pid_file: &Path) -> IoResult<Readiness> {
+    let mut fds: [c_int; 2] = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let [read_fd, write_fd] = fds;
+
+    // O primeiro filho sai do grupo do terminal; o neto nunca readquire um terminal
+    match unsafe { libc::fork() } {
+        pid if pid < 0 => return Err(io::Error::last_os_error()),
+        0 => unsafe { libc::close(read_fd); },
+        _ => unsafe {
+            libc::close(write_fd);
+            libc::_exit(wait_ready(read_fd));
+        },
+    }
+    if unsafe { libc::setsid() } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    fork_and_exit_parent()?;
+
+    redirect_standard_streams()?;
+    fs::write(pid_file, format!("{}\n", process::id()))?;
+    log(LogLevel::Info, &format!("Rodando em background (PID {}, arquivo {})", process::id(), pid_file.display()));
+    Ok(Readiness { fd: write_fd })
+}
+
+/// Apaga o PID file no encerramento do daemon
+pub fn remove_pid_file(pid_file: &Path) {
+    if let Err(e) = fs::remove_file(pid_file) {
+        log(LogLevel::Warning, &format!("Não foi possível remover {}: {}", pid_file.display(), e));
+    }
+}
+
+/// Bloqueia o processo original até o código do daemon; pipe fechado sem código é falha
+fn wait_ready(read_fd: c_int) -> c_int {
+    let mut code = 0u8;
+    loop {
+        match unsafe { libc::read(read_fd, (&mut code as *mut u8).cast::<c_void>(), 1) } {
+            1 => return c_int::from(code),
+            n if n < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => {}
+            _ => return 1,
+        }
+    }
+}
+
+fn fork_and_exit_parent() -> IoResult<()> {
+    match unsafe { libc::fork() } {
+        pid if pid < 0 => Err(io::Error::last_os_error()),
+        0 => Ok(()),
+        // `_exit` não roda destrutores nem descarrega buffers que o filho ainda usa
+        _ => unsafe { libc::_exit(0) },
+    }
+}
+
+fn redirect_standard_streams() -> IoResult<()> {
+    let dev_null = CString::new("/dev/null").map_err(io::Error::other)?;
+    let fd = unsafe { libc::open(dev_null.as_ptr(), libc::O_RDWR) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    for target in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        if unsafe { libc::dup2(fd, target as c_int) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    if fd > libc::STDERR_FILENO {
+        unsafe { libc::close(fd) };
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    sync::Arc,
+    thread,
+};
+
+use telemetry_common::discovery::{self, DiscoveryReply, DISCOVERY_PORT};
+
+use crate::{config::TransportKind, log, LogLevel, ServerState, ACCEPT_POLL_INTERVAL};
+
+/// Responde aos broadcasts de descoberta com o endereço do listener, para clientes
+/// sem endereço configurado acharem o servidor na LAN. A porta é fixa, então um
+/// segundo servidor na mesma máquina só registra o aviso e segue sem descoberta.
+/// Um listener em loopback não é alcançável pela LAN e não é anunciado
+pub fn spawn_responder(listening: SocketAddr, state: Arc<ServerState>) {
+    if listening.ip().is_loopback() {
+        log(LogLevel::Info, &format!("Descoberta desativada: {} só aceita conexões locais", listening));
+        return;
+    }
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT)) {
+        Ok(socket) => socket,
+        Err(e) => {
+            log(LogLevel::Warning, &format!("Descoberta desativada (porta UDP {}): {}", DISCOVERY_PORT, e));
+            return;
+        }
+    };
+    // O timeout devolve o controle ao loop para perceber o encerramento
+    if let Err(e) = socket.set_read_timeout(Some(ACCEPT_POLL_INTERVAL)) {
+        log(LogLevel::Warning, &format!("Descoberta desativada: {}", e));
+        return;
+    }
+
+    let reply = DiscoveryReply {
+        ip: Some(listening.ip()).filter(|ip| !ip.is_unspecified()),
+        port: listening.port(),
+        transport: match state.config.transport {
+            TransportKind::Tcp => "tcp",
+            TransportKind::Udp => "udp",
+        }
+        .to_string(),
+        tls: state.tls.is_some(),
+    }
+    .to_json();
+
+    let spawned = thread::Builder::new()
+        .name("discovery".to_string())
+        .spawn(move || {
+            let mut buffer = [0u8; 512];
+            while !state.is_shutting_down() {
+                let Ok((len, from)) = socket.recv_from(&mut buffer) else {
+                    continue;
+                };
+                if !discovery::is_request(&buffer[..len]) {
+                    continue;
+                }
+                match socket.send_to(reply.as_bytes(), from) {
+                    Ok(_) => log(LogLevel::Info, &format!("Descoberta respondida para {}", from)),
+                    Err(e) => log(LogLevel::Warning, &format!("Erro ao responder descoberta de {}: {}", from, e)),
+                }
+            }
+        });
+
+    match spawned {
+        Ok(_) => log(LogLevel::Info, &format!("Respondendo a descoberta na porta UDP {}", DISCOVERY_PORT)),
+        Err(e) => log(LogLevel::Warning, &format!("Descoberta desativada: {}", e)),
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use telemetry_common::labels;
+
+/// Ordem fixa das métricas conhecidas na tela; as demais vêm depois, em ordem alfabética
+const METRIC_ORDER: &[&str] = &[
+    "CPU",
+    "CPU_FREQ",
+    "MEM",
+    "MEMORY",
+    "MEM_PERCENT",
+    "SWAP",
+    "DISK",
+    "STORAGE",
+    "DISK_READ",
+    "DISK_WRITE",
+    "NET",
+    "NETWORK",
+    "TEMP",
+    "TEMPERATURE",
+    "GPU_UTIL",
+    "GPU_MEM",
+    "BATTERY",
+    "BATTERY_CHARGING",
+    "PROC_COUNT",
+    "TCP_CONNS",
+    "UPTIME",
+    "RTT_MS",
+];
+
+/// Seção das métricas que nenhuma entrada classifica, sempre a última do painel
+pub const OTHER_CATEGORY: &str = "Outros";
+
+/// Seções padrão do painel e os nomes que caem em cada uma. Um nome vale para
+/// ele mesmo e para os que começam com ele seguido de `_` (`CPU` casa `CPU_FREQ`)
+const DEFAULT_CATEGORIES: &[(&str, &[&str])] = &[
+    ("Processamento", &["CPU", "GPU", "PROC_COUNT", "LOAD"]),
+    ("Memória", &["MEM", "MEMORY", "SWAP"]),
+    ("Armazenamento", &["DISK", "STORAGE"]),
+    ("Rede", &["NET", "NETWORK", "TCP_CONNS", "RTT_MS"]),
+    ("Térmico", &["TEMP", "TEMPERATURE", "FAN"]),
+];
+
+/// Categoria de cada métrica no painel: os padrões embutidos, sobrescritos ou
+/// estendidos pela seção `[categories]` da configuração
+#[derive(Debug)]
+pub struct CategoryTable {
+    /// Nome em maiúsculas → seção
+    entries: HashMap<String, String>,
+}
+
+impl CategoryTable {
+    pub fn new(overrides: &HashMap<String, Vec<String>>) -> Self {
+        let mut entries: HashMap<String, String> = DEFAULT_CATEGORIES
+            .iter()
+            .flat_map(|(category, names)| names.iter().map(move |name| (name.to_string(), category.to_string())))
+            .collect();
+        // Ordem fixa: um nome repetido em duas seções da config cai sempre na mesma
+        let mut overrides: Vec<_> = overrides.iter().collect();
+        overrides.sort();
+        for (category, names) in overrides {
+            for name in names {
+                entries.insert(name.trim().to_uppercase(), category.clone());
+            }
+        }
+        Self { entries }
+    }
+
+    /// Seção de uma métrica pela entrada mais específica que a casa: `CPU_FREQ`
+    /// antes de `CPU`. Séries com labels seguem a métrica pai
+    pub fn category(&self, name: &str) -> &str {
+        let upper = labels::base_name(name).to_uppercase();
+        let mut candidate = upper.as_str();
+        loop {
+            if let Some(category) = self.entries.get(candidate) {
+                return category;
+            }
+            match candidate.rfind('_') {
+                Some(end) => candidate = &candidate[..end],
+                None => return OTHER_CATEGORY,
+            }
+        }
+    }
+
+    /// Seções com alguma das métricas, na ordem em que aparecem; `Outros` por último
+    pub fn sections<'a, 'b>(&'a self, names: impl IntoIterator<Item = &'b str>) -> Vec<&'a str> {
+        let mut sections: Vec<&str> = Vec::new();
+        for name in names {
+            let category = self.category(name);
+            if !sections.contains(&category) {
+                sections.push(category);
+            }
+        }
+        sections.sort_by_key(|category| *category == OTHER_CATEGORY);
+        sections
+    }
+}
+
+/// Métricas exibidas na tela (`--show cpu,mem`). Só afeta o painel e o modo
+/// headless: histórico, alertas e exportações continuam recebendo tudo
+#[derive(Debug, Default)]
+pub struct MetricFilter {
+    /// Nomes em maiúsculas, na ordem pedida; vazio exibe todas
+    show: Vec<String>,
+}
+
+impl MetricFilter {
+    pub fn new(show: &[String]) -> Self {
+        Self { show: show.iter().map(|name| name.trim().to_uppercase()).filter(|name| !name.is_empty()).collect() }
+    }
+
+    /// Posição da entrada que seleciona a métrica. `CPU` também casa `CPU_0`, `CPU_1`...
+    /// e `MEM` casa `MEM_PERCENT`: o nome pedido vale como prefixo antes de `_`
+    fn position(&self, upper: &str) -> Option<usize> {
+        if self.show.is_empty() {
+            return Some(0);
+        }
+        self.show.iter().position(|entry| {
+            upper == entry || upper.strip_prefix(entry.as_str()).is_some_and(|rest| rest.starts_with('_'))
+        })
+    }
+
+    /// A métrica entra na tela; séries com labels seguem a métrica pai
+    pub fn shows(&self, name: &str) -> bool {
+        self.position(&labels::base_name(name).to_uppercase()).is_some()
+    }
+
+    /// Métricas a exibir, em ordem determinística: a do `--show` e, dentro de
+    /// cada entrada, a de `METRIC_ORDER` seguida das customizadas por nome. As
+    /// séries com labels vêm logo depois da métrica pai, ordenadas pela chave
+    pub fn select<'a>(&self, metrics: &'a HashMap<String, f32>) -> Vec<(&'a str, f32)> {
+        let mut selected: Vec<(usize, usize, String, &'a str, &'a str, f32)> = metrics
+            .iter()
+            .filter_map(|(name, &value)| {
+                let base = labels::base_name(name);
+                let upper = base.to_uppercase();
+                let position = self.position(&upper)?;
+                let rank = METRIC_ORDER.iter().position(|known| *known == upper).unwrap_or(METRIC_ORDER.len());
+                Some((position, rank, upper, &name[base.len()..], name.as_str(), value))
+            })
+            .collect();
+        selected.sort_by(|a, b| (a.0, a.1, &a.2, a.3, a.4).cmp(&(b.0, b.1, &b.2, b.3, b.4)));
+        selected.into_iter().map(|(_, _, _, _, name, value)| (name, value)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(names: &[&str]) -> HashMap<String, f32> {
+        names.iter().map(|name| (name.to_string(), 1.0)).collect()
+    }
+
+    fn names(filter: &MetricFilter, metrics: &HashMap<String, f32>) -> Vec<String> {
+        filter.select(metrics).into_iter().map(|(name, _)| name.to_string()).collect()
+    }
+
+    #[test]
+    fn orders_known_metrics_before_custom_ones() {
+        let metrics = metrics(&["FAN_RPM", "UPTIME", "DISK", "CPU", "MEM", "AMBIENT"]);
+        assert_eq!(names(&MetricFilter::default(), &metrics), ["CPU", "MEM", "DISK", "UPTIME", "AMBIENT", "FAN_RPM"]);
+    }
+
+    #[test]
+    fn order_does_not_depend_on_insertion() {
+        let names_in = ["TEMP", "NET", "DISK", "MEM", "CPU", "ZETA", "ALPHA"];
+        let forward = metrics(&names_in);
+        let backward: HashMap<String, f32> = names_in.iter().rev().map(|name| (name.to_string(), 1.0)).collect();
+
+        let filter = MetricFilter::default();
+        assert_eq!(names(&filter, &forward), ["CPU", "MEM", "DISK", "NET", "TEMP", "ALPHA", "ZETA"]);
+        assert_eq!(names(&filter, &forward), names(&filter, &backward));
+    }
+
+    #[test]
+    fn shows_only_selected_metrics_in_requested_order() {
+        let metrics = metrics(&["CPU", "CPU_1", "CPU_0", "MEM", "MEM_PERCENT", "DISK", "MEMORY_X"]);
+        let filter = MetricFilter::new(&["mem".to_string(), " cpu ".to_string()]);
+        assert_eq!(names(&filter, &metrics), ["MEM", "MEM_PERCENT", "CPU", "CPU_0", "CPU_1"]);
+    }
+
+    #[test]
+    fn classifies_metrics_by_most_specific_entry() {
+        let overrides = HashMap::from([
+            ("Energia".to_string(), vec!["battery".to_string(), "CPU_FREQ".to_string()]),
+        ]);
+        let table = CategoryTable::new(&overrides);
+        assert_eq!(table.category("CPU"), "Processamento");
+        assert_eq!(table.category("CPU_3"), "Processamento");
+        assert_eq!(table.category("CPU_FREQ"), "Energia");
+        assert_eq!(table.category("BATTERY_CHARGING"), "Energia");
+        assert_eq!(table.category("MEM_PERCENT"), "Memória");
+        assert_eq!(table.category(r#"DISK{mount="/home"}"#), "Armazenamento");
+        assert_eq!(table.category("QUEUE_DEPTH"), OTHER_CATEGORY);
+
+        let sections = table.sections(["UPTIME", "MEM", "CPU", "BATTERY", "TEMP"]);
+        assert_eq!(sections, ["Memória", "Processamento", "Energia", "Térmico", OTHER_CATEGORY]);
+    }
+
+    #[test]
+    fn labeled_series_follow_their_parent() {
+        let metrics = metrics(&[r#"DISK{mount="/home"}"#, "NET", r#"DISK{mount="/"}"#, "DISK", "CPU", r#"FAN{id="2"}"#]);
+        assert_eq!(
+            names(&MetricFilter::default(), &metrics),
+            ["CPU", "DISK", r#"DISK{mount="/"}"#, r#"DISK{mount="/home"}"#, "NET", r#"FAN{id="2"}"#]
+        );
+        let filter = MetricFilter::new(&["disk".to_string()]);
+        assert_eq!(names(&filter, &metrics), ["DISK", r#"DISK{mount="/"}"#, r#"DISK{mount="/home"}"#]);
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    io::Result as IoResult,
+    net::TcpListener,
+    sync::{atomic::Ordering, Arc, Mutex},
+};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::{
+    http::{self, Response},
+    log, LogLevel, ServerState,
+};
+
+/// Sobe o endpoint `GET /health` numa thread própria, para healthchecks de
+/// orquestradores e balanceadores. O bind acontece antes do spawn para que
+/// erros de porta cheguem ao chamador.
+pub fn spawn_health(address: &str, state: Arc<ServerState>) -> IoResult<()> {
+    let listener = TcpListener::bind(address)?;
+    log(LogLevel::Info, &format!("Healthcheck em http://{}/health", address));
+
+    let probe = Mutex::new(MemoryProbe::new());
+    http::serve("health", listener, move |request, stream| {
+        Response::route(&request, "/health", "application/json", || {
+            let mut probe = probe.lock().unwrap_or_else(|e| e.into_inner());
+            health_json(&state, &mut probe).to_string()
+        }).write_to(stream)
+    })
+}
+
+/// `{"status":"ok","uptime_secs":...,"clients":...,"samples_processed":...,"memory_bytes":...}`
+fn health_json(state: &ServerState, probe: &mut MemoryProbe) -> serde_json::Value {
+    let clients = state.clients.lock().unwrap_or_else(|e| e.into_inner()).len();
+    serde_json::json!({
+        "status": "ok",
+        "uptime_secs": state.started_at.elapsed().as_secs(),
+        "clients": clients,
+        "samples_processed": state.samples_processed.load(Ordering::Relaxed),
+        "memory_bytes": probe.usage().map(|usage| usage.memory_bytes),
+    })
+}
+
+/// Uso de recursos do próprio processo
+pub struct ProcessUsage {
+    /// Memória residente
+    pub memory_bytes: u64,
+    /// Threads do processo; `None` onde o sysinfo não as lista (fora do Linux)
+    pub threads: Option<usize>,
+}
+
+/// Lê o uso do próprio processo via sysinfo, só quando o healthcheck ou o
+/// diagnóstico `S` pedem
+pub struct MemoryProbe {
+    system: System,
+    pid: Option<Pid>,
+}
+
+impl MemoryProbe {
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+            pid: sysinfo::get_current_pid().ok(),
+        }
+    }
+
+    /// `None` em plataformas onde o sysinfo não enxerga o processo
+    pub fn usage(&mut self) -> Option<ProcessUsage> {
+        let pid = self.pid?;
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::nothing().with_memory().with_tasks(),
+        );
+        self.system.process(pid).map(|process| ProcessUsage {
+            memory_bytes: process.memory(),
+            threads: process.tasks().map(|tasks| tasks.len()),
+        })
+    }
+}
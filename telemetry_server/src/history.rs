@@ -0,0 +1,327 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use telemetry_common::is_temperature_unavailable;
+
+/// Agregados de uma métrica dentro da janela deslizante
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowStats {
+    pub min: f32,
+    pub max: f32,
+    pub avg: f32,
+    pub count: usize,
+    /// Intervalo entre a amostra mais antiga e a mais recente da janela
+    pub span_ms: u64,
+}
+
+/// Camada de retenção (`[[history_tiers]]`): o que sai da camada anterior é
+/// agregado em buckets de `bucket_secs`, dos quais os `buckets` mais recentes ficam
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryTier {
+    pub bucket_secs: u64,
+    pub buckets: usize,
+}
+
+impl HistoryTier {
+    /// Padrão: média por minuto na última hora e por 10 minutos nas últimas 24h
+    pub fn defaults() -> Vec<HistoryTier> {
+        vec![
+            HistoryTier { bucket_secs: 60, buckets: 60 },
+            HistoryTier { bucket_secs: 600, buckets: 144 },
+        ]
+    }
+}
+
+/// Mín/máx/soma das amostras de um intervalo que começa em `start`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bucket {
+    start: u64,
+    min: f32,
+    max: f32,
+    sum: f64,
+    count: usize,
+}
+
+impl Bucket {
+    /// Valores não finitos não entram nos agregados
+    fn single(timestamp: u64, value: f32) -> Option<Self> {
+        value.is_finite().then_some(Self { start: timestamp, min: value, max: value, sum: value as f64, count: 1 })
+    }
+
+    fn merge(&mut self, other: &Bucket) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+        self.count += other.count;
+    }
+}
+
+#[derive(Debug)]
+struct Tier {
+    bucket_ms: u64,
+    capacity: usize,
+    buckets: VecDeque<Bucket>,
+}
+
+impl Tier {
+    /// Agrega no bucket do intervalo e devolve o que passou da capacidade, para a
+    /// camada seguinte. Amostras fora de ordem caem no bucket mais recente
+    fn push(&mut self, bucket: Bucket) -> Option<Bucket> {
+        let start = bucket.start - bucket.start % self.bucket_ms;
+        match self.buckets.back_mut() {
+            Some(last) if start <= last.start => {
+                last.merge(&bucket);
+                return None;
+            }
+            _ => self.buckets.push_back(Bucket { start, ..bucket }),
+        }
+        if self.buckets.len() > self.capacity {
+            return self.buckets.pop_front();
+        }
+        None
+    }
+}
+
+/// Amostras recentes em resolução total e as antigas nas camadas de buckets
+#[derive(Debug)]
+struct Series {
+    recent: VecDeque<(u64, f32)>,
+    tiers: Vec<Tier>,
+}
+
+impl Series {
+    /// Desce a amostra que saiu da janela fina pelas camadas; o que passa da
+    /// última é descartado
+    fn retire(&mut self, timestamp: u64, value: f32) {
+        let mut carry = Bucket::single(timestamp, value);
+        for tier in &mut self.tiers {
+            match carry {
+                Some(bucket) => carry = tier.push(bucket),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Histórico de cada métrica de um cliente: as últimas N amostras em ring buffer
+/// e, com `history_tiers`, as anteriores reduzidas a buckets de resolução
+/// decrescente, para a memória ficar limitada sem perder a tendência
+#[derive(Debug)]
+pub struct MetricHistory {
+    capacity: usize,
+    tiers: Vec<HistoryTier>,
+    series: HashMap<String, Series>,
+    /// Amostras registradas desde a primeira conexão do cliente, sem limite de janela
+    received: u64,
+}
+
+impl MetricHistory {
+    pub fn new(capacity: usize, tiers: &[HistoryTier]) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            tiers: tiers.to_vec(),
+            series: HashMap::new(),
+            received: 0,
+        }
+    }
+
+    /// Registra uma amostra; quando a janela está cheia, a mais antiga desce para as camadas.
+    /// Amostras atrasadas (reenviadas pelo cliente após uma queda) entram na posição
+    /// do timestamp; as mais velhas que toda a janela cheia vão direto para as camadas
+    pub fn record(&mut self, timestamp: u64, metrics: &HashMap<String, f32>) {
+        self.received += 1;
+        for (name, value) in metrics {
+            if is_temperature_unavailable(name, f64::from(*value)) {
+                continue;
+            }
+            let series = self.series.entry(name.clone()).or_insert_with(|| Series {
+                recent: VecDeque::with_capacity(self.capacity),
+                tiers: self.tiers.iter()
+                    .map(|tier| Tier {
+                        bucket_ms: tier.bucket_secs.max(1) * 1000,
+                        capacity: tier.buckets.max(1),
+                        buckets: VecDeque::new(),
+                    })
+                    .collect(),
+            });
+            let mut position = series.recent.iter()
+                .rposition(|(recorded, _)| *recorded <= timestamp)
+                .map_or(0, |index| index + 1);
+            if series.recent.len() == self.capacity {
+                if position == 0 {
+                    series.retire(timestamp, *value);
+                    continue;
+                }
+                if let Some((old_ts, old_value)) = series.recent.pop_front() {
+                    series.retire(old_ts, old_value);
+                }
+                position -= 1;
+            }
+            series.recent.insert(position, (timestamp, *value));
+        }
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Agregados da janela; valores não finitos (NaN/Inf) ficam de fora
+    pub fn stats(&self, name: &str) -> Option<WindowStats> {
+        let window = &self.series.get(name)?.recent;
+        let (first_ts, _) = *window.front()?;
+        let (last_ts, _) = *window.back()?;
+
+        let finite = || window.iter().map(|(_, value)| *value).filter(|value| value.is_finite());
+        let count = finite().count();
+        if count == 0 {
+            return None;
+        }
+        let (min, max, sum) = finite().fold(
+            (f32::INFINITY, f32::NEG_INFINITY, 0.0f64),
+            |(min, max, sum), value| (min.min(value), max.max(value), sum + value as f64),
+        );
+
+        Some(WindowStats {
+            min,
+            max,
+            avg: (sum / count as f64) as f32,
+            count,
+            span_ms: last_ts.saturating_sub(first_ts),
+        })
+    }
+
+    /// Agregados de todo o período retido, janela e camadas juntas. `None`
+    /// enquanto nada desceu para as camadas, quando equivaleria a `stats`
+    pub fn retained_stats(&self, name: &str) -> Option<WindowStats> {
+        let series = self.series.get(name)?;
+        let oldest = series.tiers.iter().rev().find_map(|tier| tier.buckets.front())?.start;
+
+        let mut total = series.tiers.iter()
+            .flat_map(|tier| tier.buckets.iter())
+            .fold(None::<Bucket>, |total, bucket| match total {
+                Some(mut total) => {
+                    total.merge(bucket);
+                    Some(total)
+                }
+                None => Some(*bucket),
+            })?;
+        for (timestamp, value) in &series.recent {
+            if let Some(bucket) = Bucket::single(*timestamp, *value) {
+                total.merge(&bucket);
+            }
+        }
+        let newest = series.recent.back().map_or(oldest, |(timestamp, _)| *timestamp);
+
+        Some(WindowStats {
+            min: total.min,
+            max: total.max,
+            avg: (total.sum / total.count as f64) as f32,
+            count: total.count,
+            span_ms: newest.saturating_sub(oldest),
+        })
+    }
+
+    /// Pontos guardados somando todas as métricas, entre amostras e buckets; base
+    /// do diagnóstico `S` para acompanhar a memória dos históricos
+    pub fn retained_points(&self) -> usize {
+        self.series.values()
+            .map(|series| series.recent.len() + series.tiers.iter().map(|tier| tier.buckets.len()).sum::<usize>())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_keeps_last_samples_with_min_avg_max() {
+        let mut history = MetricHistory::new(3, &[]);
+        for (timestamp, value) in [(1_000, 90.0), (2_000, 10.0), (3_000, 35.0), (4_000, 60.0)] {
+            history.record(timestamp, &HashMap::from([("CPU".to_string(), value)]));
+        }
+
+        // 90.0 saiu da janela de 3 amostras
+        let stats = history.stats("CPU").unwrap();
+        assert_eq!((stats.min, stats.avg, stats.max, stats.count), (10.0, 35.0, 60.0, 3));
+        assert_eq!(stats.span_ms, 2_000);
+        assert!(history.stats("MEM").is_none());
+    }
+
+    #[test]
+    fn stats_ignore_non_finite_values() {
+        let mut history = MetricHistory::new(10, &[]);
+        for (timestamp, value) in [(0, 10.0), (1, f32::NAN), (2, 30.0), (3, f32::INFINITY), (4, f32::NEG_INFINITY)] {
+            history.record(timestamp, &HashMap::from([("CPU".to_string(), value)]));
+        }
+
+        let stats = history.stats("CPU").unwrap();
+        assert_eq!((stats.min, stats.max, stats.avg, stats.count), (10.0, 30.0, 20.0, 2));
+        assert_eq!(stats.span_ms, 4);
+    }
+
+    /// Sem sensor no cliente, a sentinela de temperatura não entra no histórico
+    #[test]
+    fn temperature_sentinel_is_not_recorded() {
+        let mut history = MetricHistory::new(10, &[]);
+        let sentinel = telemetry_common::TEMPERATURE_UNAVAILABLE;
+        history.record(0, &HashMap::from([("TEMP".to_string(), 40.0), ("TEMP{sensor=\"gpu\"}".to_string(), sentinel)]));
+        history.record(1, &HashMap::from([("TEMP".to_string(), sentinel)]));
+
+        let stats = history.stats("TEMP").unwrap();
+        assert_eq!((stats.min, stats.max, stats.count), (40.0, 40.0, 1));
+        assert!(history.stats("TEMP{sensor=\"gpu\"}").is_none());
+    }
+
+    #[test]
+    fn late_samples_take_their_timestamp_position() {
+        let mut history = MetricHistory::new(3, &[HistoryTier { bucket_secs: 60, buckets: 10 }]);
+        let cpu = |value: f32| HashMap::from([("CPU".to_string(), value)]);
+        history.record(1_000, &cpu(1.0));
+        history.record(4_000, &cpu(4.0));
+        // Reenviadas após a reconexão, depois de uma amostra nova
+        history.record(2_000, &cpu(2.0));
+        history.record(3_000, &cpu(3.0));
+
+        let window = &history.series["CPU"].recent;
+        assert_eq!(window.iter().map(|(timestamp, _)| *timestamp).collect::<Vec<_>>(), [2_000, 3_000, 4_000]);
+        assert_eq!(history.stats("CPU").unwrap().span_ms, 2_000);
+
+        // Mais velha que toda a janela cheia: vai direto para as camadas
+        history.record(500, &cpu(0.5));
+        assert_eq!(history.stats("CPU").unwrap().min, 2.0);
+        assert_eq!(history.retained_stats("CPU").unwrap().min, 0.5);
+    }
+
+    #[test]
+    fn old_samples_are_downsampled_into_tiers() {
+        let tiers = [HistoryTier { bucket_secs: 60, buckets: 2 }, HistoryTier { bucket_secs: 300, buckets: 1 }];
+        let mut history = MetricHistory::new(3, &tiers);
+        // Uma amostra a cada 10s por 10 minutos: 3 na janela, o resto em buckets
+        for step in 0..60u64 {
+            history.record(step * 10_000, &HashMap::from([("CPU".to_string(), step as f32)]));
+        }
+
+        assert_eq!(history.stats("CPU").unwrap().count, 3);
+        assert_eq!(history.retained_points(), 3 + 2 + 1);
+
+        let retained = history.retained_stats("CPU").unwrap();
+        assert_eq!(retained.max, 59.0);
+        assert!(retained.min > 0.0, "o bucket mais antigo passou da última camada");
+        assert_eq!(retained.span_ms, 590_000 - 300_000);
+    }
+
+    #[test]
+    fn retained_stats_need_downsampled_data() {
+        let mut history = MetricHistory::new(5, &HistoryTier::defaults());
+        history.record(0, &HashMap::from([("CPU".to_string(), 1.0)]));
+        assert!(history.retained_stats("CPU").is_none());
+
+        let mut fine_only = MetricHistory::new(1, &[]);
+        for step in 0..10 {
+            fine_only.record(step, &HashMap::from([("CPU".to_string(), 1.0)]));
+        }
+        assert!(fine_only.retained_stats("CPU").is_none());
+        assert_eq!(fine_only.retained_points(), 1);
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Result as IoResult, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{log, LogLevel};
+
+/// Prazo para a requisição inteira (linha e headers, inclusive o handshake do
+/// WebSocket): um cliente que conecta e não envia nada, ou manda um byte por vez
+/// (slowloris), só prende a própria thread, e por pouco tempo
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Conexões atendidas ao mesmo tempo por endpoint; além disso, `503`
+const MAX_CONNECTIONS: usize = 32;
+
+/// Maior requisição aceita (linha e headers): os endpoints só usam `GET`
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+
+/// Linha de requisição e headers de um `GET`; o corpo nunca é lido
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Nomes em minúsculas
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Resposta completa, sempre com `Connection: close`
+pub struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: String) -> Self {
+        Self { status: "200 OK", content_type, body }
+    }
+
+    pub fn not_found() -> Self {
+        Self { status: "404 Not Found", content_type: "text/plain", body: "not found\n".to_string() }
+    }
+
+    pub fn method_not_allowed() -> Self {
+        Self { status: "405 Method Not Allowed", content_type: "text/plain", body: "method not allowed\n".to_string() }
+    }
+
+    fn unavailable() -> Self {
+        Self { status: "503 Service Unavailable", content_type: "text/plain", body: "busy\n".to_string() }
+    }
+
+    /// `GET` no caminho esperado ou as respostas padrão de erro
+    pub fn route(request: &Request, path: &str, content_type: &'static str, body: impl FnOnce() -> String) -> Self {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", requested) if requested == path => Self::ok(content_type, body()),
+            ("GET", _) => Self::not_found(),
+            _ => Self::method_not_allowed(),
+        }
+    }
+
+    pub fn write_to(&self, mut stream: TcpStream) -> IoResult<()> {
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body
+        );
+        stream.write_all(response.as_bytes())?;
+        stream.flush()
+    }
+}
+
+/// Atende o listener numa thread `name`, com uma thread por conexão: um cliente
+/// lento não atrasa os demais. `handler` recebe a requisição já lida e a conexão,
+/// para responder (ou, no dashboard, assumir o socket)
+pub fn serve<F>(name: &str, listener: TcpListener, handler: F) -> IoResult<()>
+where
+    F: Fn(Request, TcpStream) -> IoResult<()> + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    let active = Arc::new(AtomicUsize::new(0));
+    let label = name.to_string();
+
+    thread::Builder::new().name(name.to_string()).spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log(LogLevel::Error, &format!("Erro ao aceitar conexão HTTP ({}): {}", label, e));
+                    continue;
+                }
+            };
+            if active.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                active.fetch_sub(1, Ordering::Relaxed);
+                let _ = Response::unavailable().write_to(stream);
+                continue;
+            }
+
+            let handler = Arc::clone(&handler);
+            let slot = Arc::clone(&active);
+            let label = label.clone();
+            let spawned = thread::Builder::new().name(format!("{}-conexão", label)).spawn(move || {
+                let result = read_request(&stream, REQUEST_TIMEOUT).and_then(|request| handler(request, stream));
+                if let Err(e) = result {
+                    log(LogLevel::Warning, &format!("Erro na requisição HTTP ({}): {}", label, e));
+                }
+                slot.fetch_sub(1, Ordering::Relaxed);
+            });
+            if spawned.is_err() {
+                active.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    })?;
+
+    Ok(())
+}
+
+/// Mini parser HTTP: linha de requisição e headers até a linha em branco, em até `timeout`
+fn read_request(stream: &TcpStream, timeout: Duration) -> IoResult<Request> {
+    let deadline = DeadlineReader { stream, deadline: Instant::now() + timeout };
+    let mut reader = BufReader::new(deadline.take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut headers = Vec::new();
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    Ok(Request {
+        method: parts.next().unwrap_or_default().to_string(),
+        path: parts.next().unwrap_or_default().to_string(),
+        headers,
+    })
+}
+
+/// Leitura com prazo total: cada `read` só espera o que falta até `deadline`
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "requisição HTTP incompleta no prazo"));
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    /// Uma conexão parada no meio da requisição não segura a resposta das outras
+    #[test]
+    fn stalled_connection_does_not_block_others() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        serve("teste", listener, |request, stream| {
+            Response::route(&request, "/ping", "text/plain", || "pong\n".to_string()).write_to(stream)
+        }).unwrap();
+
+        let mut stalled = TcpStream::connect(address).unwrap();
+        stalled.write_all(b"GET /ping HT").unwrap();
+
+        let started = Instant::now();
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"GET /ping HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "resposta: {}", response);
+        assert!(response.ends_with("\r\n\r\npong\n"));
+        assert!(started.elapsed() < REQUEST_TIMEOUT);
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"POST /ping HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 405"));
+        drop(stalled);
+    }
+
+    /// Bytes pingados abaixo do timeout de cada leitura não estendem o prazo total
+    #[test]
+    fn slow_request_hits_the_deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let dripping = thread::spawn(move || {
+            for byte in b"GET / HTTP/1.1\r\nX-Lento: ".iter().cycle().take(40) {
+                if client.write_all(&[*byte]).is_err() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(25));
+            }
+        });
+
+        let started = Instant::now();
+        let error = read_request(&server, Duration::from_millis(300)).err().unwrap();
+        assert!(telemetry_common::framing::is_timeout(&error), "erro: {}", error);
+        assert!(started.elapsed() < Duration::from_millis(800));
+        drop(server);
+        dripping.join().unwrap();
+    }
+}
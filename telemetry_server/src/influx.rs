@@ -0,0 +1,355 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as FmtWrite,
+    io::{self, BufRead, BufReader, Result as IoResult, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use telemetry_common::is_temperature_unavailable;
+
+use crate::{log, LogLevel};
+
+/// Timeout de conexão e de cada requisição ao InfluxDB
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Teto da espera entre tentativas com o InfluxDB fora
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Seção `[influx]` do `telemetry.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InfluxConfig {
+    /// Endpoint de escrita, ex.: `http://localhost:8086/api/v2/write?org=o&bucket=b&precision=ns`
+    pub url: String,
+    /// Token enviado como `Authorization: Token ...` (InfluxDB 2.x)
+    pub token: Option<String>,
+    pub measurement: String,
+    /// Linhas por requisição
+    pub batch_size: usize,
+    /// Intervalo máximo entre envios de um lote incompleto
+    pub flush_interval_secs: u64,
+    /// Linhas retidas enquanto o InfluxDB está fora; as mais antigas são descartadas
+    pub max_buffered: usize,
+    /// Falhas seguidas no mesmo lote antes de descartá-lo
+    pub max_retries: u32,
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            token: None,
+            measurement: "telemetry".to_string(),
+            batch_size: 100,
+            flush_interval_secs: 5,
+            max_buffered: 10_000,
+            max_retries: 3,
+        }
+    }
+}
+
+impl InfluxConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        HttpTarget::parse(&self.url)?;
+        if self.batch_size == 0 || self.max_buffered == 0 || self.flush_interval_secs == 0 {
+            return Err("influx: batch_size, max_buffered e flush_interval_secs devem ser maiores que zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Envia as amostras em line protocol para o InfluxDB numa thread própria,
+/// para que lentidão ou queda do banco não atrasem as conexões de clientes
+pub struct InfluxExporter {
+    sender: SyncSender<String>,
+    measurement: String,
+    /// Linhas recusadas com a fila da thread cheia
+    dropped: AtomicU64,
+}
+
+impl InfluxExporter {
+    pub fn spawn(config: InfluxConfig) -> IoResult<Self> {
+        let target = HttpTarget::parse(&config.url)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // A thread já retém até `max_buffered` linhas; a fila até ela tem o mesmo limite
+        let (sender, receiver) = mpsc::sync_channel(config.max_buffered);
+        let measurement = escape(&config.measurement, false);
+
+        log(LogLevel::Info, &format!("Exportando para o InfluxDB em {}", config.url));
+        thread::Builder::new()
+            .name("influx".to_string())
+            .spawn(move || Batcher::new(config, target).run(receiver))?;
+
+        Ok(Self { sender, measurement, dropped: AtomicU64::new(0) })
+    }
+
+    /// Enfileira a amostra; valores não finitos e a sentinela de temperatura ficam de fora
+    pub fn export(
+        &self,
+        client: &str,
+        hostname: Option<&str>,
+        namespace: Option<&str>,
+        timestamp_ms: u64,
+        metrics: &HashMap<String, f64>,
+    ) {
+        let tags = ClientTags { client, hostname, namespace };
+        if let Some(line) = format_line(&self.measurement, &tags, timestamp_ms, metrics) {
+            if let Err(TrySendError::Full(_)) = self.sender.try_send(line) {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                if dropped.is_power_of_two() {
+                    log(LogLevel::Warning, &format!("InfluxDB: exportação atrasada, {} linhas descartadas", dropped));
+                }
+            }
+        }
+    }
+}
+
+/// Identificação do cliente numa linha exportada
+struct ClientTags<'a> {
+    client: &'a str,
+    hostname: Option<&'a str>,
+    /// Prefixa os campos (`web-01_cpu`), evitando colisão de séries entre hosts
+    namespace: Option<&'a str>,
+}
+
+/// `medida,client=ID[,host=HOST] [ns_]cpu=42.5,[ns_]mem=1024 <timestamp_ns>`
+fn format_line(measurement: &str, tags: &ClientTags, timestamp_ms: u64, metrics: &HashMap<String, f64>) -> Option<String> {
+    let mut names: Vec<&String> = metrics.keys().collect();
+    names.sort();
+
+    let mut fields = String::new();
+    for name in names {
+        let value = metrics[name];
+        if !value.is_finite() || is_temperature_unavailable(name, value) {
+            continue;
+        }
+        if !fields.is_empty() {
+            fields.push(',');
+        }
+        let field = match tags.namespace {
+            Some(namespace) => format!("{}_{}", namespace, name),
+            None => name.clone(),
+        };
+        // Chaves de campo escapam `=` como as tags
+        let _ = write!(fields, "{}={}", escape(&field.to_lowercase(), true), value);
+    }
+    if fields.is_empty() {
+        return None;
+    }
+
+    let mut line = format!("{},client={}", measurement, escape(tags.client, true));
+    if let Some(hostname) = tags.hostname {
+        let _ = write!(line, ",host={}", escape(hostname, true));
+    }
+    let _ = write!(line, " {} {}", fields, u128::from(timestamp_ms) * 1_000_000);
+    Some(line)
+}
+
+/// Escapa vírgulas, espaços e (em tags) sinais de igual, como exige o line protocol
+fn escape(raw: &str, is_tag: bool) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        if c == ',' || c == ' ' || (is_tag && c == '=') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Acumula linhas e as envia em lotes, retendo o que falhar até `max_buffered`
+struct Batcher {
+    config: InfluxConfig,
+    target: HttpTarget,
+    pending: VecDeque<String>,
+    /// Tentativas falhas seguidas com o lote da frente
+    failures: u32,
+    dropped: u64,
+    /// Tentativas falhas seguidas, mesmo entre lotes descartados; base do backoff
+    attempts_failed: u32,
+    /// Com o banco fora, nada é enviado antes deste instante
+    retry_at: Option<Instant>,
+}
+
+impl Batcher {
+    fn new(config: InfluxConfig, target: HttpTarget) -> Self {
+        Self { config, target, pending: VecDeque::new(), failures: 0, dropped: 0, attempts_failed: 0, retry_at: None }
+    }
+
+    fn run(&mut self, receiver: Receiver<String>) {
+        let interval = Duration::from_secs(self.config.flush_interval_secs);
+        let mut last_flush = Instant::now();
+
+        loop {
+            match receiver.recv_timeout(interval) {
+                Ok(line) => self.push(line),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    // Com o banco fora, o encerramento não espera mais uma tentativa
+                    if self.retry_at.is_none() {
+                        self.flush();
+                    }
+                    return;
+                }
+            }
+
+            // Em backoff, as linhas só se acumulam: a próxima tentativa espera o prazo
+            let due = self.retry_at.is_none_or(|retry_at| Instant::now() >= retry_at);
+            if due && (self.pending.len() >= self.config.batch_size || last_flush.elapsed() >= interval) {
+                self.flush();
+                last_flush = Instant::now();
+            }
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        if self.pending.len() >= self.config.max_buffered {
+            self.pending.pop_front();
+            self.dropped += 1;
+            if self.dropped.is_power_of_two() {
+                log(LogLevel::Warning, &format!("InfluxDB: buffer cheio, {} linhas descartadas", self.dropped));
+            }
+        }
+        self.pending.push_back(line);
+    }
+
+    fn flush(&mut self) {
+        while !self.pending.is_empty() {
+            let count = self.pending.len().min(self.config.batch_size);
+            let body = self.pending.iter().take(count).map(String::as_str).collect::<Vec<_>>().join("\n");
+
+            match self.target.post(&body, self.config.token.as_deref()) {
+                Ok(()) => {
+                    self.pending.drain(..count);
+                    self.failures = 0;
+                    self.attempts_failed = 0;
+                    self.retry_at = None;
+                }
+                Err(e) => {
+                    self.failures += 1;
+                    if self.failures >= self.config.max_retries {
+                        log(LogLevel::Error, &format!(
+                            "InfluxDB: lote de {} linhas descartado após {} falhas: {}", count, self.failures, e
+                        ));
+                        self.pending.drain(..count);
+                        self.failures = 0;
+                    } else {
+                        log(LogLevel::Warning, &format!(
+                            "InfluxDB: falha ao enviar lote ({}/{}): {}", self.failures, self.config.max_retries, e
+                        ));
+                    }
+                    // O banco continua fora mesmo com o lote descartado: a espera só zera num envio aceito
+                    let interval = Duration::from_secs(self.config.flush_interval_secs);
+                    self.retry_at = Some(Instant::now() + retry_delay(interval, self.attempts_failed));
+                    self.attempts_failed += 1;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Espera antes da próxima tentativa: o intervalo de flush após a primeira
+/// falha, dobrando a cada nova falha seguida até `MAX_RETRY_DELAY`
+fn retry_delay(interval: Duration, previous_failures: u32) -> Duration {
+    interval.saturating_mul(1 << previous_failures.min(16)).min(MAX_RETRY_DELAY)
+}
+
+/// Destino HTTP simples (sem TLS) extraído da URL configurada
+#[derive(Debug)]
+struct HttpTarget {
+    host: String,
+    path: String,
+}
+
+impl HttpTarget {
+    fn parse(url: &str) -> Result<Self, String> {
+        let rest = url.strip_prefix("http://")
+            .ok_or_else(|| format!("influx.url deve começar com http:// ({})", url))?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        if authority.is_empty() {
+            return Err(format!("influx.url sem host: {}", url));
+        }
+
+        let host = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+        Ok(Self { host, path: path.to_string() })
+    }
+
+    fn post(&self, body: &str, token: Option<&str>) -> IoResult<()> {
+        let address = self.host.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host do InfluxDB não resolvido"))?;
+        let mut stream = TcpStream::connect_timeout(&address, HTTP_TIMEOUT)?;
+        stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.path,
+            self.host,
+            body.len()
+        );
+        if let Some(token) = token {
+            let _ = write!(request, "Authorization: Token {}\r\n", token);
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+        stream.write_all(body.as_bytes())?;
+
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line)?;
+        let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+        if status.starts_with('2') {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!("resposta HTTP {}", status_line.trim())))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        let interval = Duration::from_secs(5);
+        let delays: Vec<u64> = (0..8).map(|failures| retry_delay(interval, failures).as_secs()).collect();
+        assert_eq!(delays, [5, 10, 20, 40, 80, 160, 300, 300]);
+        assert_eq!(retry_delay(interval, u32::MAX), MAX_RETRY_DELAY);
+    }
+
+    /// Com o banco fora, só uma tentativa por prazo, não uma por amostra recebida
+    #[test]
+    fn failed_flush_waits_for_backoff() {
+        // Porta fechada: a conexão é recusada na hora
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let config = InfluxConfig {
+            url: format!("http://127.0.0.1:{}/write", port),
+            batch_size: 1,
+            max_retries: 10,
+            ..InfluxConfig::default()
+        };
+        let target = HttpTarget::parse(&config.url).unwrap();
+        let mut batcher = Batcher::new(config, target);
+        let (sender, receiver) = mpsc::sync_channel(10);
+        for i in 0..5 {
+            sender.send(format!("telemetry,client=a cpu={} 0", i)).unwrap();
+        }
+        drop(sender);
+
+        batcher.run(receiver);
+        assert_eq!(batcher.failures, 1);
+        assert_eq!(batcher.pending.len(), 5);
+    }
+}
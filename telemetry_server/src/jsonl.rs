@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Result as IoResult, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use telemetry_common::{is_temperature_unavailable, timefmt::civil_from_days};
+
+use crate::{epoch_millis, log, LogLevel};
+
+/// Intervalo máximo com linhas só no buffer; limita o que se perde num crash
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+const MILLIS_PER_DAY: u64 = 86_400_000;
+
+/// Persiste todas as amostras em `telemetry-AAAA-MM-DD.jsonl` no diretório
+/// configurado, com um arquivo por dia (UTC). A escrita fica numa thread
+/// própria para que o disco não atrase as conexões de clientes
+pub struct DailyJsonl {
+    /// Linha JSON e o momento de chegada, que decide o arquivo do dia
+    sender: Sender<(u64, String)>,
+}
+
+impl DailyJsonl {
+    pub fn spawn(dir: &Path) -> IoResult<Self> {
+        fs::create_dir_all(dir)?;
+        let (sender, receiver) = mpsc::channel();
+        let mut files = DailyFiles::new(dir.to_path_buf());
+
+        log(LogLevel::Info, &format!("Gravando amostras em {}/telemetry-AAAA-MM-DD.jsonl", dir.display()));
+        thread::Builder::new()
+            .name("jsonl".to_string())
+            .spawn(move || files.run(receiver))?;
+
+        Ok(Self { sender })
+    }
+
+    /// Enfileira `{"client":...,"timestamp":...,"metrics":{...}}`, sem a sentinela de temperatura
+    pub fn write(&self, client: &str, timestamp: u64, metrics: &HashMap<String, f32>) {
+        let metrics: HashMap<&String, &f32> = metrics.iter()
+            .filter(|(name, value)| !is_temperature_unavailable(name, f64::from(**value)))
+            .collect();
+        let line = serde_json::json!({
+            "client": client,
+            "timestamp": timestamp,
+            "metrics": metrics,
+        });
+        let _ = self.sender.send((epoch_millis(), line.to_string()));
+    }
+}
+
+/// Arquivo do dia corrente, trocado quando chega uma linha de outro dia
+struct DailyFiles {
+    dir: PathBuf,
+    /// Dias desde 1970-01-01 (UTC) do arquivo aberto
+    day: Option<u64>,
+    writer: Option<BufWriter<File>>,
+    last_flush: Instant,
+}
+
+impl DailyFiles {
+    fn new(dir: PathBuf) -> Self {
+        Self { dir, day: None, writer: None, last_flush: Instant::now() }
+    }
+
+    fn run(&mut self, receiver: Receiver<(u64, String)>) {
+        loop {
+            match receiver.recv_timeout(FLUSH_INTERVAL) {
+                Ok((received_ms, line)) => {
+                    if let Err(e) = self.write(received_ms, &line) {
+                        log(LogLevel::Warning, &format!("Erro ao gravar JSONL: {}", e));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.flush();
+                    return;
+                }
+            }
+            if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+                self.flush();
+            }
+        }
+    }
+
+    fn write(&mut self, received_ms: u64, line: &str) -> IoResult<()> {
+        let day = received_ms / MILLIS_PER_DAY;
+        if self.day != Some(day) {
+            // Fecha o arquivo do dia anterior com tudo gravado antes de abrir o novo
+            self.flush();
+            self.writer = None;
+            let path = self.dir.join(file_name(day));
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            if self.day.is_some() {
+                log(LogLevel::Info, &format!("Novo arquivo JSONL: {}", path.display()));
+            }
+            self.writer = Some(BufWriter::new(file));
+            self.day = Some(day);
+        }
+
+        match &mut self.writer {
+            Some(writer) => writeln!(writer, "{}", line),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if let Some(writer) = &mut self.writer {
+            if let Err(e) = writer.flush() {
+                log(LogLevel::Warning, &format!("Erro ao gravar JSONL: {}", e));
+            }
+        }
+    }
+}
+
+/// `telemetry-2024-02-29.jsonl`
+fn file_name(day: u64) -> String {
+    let (year, month, day) = civil_from_days(day as i64);
+    format!("telemetry-{:04}-{:02}-{:02}.jsonl", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_file_by_utc_day() {
+        assert_eq!(file_name(0), "telemetry-1970-01-01.jsonl");
+        assert_eq!(file_name(1_709_208_000_123 / MILLIS_PER_DAY), "telemetry-2024-02-29.jsonl");
+    }
+
+    #[test]
+    fn rotates_at_midnight() {
+        let dir = std::env::temp_dir().join(format!("telemetry-jsonl-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut files = DailyFiles::new(dir.clone());
+
+        let midnight = 19_783 * MILLIS_PER_DAY;
+        files.write(midnight - 1, "{\"a\":1}").unwrap();
+        files.write(midnight, "{\"b\":2}").unwrap();
+        files.flush();
+
+        let before = fs::read_to_string(dir.join("telemetry-2024-02-29.jsonl")).unwrap();
+        let after = fs::read_to_string(dir.join("telemetry-2024-03-01.jsonl")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(before, "{\"a\":1}\n");
+        assert_eq!(after, "{\"b\":2}\n");
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/// Janela do rate-limit de conexões novas por IP
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Conta conexões novas por IP numa janela deslizante de um minuto.
+/// Usado só pela thread de accept, então dispensa lock
+pub struct ConnectionRateLimiter {
+    max_per_minute: u32,
+    attempts: HashMap<IpAddr, VecDeque<Instant>>,
+    last_prune: Instant,
+}
+
+impl ConnectionRateLimiter {
+    /// `max_per_minute = 0` desativa o limite
+    pub fn new(max_per_minute: u32) -> Self {
+        Self {
+            max_per_minute,
+            attempts: HashMap::new(),
+            last_prune: Instant::now(),
+        }
+    }
+
+    /// Registra a tentativa e diz se ela cabe no limite do IP
+    pub fn allow(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.max_per_minute == 0 {
+            return true;
+        }
+        self.prune(now);
+
+        let window = self.attempts.entry(ip).or_default();
+        while window.front().is_some_and(|&at| now.duration_since(at) >= RATE_WINDOW) {
+            window.pop_front();
+        }
+        if window.len() >= self.max_per_minute as usize {
+            return false;
+        }
+        window.push_back(now);
+        true
+    }
+
+    /// Remove IPs sem tentativas na janela, no máximo uma vez por janela
+    fn prune(&mut self, now: Instant) {
+        if now.duration_since(self.last_prune) < RATE_WINDOW {
+            return;
+        }
+        self.last_prune = now;
+        self.attempts.retain(|_, window| {
+            window.back().is_some_and(|&at| now.duration_since(at) < RATE_WINDOW)
+        });
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Result as IoResult, Write},
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+use serde::{Deserialize, Serialize};
+
+use telemetry_common::timefmt;
+
+use crate::{
+    config::{OutputMode, ServerConfig},
+    epoch_millis,
+};
+
+/// Logger global, inicializado no `main` a partir da configuração.
+/// Antes disso (ex.: erro ao ler o `telemetry.toml`) valem os padrões: tudo no console, com emojis
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Níveis de log para diferentes tipos de mensagens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    #[default]
+    Info,
+    #[serde(alias = "warn")]
+    Warning,
+    Error,
+    Success,
+}
+
+impl LogLevel {
+    /// Nível por extenso, sem diferenciar maiúsculas: `error`, `warn` (ou `warning`),
+    /// `info` ou `success`, que filtra como `info`
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" | "warning" => Ok(LogLevel::Warning),
+            "info" => Ok(LogLevel::Info),
+            "success" => Ok(LogLevel::Success),
+            _ => Err(format!("Nível de log inválido '{}' (use error, warn ou info)", raw)),
+        }
+    }
+
+    /// Gravidade para o filtro de nível mínimo; `Success` conta como `Info`
+    fn severity(self) -> u8 {
+        match self {
+            LogLevel::Info | LogLevel::Success => 0,
+            LogLevel::Warning => 1,
+            LogLevel::Error => 2,
+        }
+    }
+
+    fn emoji(self) -> &'static str {
+        match self {
+            LogLevel::Info => "ℹ️",
+            LogLevel::Warning => "⚠️",
+            LogLevel::Error => "❌",
+            LogLevel::Success => "✅",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warning => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Success => "SUCCESS",
+        }
+    }
+}
+
+struct Logger {
+    min_level: LogLevel,
+    emojis: bool,
+    /// Todas as linhas no stderr, deixando o stdout livre para a saída JSON
+    stderr_only: bool,
+    file: Option<Mutex<LogFile>>,
+}
+
+/// Arquivo de log com rotação simples: ao passar de `max_bytes`, o atual vira `<arquivo>.1`
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf, max_bytes: u64) -> IoResult<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size, max_bytes })
+    }
+
+    fn write_line(&mut self, line: &str) -> IoResult<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> IoResult<()> {
+        let mut backup = self.path.clone().into_os_string();
+        backup.push(".1");
+        fs::rename(&self.path, &backup)?;
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Configura nível mínimo, emojis e arquivo de log; só a primeira chamada tem efeito
+pub fn init(config: &ServerConfig) -> IoResult<()> {
+    let file = match &config.log_file {
+        Some(path) => Some(Mutex::new(LogFile::open(PathBuf::from(path), config.log_max_bytes)?)),
+        None => None,
+    };
+
+    let _ = LOGGER.set(Logger {
+        min_level: config.log_level,
+        emojis: config.log_emojis,
+        stderr_only: config.output == OutputMode::Json,
+        file,
+    });
+    Ok(())
+}
+
+/// Escreve `TIMESTAMP EMOJI NIVEL: mensagem` no console (erros, ou tudo no modo JSON, no stderr) e no arquivo, se houver
+pub fn log(level: LogLevel, message: &str) {
+    let logger = LOGGER.get();
+    let min_level = logger.map_or(LogLevel::Info, |logger| logger.min_level);
+    if level.severity() < min_level.severity() {
+        return;
+    }
+
+    let line = format_line(logger, level.emoji(), level.name(), message);
+    match level {
+        LogLevel::Error => eprintln!("{}", line),
+        _ if logger.is_some_and(|logger| logger.stderr_only) => eprintln!("{}", line),
+        _ => println!("{}", line),
+    }
+    write_file(logger, &line);
+}
+
+/// Linha do modo `--debug-protocol`, sempre no stderr e marcada com `PROTO`,
+/// para não se misturar à saída normal; ignora o nível mínimo
+pub fn log_protocol(message: &str) {
+    let logger = LOGGER.get();
+    let line = format_line(logger, "🔬", "PROTO", message);
+    eprintln!("{}", line);
+    write_file(logger, &line);
+}
+
+fn format_line(logger: Option<&Logger>, emoji: &str, name: &str, message: &str) -> String {
+    let timestamp = timefmt::format_iso8601(epoch_millis());
+    if logger.is_none_or(|logger| logger.emojis) {
+        format!("{} {} {}: {}", timestamp, emoji, name, message)
+    } else {
+        format!("{} {}: {}", timestamp, name, message)
+    }
+}
+
+fn write_file(logger: Option<&Logger>, line: &str) {
+    if let Some(file) = logger.and_then(|logger| logger.file.as_ref()) {
+        let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = file.write_line(line) {
+            eprintln!("❌ Falha ao gravar log em {}: {}", file.path.display(), e);
+        }
+    }
+}
@@ -1,133 +1,926 @@
+mod alerts;
+mod apps;
+mod auth;
+mod commands;
+mod clock;
+mod config;
+mod csv_writer;
+#[cfg(unix)]
+mod daemon;
+mod discovery;
+mod display;
+mod health;
+mod history;
+mod http;
+mod influx;
+mod jsonl;
+mod limits;
+mod logger;
+mod notify;
+mod output;
+mod prometheus;
+mod protocol_debug;
+mod parse_stats;
+mod ranges;
+mod replay;
+mod session;
+mod severity;
+mod sqlite;
+mod statsd;
+mod transport;
+mod udp;
+mod web;
+
 use std::{
-    collections::HashMap,
-    io::{self, BufRead, BufReader, Result as IoResult},
-    net::{SocketAddr, TcpListener, TcpStream},
-    fmt,
+    collections::{BTreeMap, HashMap},
+    env,
+    io::{self, BufRead, IsTerminal, Result as IoResult, Write},
+    net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, UdpSocket},
+    path::Path,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+#[cfg(unix)]
+use std::os::unix::{
+    fs::FileTypeExt,
+    net::{UnixListener, UnixStream},
+};
+use serde::Deserialize;
+use telemetry_common::{
+    compression,
+    format::FormatterTable,
+    framing::{self, is_timeout, Framing, MAX_FRAME_SIZE},
+    reader::MessageReader,
+    commands::{rejection_json, Ping, ServerCommand},
+    protocol,
+    sample::{TelemetrySample, TopProcess},
+    value::Value,
+    UNIX_ADDRESS_PREFIX,
 };
+use alerts::AlertRule;
+use config::{OutputMode, ServerConfig, TransportKind, CONFIG_PATH};
+use csv_writer::CsvWriter;
+use history::MetricHistory;
+use commands::CommandRegistry;
+use display::{CategoryTable, MetricFilter};
+use influx::InfluxExporter;
+use jsonl::DailyJsonl;
+use limits::ConnectionRateLimiter;
+use logger::{log, LogLevel};
+use output::{OutputSink, StdoutSink};
+use ranges::RangeTable;
+use replay::Recorder;
+use session::{ClientInfo, ClientSession};
+use severity::SeverityTable;
+use sqlite::SqliteStore;
+use statsd::StatsdExporter;
+use web::WebHub;
+use transport::{ClientStream, Socket};
 
-// LocalHost IP for Tests
-// const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
-const DEFAULT_ADDRESS: &str = "0.0.0.0:8080";
-const BUFFER_SIZE: usize = 4096;
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[1;1H";
+const RED: &str = "\x1B[31m";
+const RESET: &str = "\x1B[0m";
+/// Intervalo entre verificações do listener não bloqueante e do pedido de encerramento
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Tempo máximo de espera pelas threads de clientes no encerramento
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Códigos de saída, para scripts distinguirem as falhas
+const EXIT_FAILURE: u8 = 1;
+const EXIT_BIND: u8 = 2;
+const EXIT_CONFIG: u8 = 4;
 
 type TelemetryResult<T> = IoResult<T>;
 
-/// Níveis de log para diferentes tipos de mensagens
-#[derive(Debug, Clone, Copy)]
-enum LogLevel {
-    Info,
-    Warning,
-    Error,
-    Success,
-}
-
-impl fmt::Display for LogLevel {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let (emoji, name) = match self {
-            LogLevel::Info => ("ℹ️", "INFO"),
-            LogLevel::Warning => ("⚠️", "WARN"),
-            LogLevel::Error => ("❌", "ERROR"),
-            LogLevel::Success => ("✅", "SUCCESS"),
+/// Última amostra recebida de cada cliente conectado
+type SharedSamples = Arc<Mutex<HashMap<SocketAddr, LatestSample>>>;
+
+/// Última amostra de um cliente, com o namespace que prefixa as métricas exportadas
+struct LatestSample {
+    hostname: Option<String>,
+    /// Namespace efetivo do cliente (o hostname, se ele não enviou um)
+    namespace: Option<String>,
+    metrics: HashMap<String, f32>,
+    /// Originais das customizadas que não são float, para o Prometheus exportá-las exatas
+    values: BTreeMap<String, Value>,
+}
+
+/// Estado compartilhado entre o servidor e as threads de clientes
+struct ServerState {
+    config: ServerConfig,
+    /// Tela: um bloco de saída por cliente, ordenado por endereço
+    screen: Mutex<BTreeMap<SocketAddr, String>>,
+    csv: Option<Mutex<CsvWriter>>,
+    influx: Option<InfluxExporter>,
+    statsd: Option<StatsdExporter>,
+    /// Gravação das amostras recebidas (`--record`)
+    recorder: Option<Recorder>,
+    /// Arquivos JSONL diários (`jsonl_dir`)
+    jsonl: Option<DailyJsonl>,
+    /// Banco SQLite para consultas históricas (`[sqlite]`)
+    sqlite: Option<SqliteStore>,
+    samples: SharedSamples,
+    alert_rules: Arc<[AlertRule]>,
+    /// Faixas plausíveis usadas para sinalizar valores suspeitos
+    ranges: RangeTable,
+    /// Cores por severidade no painel
+    severity: SeverityTable,
+    /// Rótulo e unidade das métricas: embutidas e `[units]`
+    formatters: FormatterTable,
+    /// Métricas exibidas na tela (`--show`) e sua ordem
+    display_filter: MetricFilter,
+    /// Seção de cada métrica no painel
+    categories: CategoryTable,
+    /// Históricos de clientes desconectados, retomados quando o mesmo ID reconecta
+    histories: Mutex<HashMap<String, MetricHistory>>,
+    tls: Option<Arc<rustls::ServerConfig>>,
+    /// Token exigido no handshake (autenticação desativada quando `None`)
+    auth_token: Option<String>,
+    /// Como cada amostra aparece no stdout
+    display: DisplayMode,
+    /// Para onde vai o que `display` produz (stdout, fora dos testes)
+    output: Arc<dyn OutputSink>,
+    /// Navegadores do dashboard web, presente quando `web_address` está configurado
+    web: Option<Arc<WebHub>>,
+    /// Clientes ativos (TCP e UDP), listados pelo comando `L`
+    clients: Mutex<BTreeMap<SocketAddr, ClientInfo>>,
+    /// Conexões TCP abertas, para comandos remotos e para o encerramento
+    connections: Mutex<HashMap<SocketAddr, ConnectionHandle>>,
+    /// Sinalizado por Ctrl+C; o loop de `run` sai e o servidor encerra graciosamente
+    shutdown: AtomicBool,
+    /// Início do servidor, base do uptime do `/health`
+    started_at: Instant,
+    /// Amostras processadas desde o início, de todos os clientes
+    samples_processed: AtomicU64,
+}
+
+/// Conexão TCP aberta, vista de fora da thread do cliente
+struct ConnectionHandle {
+    /// Clone do socket: derruba a leitura no encerramento e envia comandos sem TLS
+    socket: Socket,
+    /// Mensagens para clientes TLS: o stream pertence à thread da conexão,
+    /// que as envia na próxima vez que acordar (mensagem recebida ou timeout)
+    outbox: Vec<String>,
+}
+
+/// Destino de cada amostra no stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DisplayMode {
+    /// Painel redesenhado com escapes ANSI
+    Dashboard,
+    /// Uma linha de log compacta por amostra (`--no-clear` ou stdout fora de um TTY)
+    Lines,
+    /// Uma linha JSON por amostra (`--output json`), sem ANSI nem cabeçalho
+    Json,
+}
+
+impl DisplayMode {
+    fn for_config(config: &ServerConfig) -> Self {
+        match config.output {
+            OutputMode::Json => DisplayMode::Json,
+            OutputMode::Pretty if config.clear_screen && io::stdout().is_terminal() => DisplayMode::Dashboard,
+            OutputMode::Pretty => DisplayMode::Lines,
+        }
+    }
+}
+
+impl ServerState {
+    /// Pede o encerramento gracioso; o loop de accept percebe em até `ACCEPT_POLL_INTERVAL`
+    fn request_shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    /// Envia uma linha JSON ao cliente identificado por ID ou endereço
+    fn send_to_client(&self, target: &str, message: &str) -> Result<SocketAddr, String> {
+        let addr = self.clients.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|(addr, info)| info.client_id == target || addr.to_string() == target)
+            .map(|(addr, _)| *addr);
+
+        let mut connections = self.connections.lock().unwrap_or_else(|e| e.into_inner());
+        let (addr, handle) = addr
+            .and_then(|addr| connections.get_mut(&addr).map(|handle| (addr, handle)))
+            .ok_or_else(|| format!("Cliente {} não está conectado por TCP", target))?;
+
+        self.deliver(handle, message)
+            .map_err(|e| format!("Falha ao enviar comando a {}: {}", addr, e))?;
+        Ok(addr)
+    }
+
+    /// Envia uma linha JSON a todos os clientes TCP; devolve quantos receberam
+    fn broadcast_to_clients(&self, message: &str) -> usize {
+        let mut connections = self.connections.lock().unwrap_or_else(|e| e.into_inner());
+        let mut delivered = 0;
+        for (addr, handle) in connections.iter_mut() {
+            match self.deliver(handle, message) {
+                Ok(()) => delivered += 1,
+                Err(e) => log(LogLevel::Warning, &format!("Falha ao enviar comando a {}: {}", addr, e)),
+            }
+        }
+        delivered
+    }
+
+    /// Com TLS a linha vai para a fila da conexão; sem TLS é escrita direto no socket
+    fn deliver(&self, handle: &mut ConnectionHandle, message: &str) -> io::Result<()> {
+        let line = format!("{}\n", message);
+        if self.tls.is_some() {
+            handle.outbox.push(line);
+            return Ok(());
+        }
+        handle.socket.write_all(line.as_bytes())
+    }
+}
+
+/// Identidade declarada pelo cliente no handshake
+#[derive(Debug, Clone, Deserialize)]
+struct ClientIdentity {
+    client_id: String,
+    #[serde(default)]
+    hostname: Option<String>,
+    /// Prefixo das métricas nas exportações (ex.: `web-server-01`); o hostname quando ausente
+    #[serde(default)]
+    namespace: Option<String>,
+    /// Verdadeiro quando o ID foi derivado do endereço por falta de handshake
+    #[serde(skip)]
+    anonymous: bool,
+}
+
+impl ClientIdentity {
+    /// Cliente sem handshake: ID derivado do endereço
+    fn anonymous(addr: SocketAddr) -> Self {
+        Self {
+            client_id: format!("anon-{}", addr),
+            hostname: None,
+            namespace: None,
+            anonymous: true,
+        }
+    }
+
+    fn display_name(&self) -> String {
+        let host = match (&self.hostname, &self.namespace) {
+            (Some(hostname), Some(namespace)) if hostname != namespace => format!("{} [{}]", hostname, namespace),
+            (Some(hostname), _) => hostname.clone(),
+            (None, Some(namespace)) => format!("[{}]", namespace),
+            (None, None) => return self.client_id.clone(),
         };
-        write!(f, "{} {}", emoji, name)
+        format!("{} ({})", host, self.client_id)
+    }
+
+    /// Namespace efetivo: o enviado no handshake ou, na falta dele, o hostname
+    fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref().or(self.hostname.as_deref()).filter(|namespace| !namespace.is_empty())
+    }
+}
+
+/// Primeira mensagem da conexão: `{"handshake": {"client_id": ..., "hostname": ..., "namespace": ...}}`
+#[derive(Debug, Deserialize)]
+struct HandshakeMessage {
+    handshake: Handshake,
+}
+
+#[derive(Debug, Deserialize)]
+struct Handshake {
+    #[serde(flatten)]
+    identity: ClientIdentity,
+    /// Token pré-compartilhado, exigido quando `TELEMETRY_AUTH_TOKEN` está definido
+    #[serde(default)]
+    auth_token: Option<String>,
+    /// O cliente quer mandar amostras em arrays; só o faz depois do `accept_batch`
+    #[serde(default)]
+    batch: bool,
+    /// Nonce do primeiro ping: o `pong` de volta habilita os pings periódicos do cliente
+    #[serde(default)]
+    ping: Option<u64>,
+    /// Relógio do cliente (epoch em ms) no envio, base da estimativa de offset
+    #[serde(default)]
+    clock: Option<u64>,
+    /// Versão do protocolo falada pelo cliente; ausente nos clientes v1
+    #[serde(default)]
+    protocol_version: Option<u32>,
+}
+
+/// Pacote de telemetria já validado, com as métricas numa visão plana
+#[derive(Debug)]
+struct TelemetryPacket {
+    /// Epoch em milissegundos do momento da coleta no cliente
+    timestamp: Option<u64>,
+    top_process: Option<TopProcess>,
+    metrics: HashMap<String, f32>,
+    /// Customizadas que não são float (inteiros, texto, booleanos), exibidas no valor original
+    values: BTreeMap<String, Value>,
+}
+
+impl From<TelemetrySample> for TelemetryPacket {
+    fn from(mut sample: TelemetrySample) -> Self {
+        let (metrics, values) = sample.take_metrics();
+        Self {
+            timestamp: sample.timestamp,
+            top_process: sample.top_process,
+            metrics,
+            values,
+        }
+    }
+}
+
+impl TelemetryPacket {
+    fn exported(&self) -> HashMap<String, f64> {
+        export_values(&self.metrics, &self.values)
+    }
+}
+
+/// Visão numérica das exportações (Prometheus, InfluxDB, SQLite, StatsD): inteiros e
+/// booleanos customizados saem exatos do valor original, sem passar pelo `f32`, e
+/// os demais na representação curta do `f32` (`0.1`, não `0.10000000149011612`)
+fn export_values(metrics: &HashMap<String, f32>, values: &BTreeMap<String, Value>) -> HashMap<String, f64> {
+    metrics.iter()
+        .map(|(name, value)| {
+            let exact = values.get(name).and_then(Value::as_f64);
+            (name.clone(), exact.or_else(|| Value::from(*value).as_f64()).unwrap_or(f64::NAN))
+        })
+        .collect()
+}
+
+/// Epoch atual em milissegundos segundo o relógio local
+fn epoch_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn main() -> ExitCode {
+    let mut config = match ServerConfig::load(Path::new(CONFIG_PATH)) {
+        Ok(config) => config,
+        Err(e) => {
+            log(LogLevel::Error, &format!("Configuração inválida em {}: {}", CONFIG_PATH, e));
+            return ExitCode::from(EXIT_CONFIG);
+        }
+    };
+
+    if let Err(message) = config.apply_env().and_then(|()| config.apply_args(env::args().skip(1))) {
+        log(LogLevel::Error, &message);
+        return ExitCode::from(EXIT_CONFIG);
+    }
+
+    if let Err(e) = logger::init(&config) {
+        log(LogLevel::Error, &format!("Não foi possível abrir o arquivo de log: {}", e));
+        return ExitCode::from(EXIT_FAILURE);
+    }
+    protocol_debug::set_enabled(config.debug_protocol);
+
+    #[cfg(not(unix))]
+    if config.daemon {
+        log(LogLevel::Warning, "--daemon só é suportado em sistemas Unix; seguindo em primeiro plano");
+        config.daemon = false;
+    }
+
+    // O bind vem antes do fork, para a porta ocupada falhar ainda no terminal
+    let listener = match TelemetryServer::bind(&config) {
+        Ok(listener) => listener,
+        Err(e) => return ExitCode::from(report_fatal_error(&e)),
+    };
+
+    // Antes do `with_listener`, que já cria threads de exportação
+    #[cfg(unix)]
+    let pid_file = config.daemon.then(|| std::path::PathBuf::from(&config.pid_file));
+    #[cfg(unix)]
+    let mut readiness = None;
+    #[cfg(unix)]
+    if let Some(pid_file) = &pid_file {
+        match daemon::daemonize(pid_file) {
+            Ok(ready) => readiness = Some(ready),
+            Err(e) => {
+                log(LogLevel::Error, &format!("Falha ao entrar em modo daemon: {}", e));
+                return ExitCode::from(EXIT_FAILURE);
+            }
+        }
+    }
+
+    let started = TelemetryServer::with_listener(config, listener, Arc::new(StdoutSink))
+        .and_then(|server| server.start().map(|()| server));
+    let code = started.as_ref().err().map(report_fatal_error);
+    // O terminal que iniciou o daemon sai com o resultado da inicialização
+    #[cfg(unix)]
+    if let Some(readiness) = readiness {
+        readiness.report(code.unwrap_or(0));
+    }
+
+    let result = match started {
+        Ok(server) => server.run().map_err(|e| report_fatal_error(&e)),
+        Err(_) => Err(code.unwrap_or(EXIT_FAILURE)),
+    };
+    #[cfg(unix)]
+    if let Some(pid_file) = &pid_file {
+        daemon::remove_pid_file(pid_file);
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => ExitCode::from(code),
+    }
+}
+
+/// Loga o erro que derrubou o servidor, com uma dica quando há, e escolhe o código de saída
+fn report_fatal_error(error: &io::Error) -> u8 {
+    match exit_code(error) {
+        EXIT_BIND => {
+            log(LogLevel::Error, &format!("Falha de bind: {}", error));
+            log(LogLevel::Info, "Dica: a porta pode estar em uso por outro processo; escolha outra com `telemetry_server 0.0.0.0:PORTA` ou `address` no telemetry.toml");
+            EXIT_BIND
+        }
+        EXIT_CONFIG => {
+            log(LogLevel::Error, &format!("Configuração inválida: {}", error));
+            EXIT_CONFIG
+        }
+        code => {
+            log(LogLevel::Error, &format!("Servidor encerrado com erro: {}", error));
+            code
+        }
     }
 }
 
-fn log(level: LogLevel, message: &str) {
-    match level {
-        LogLevel::Error => eprintln!("{}: {}", level, message),
-        _ => println!("{}: {}", level, message),
+fn exit_code(error: &io::Error) -> u8 {
+    match error.kind() {
+        io::ErrorKind::AddrInUse | io::ErrorKind::AddrNotAvailable => EXIT_BIND,
+        io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData | io::ErrorKind::Unsupported => EXIT_CONFIG,
+        _ => EXIT_FAILURE,
     }
 }
 
-fn main() -> TelemetryResult<()> {
-    let server = TelemetryServer::new(DEFAULT_ADDRESS)?;
-    server.run()
+/// Acrescenta o endereço à mensagem, mantendo o tipo do erro para o código de saída
+fn bind_error(address: &str, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", address, e))
+}
+
+/// Socket de escuta conforme o transporte configurado
+enum Listener {
+    Tcp(TcpListener),
+    Udp(UdpSocket),
+    /// Endereço `unix:/caminho`: só clientes na mesma máquina
+    #[cfg(unix)]
+    Unix(UnixListener),
+    /// Modo `--replay`: nenhum socket aberto
+    Replay,
+}
+
+impl Listener {
+    /// Endereço efetivamente aberto, anunciado na descoberta
+    fn local_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Listener::Tcp(listener) => listener.local_addr().ok(),
+            Listener::Udp(socket) => socket.local_addr().ok(),
+            // Um socket local não tem o que anunciar na LAN
+            #[cfg(unix)]
+            Listener::Unix(_) => None,
+            Listener::Replay => None,
+        }
+    }
+
+    /// Aceita uma conexão pendente (TCP ou Unix). Clientes Unix não têm endereço
+    /// de rede; recebem uma chave sintética `0.0.0.0:N` para os registros por cliente
+    fn accept(&self, unix_clients: &mut u16) -> io::Result<Option<(Socket, SocketAddr)>> {
+        let accepted = match self {
+            Listener::Tcp(listener) => listener.accept().map(|(socket, addr)| (Socket::Tcp(socket), addr)),
+            #[cfg(unix)]
+            Listener::Unix(listener) => listener.accept().map(|(socket, _)| {
+                *unix_clients = unix_clients.wrapping_add(1).max(1);
+                (Socket::Unix(socket), SocketAddr::from((Ipv4Addr::UNSPECIFIED, *unix_clients)))
+            }),
+            _ => return Ok(None),
+        };
+        match accepted {
+            Ok(connection) => Ok(Some(connection)),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Abre o Unix domain socket em `path`. Um socket que sobrou de uma execução
+/// anterior (ninguém aceitando conexões nele) é removido antes do bind; qualquer
+/// outro arquivo no caminho fica intacto e o erro original sobe
+#[cfg(unix)]
+fn bind_unix(path: &str) -> io::Result<UnixListener> {
+    match UnixListener::bind(path) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            let is_socket = std::fs::symlink_metadata(path)
+                .is_ok_and(|metadata| metadata.file_type().is_socket());
+            if !is_socket || UnixStream::connect(path).is_ok() {
+                return Err(e);
+            }
+            log(LogLevel::Warning, &format!("Removendo socket antigo em {}", path));
+            std::fs::remove_file(path)?;
+            UnixListener::bind(path)
+        }
+        result => result,
+    }
 }
 
 struct TelemetryServer {
-    listener: TcpListener,
-    address: String,
+    listener: Listener,
+    state: Arc<ServerState>,
+    /// Threads de clientes ainda em execução
+    workers: Vec<JoinHandle<()>>,
+    rate_limiter: ConnectionRateLimiter,
+    /// Último número dado a um cliente Unix, base da chave sintética
+    unix_clients: u16,
 }
 
 impl TelemetryServer {
-    fn new(address: &str) -> TelemetryResult<Self> {
-        let listener = TcpListener::bind(address)?;
+    #[cfg(test)]
+    fn with_output(config: ServerConfig, output: Arc<dyn OutputSink>) -> TelemetryResult<Self> {
+        let listener = Self::bind(&config)?;
+        Self::with_listener(config, listener, output)
+    }
+
+    /// Valida a combinação de transporte e endereço e abre o socket de escuta, sem
+    /// criar threads: no modo daemon roda antes do fork
+    fn bind(config: &ServerConfig) -> TelemetryResult<Listener> {
+        if config.transport == TransportKind::Udp && (config.tls_cert.is_some() || auth::token_from_env().is_some()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "o transporte UDP não suporta TLS nem autenticação por token",
+            ));
+        }
+
+        let unix_path = config.address.strip_prefix(UNIX_ADDRESS_PREFIX);
+        if unix_path.is_some() && config.transport == TransportKind::Udp {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "o transporte UDP não suporta endereços unix:"));
+        }
+        #[cfg(not(unix))]
+        if unix_path.is_some() {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "Unix domain sockets não são suportados nesta plataforma"));
+        }
+
+        Ok(match config.transport {
+            // O replay não abre socket: as amostras vêm do arquivo
+            _ if config.replay.is_some() => Listener::Replay,
+            #[cfg(unix)]
+            TransportKind::Tcp if unix_path.is_some() => {
+                let listener = bind_unix(unix_path.unwrap_or_default()).map_err(|e| bind_error(&config.address, e))?;
+                listener.set_nonblocking(true)?;
+                Listener::Unix(listener)
+            }
+            TransportKind::Tcp => {
+                let listener = TcpListener::bind(&config.address).map_err(|e| bind_error(&config.address, e))?;
+                // Não bloqueante para o loop de accept conseguir observar o pedido de encerramento
+                listener.set_nonblocking(true)?;
+                Listener::Tcp(listener)
+            }
+            TransportKind::Udp => {
+                let socket = UdpSocket::bind(&config.address).map_err(|e| bind_error(&config.address, e))?;
+                // O timeout devolve o controle ao loop para checar encerramento e inatividade
+                socket.set_read_timeout(Some(ACCEPT_POLL_INTERVAL))?;
+                Listener::Udp(socket)
+            }
+        })
+    }
+
+    /// Monta o estado e sobe as threads de exportação sobre o listener já aberto
+    fn with_listener(config: ServerConfig, listener: Listener, output: Arc<dyn OutputSink>) -> TelemetryResult<Self> {
+        let auth_token = auth::token_from_env();
+        let csv = match &config.csv_path {
+            Some(path) => {
+                let writer = CsvWriter::open(Path::new(path))?;
+                log(LogLevel::Info, &format!("Gravando telemetria em {}", path));
+                Some(Mutex::new(writer))
+            }
+            None => None,
+        };
+
+        let samples: SharedSamples = Arc::new(Mutex::new(HashMap::new()));
+        if let Some(metrics_address) = &config.metrics_address {
+            prometheus::spawn_exporter(metrics_address, Arc::clone(&samples))?;
+        }
         
+        let recorder = match &config.record_path {
+            Some(path) => {
+                let recorder = Recorder::open(Path::new(path))?;
+                log(LogLevel::Info, &format!("Gravando sessão em {}", path));
+                Some(recorder)
+            }
+            None => None,
+        };
+
+        let jsonl = config.jsonl_dir.as_deref().map(|dir| DailyJsonl::spawn(Path::new(dir))).transpose()?;
+        let sqlite = config.sqlite.clone().map(SqliteStore::spawn).transpose()?;
+        let influx = config.influx.clone().map(InfluxExporter::spawn).transpose()?;
+        let statsd = config.statsd.clone().map(StatsdExporter::connect).transpose()?;
+
+        let web = match &config.web_address {
+            Some(web_address) => {
+                let hub = Arc::new(WebHub::default());
+                web::spawn_dashboard(web_address, Arc::clone(&hub))?;
+                Some(hub)
+            }
+            None => None,
+        };
+
+        let rate_limiter = ConnectionRateLimiter::new(config.max_connections_per_ip_per_minute);
+        let alert_rules: Arc<[AlertRule]> = config.alerts.clone().into();
+
+        let tls = match (&config.tls_cert, &config.tls_key) {
+            (Some(cert), Some(key)) => Some(
+                transport::load_tls_config(Path::new(cert), Path::new(key))
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("certificado ou chave TLS: {}", e)))?,
+            ),
+            _ => None,
+        };
+
         Ok(Self {
             listener,
-            address: address.to_string(),
+            state: Arc::new(ServerState {
+                screen: Mutex::new(BTreeMap::new()),
+                csv,
+                influx,
+                statsd,
+                recorder,
+                jsonl,
+                sqlite,
+                samples,
+                alert_rules,
+                ranges: RangeTable::new(&config.ranges),
+                severity: SeverityTable::new(&config.severity),
+                formatters: FormatterTable::with_units(&config.units),
+                display_filter: MetricFilter::new(&config.show),
+                categories: CategoryTable::new(&config.categories),
+                histories: Mutex::new(HashMap::new()),
+                tls,
+                auth_token,
+                display: DisplayMode::for_config(&config),
+                output,
+                web,
+                clients: Mutex::new(BTreeMap::new()),
+                connections: Mutex::new(HashMap::new()),
+                shutdown: AtomicBool::new(false),
+                started_at: Instant::now(),
+                samples_processed: AtomicU64::new(0),
+                config,
+            }),
+            workers: Vec::new(),
+            rate_limiter,
+            unix_clients: 0,
         })
     }
 
-    fn run(&self) -> TelemetryResult<()> {
+    /// Sobe o que depende do estado pronto (Ctrl+C, console, healthcheck e
+    /// discovery); erros aqui ainda chegam ao terminal que iniciou o daemon
+    fn start(&self) -> TelemetryResult<()> {
+        let state = Arc::clone(&self.state);
+        ctrlc::set_handler(move || state.request_shutdown())
+            .map_err(io::Error::other)?;
+
         self.print_startup_message();
-        
-        loop {
+        // O daemon não tem stdin: o console de comandos só existe em primeiro plano
+        if !self.state.config.daemon {
+            let registry = CommandRegistry::builtin(&self.state);
+            commands::spawn_console(Arc::clone(&self.state), registry)?;
+        }
+        if let Some(health_address) = &self.state.config.health_address {
+            health::spawn_health(health_address, Arc::clone(&self.state))?;
+        }
+        if let Some(listening) = self.listener.local_addr().filter(|_| self.state.config.discovery) {
+            discovery::spawn_responder(listening, Arc::clone(&self.state));
+        }
+        Ok(())
+    }
+
+    fn run(mut self) -> TelemetryResult<()> {
+        match &self.listener {
+            Listener::Udp(socket) => udp::serve(socket, &self.state),
+            Listener::Tcp(_) => self.accept_loop(),
+            #[cfg(unix)]
+            Listener::Unix(_) => self.accept_loop(),
+            Listener::Replay => {
+                if let Some(replay) = &self.state.config.replay {
+                    if let Err(e) = replay::play(Path::new(&replay.path), replay.speed, &self.state) {
+                        log(LogLevel::Error, &format!("Erro no replay de {}: {}", replay.path, e));
+                    }
+                }
+            }
+        }
+
+        self.shutdown();
+        Ok(())
+    }
+
+    fn accept_loop(&mut self) {
+        while !self.state.is_shutting_down() {
             match self.accept_connection() {
-                Ok(_) => {
+                Ok(true) => {
                     log(LogLevel::Info, "Aguardando nova conexão...");
                 }
+                Ok(false) => thread::sleep(ACCEPT_POLL_INTERVAL),
                 Err(e) => {
                     log(LogLevel::Error, &format!("Erro ao aceitar conexão: {}", e));
                     // Continua executando mesmo com erro
                 }
             }
+            self.workers.retain(|worker| !worker.is_finished());
+        }
+    }
+
+    /// Fecha o listener, derruba as conexões abertas e espera as threads de clientes
+    /// (com timeout) para que CSV e históricos terminem de ser gravados
+    fn shutdown(self) {
+        let Self { listener, state, workers, .. } = self;
+        #[cfg(unix)]
+        if let Listener::Unix(listener) = &listener {
+            if let Some(path) = listener.local_addr().ok().and_then(|addr| addr.as_pathname().map(Path::to_path_buf)) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        drop(listener);
+
+        log(LogLevel::Info, &format!("Encerrando servidor: fechando {} conexão(ões)...", workers.len()));
+        for handle in state.connections.lock().unwrap_or_else(|e| e.into_inner()).values() {
+            let _ = handle.socket.shutdown(Shutdown::Both);
+        }
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while workers.iter().any(|worker| !worker.is_finished()) && Instant::now() < deadline {
+            thread::sleep(ACCEPT_POLL_INTERVAL);
+        }
+
+        let pending = workers.iter().filter(|worker| !worker.is_finished()).count();
+        if pending > 0 {
+            log(LogLevel::Warning, &format!("{} thread(s) de cliente não terminaram a tempo", pending));
+        }
+        if let Some(sqlite) = &state.sqlite {
+            sqlite.shutdown();
         }
+
+        log(LogLevel::Success, "Servidor encerrado");
+    }
+
+    /// Endereço real após o bind: com a porta `0`, a que o sistema escolheu
+    fn listening_address(&self) -> String {
+        self.listener.local_addr().map_or_else(|| self.state.config.address.clone(), |address| address.to_string())
     }
 
     fn print_startup_message(&self) {
+        let address = self.listening_address();
+        // O stdout do modo JSON é só de amostras: o aviso de início vai para o log (stderr)
+        if self.state.display == DisplayMode::Json {
+            log(LogLevel::Info, &format!("Servidor ouvindo em {} (saída JSON)", address));
+            return;
+        }
+
         println!("🚀 Servidor de Telemetria iniciado");
-        println!("📡 Ouvindo em: {}", self.address);
-        println!("⏹️  Pressione Ctrl+C para parar o servidor");
+        match self.listener {
+            Listener::Tcp(_) => println!("📡 Ouvindo em: {}", address),
+            Listener::Udp(_) => println!("📡 Ouvindo em: {} (UDP)", address),
+            #[cfg(unix)]
+            Listener::Unix(_) => println!("📡 Ouvindo em: {} (Unix domain socket)", address),
+            Listener::Replay => {
+                if let Some(replay) = &self.state.config.replay {
+                    println!("⏯️  Reproduzindo {} ({})", replay.path, replay.speed);
+                }
+            }
+        }
+        if self.state.tls.is_some() {
+            println!("🔒 TLS habilitado");
+        }
+        if self.state.auth_token.is_some() {
+            println!("🔑 Autenticação por token habilitada");
+        }
+        println!("⏹️  Pressione Ctrl+C (ou digite Q) para parar o servidor; L lista os clientes, H os comandos");
         println!("{}", "=".repeat(50));
     }
 
-    fn accept_connection(&self) -> TelemetryResult<()> {
-        let (socket, addr) = self.listener.accept()?;
+    /// Aceita uma conexão pendente; `Ok(false)` quando não há nenhuma
+    fn accept_connection(&mut self) -> TelemetryResult<bool> {
+        let Some((socket, addr)) = self.listener.accept(&mut self.unix_clients)? else {
+            return Ok(false);
+        };
+        if let Some(reason) = self.rejection_reason(addr) {
+            log(LogLevel::Warning, &format!("Conexão de {} recusada: {}", addr, reason));
+            reject(socket, self.state.tls.is_some(), reason);
+            return Ok(false);
+        }
         log(LogLevel::Success, &format!("Cliente conectado: {}", addr));
+
+        // Em algumas plataformas o socket herda o modo não bloqueante do listener
+        socket.set_nonblocking(false)?;
         
-        let mut connection = ClientConnection::new(socket, addr);
-        match connection.handle_client() {
-            Ok(_) => {
-                log(LogLevel::Info, "Conexão processada com sucesso");
-                Ok(())
-            }
-            Err(e) => {
-                log(LogLevel::Warning, &format!("Erro durante conexão com {}: {}", addr, e));
-                Ok(()) // Não propaga erro para manter servidor rodando
-            }
+        // Sem o timeout, um cliente travado sem fechar o socket bloquearia a leitura para sempre
+        socket.set_read_timeout(self.state.config.read_timeout())?;
+
+        let handle = ConnectionHandle {
+            socket: socket.try_clone()?,
+            outbox: Vec::new(),
+        };
+
+        // Cada cliente roda em sua própria thread para não bloquear o accept
+        let stream = ClientStream::accept(socket, self.state.tls.as_ref())?;
+        self.state.connections.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(addr, handle);
+
+        let mut connection = ClientConnection::new(stream, addr, Arc::clone(&self.state));
+        let worker = thread::Builder::new()
+            .name(format!("client-{}", addr))
+            .spawn(move || {
+                match connection.handle_client() {
+                    Ok(_) => log(LogLevel::Info, &format!("Conexão com {} encerrada", addr)),
+                    // Não propaga erro para manter servidor rodando
+                    Err(e) => log(LogLevel::Warning, &format!("Erro durante conexão com {}: {}", addr, e)),
+                }
+                connection.cleanup();
+            })?;
+        self.workers.push(worker);
+
+        Ok(true)
+    }
+
+    /// Aplica o limite de conexões simultâneas e o rate-limit por IP
+    fn rejection_reason(&mut self, addr: SocketAddr) -> Option<&'static str> {
+        let max = self.state.config.max_connections;
+        let active = self.state.connections.lock().unwrap_or_else(|e| e.into_inner()).len();
+        if max > 0 && active >= max {
+            return Some("limite de conexões simultâneas atingido");
         }
+
+        // Clientes Unix são locais e não têm IP: o rate-limit não se aplica
+        if !addr.ip().is_unspecified() && !self.rate_limiter.allow(addr.ip(), Instant::now()) {
+            return Some("conexões demais deste IP no último minuto");
+        }
+        None
+    }
+}
+
+/// Fecha a conexão recusada. Sem TLS, o motivo segue antes numa linha JSON
+/// (com TLS ainda não há sessão para escrever)
+fn reject(mut socket: Socket, tls: bool, reason: &str) {
+    if !tls {
+        let _ = socket.set_write_timeout(Some(ACCEPT_POLL_INTERVAL));
+        let message = rejection_json(reason);
+        let _ = socket.write_all(format!("{}\n", message).as_bytes());
     }
+    let _ = socket.shutdown(Shutdown::Both);
 }
 
 struct ClientConnection {
-    reader: BufReader<TcpStream>,
-    addr: SocketAddr,
+    reader: MessageReader<ClientStream>,
     line_buffer: String,
+    /// Frames compactados com gzip, anunciados pelo byte de modo
+    compressed: bool,
+    /// Timeouts de leitura seguidos sem nenhum dado recebido
+    consecutive_timeouts: u32,
+    session: ClientSession,
 }
 
 impl ClientConnection {
-    fn new(socket: TcpStream, addr: SocketAddr) -> Self {
+    fn new(stream: ClientStream, addr: SocketAddr, state: Arc<ServerState>) -> Self {
         Self {
-            reader: BufReader::with_capacity(BUFFER_SIZE, socket),
-            addr,
+            reader: MessageReader::with_capacity(state.config.buffer_size, stream)
+                .with_max_line(state.config.max_line_size),
             line_buffer: String::with_capacity(512),
+            compressed: false,
+            consecutive_timeouts: 0,
+            session: ClientSession::new(addr, state),
         }
     }
 
     fn handle_client(&mut self) -> TelemetryResult<()> {
+        // A primeira mensagem pode ser o handshake ou já uma amostra (cliente antigo)
+        match self.read_handshake() {
+            Ok(true) => self.session.process_message(&self.line_buffer),
+            Ok(false) => {}
+            Err(e) if matches!(e.kind(), io::ErrorKind::PermissionDenied | io::ErrorKind::Unsupported) => {
+                // Encerrar a função derruba o socket antes de qualquer métrica ser processada
+                log(LogLevel::Warning, &format!("Conexão de {} rejeitada: {}", self.session.addr, e));
+                return Ok(());
+            }
+            Err(e) => {
+                log(LogLevel::Error, &format!("Erro ao ler dados do cliente {}: {}", self.session.addr, e));
+                return Ok(());
+            }
+        }
+
         loop {
-            match self.read_telemetry_data() {
-                Ok(Some(metrics)) => {
-                    self.display_telemetry(&metrics);
+            match self.read_message() {
+                Ok(true) => {
+                    if let Err(e) = self.handle_message() {
+                        log(LogLevel::Error, &format!("Erro ao responder ao cliente {}: {}", self.session.addr, e));
+                        break;
+                    }
                 }
-                Ok(None) => {
-                    log(LogLevel::Info, &format!("Cliente {} desconectou", self.addr));
+                Ok(false) => {
+                    log(LogLevel::Info, &format!("Cliente {} desconectou", self.session.addr));
                     break;
                 }
                 Err(e) => {
-                    log(LogLevel::Error, &format!("Erro ao ler dados do cliente {}: {}", self.addr, e));
+                    log(LogLevel::Error, &format!("Erro ao ler dados do cliente {}: {}", self.session.addr, e));
                     break;
                 }
             }
@@ -136,90 +929,559 @@ impl ClientConnection {
         Ok(())
     }
 
-    fn read_telemetry_data(&mut self) -> TelemetryResult<Option<HashMap<String, f32>>> {
+    /// Lê a primeira mensagem. Se for um handshake, adota a identidade declarada
+    /// e retoma o histórico do mesmo ID; caso contrário o cliente segue anônimo e
+    /// `Ok(true)` indica que a mensagem em `line_buffer` deve ser processada como amostra.
+    /// Um handshake com `"batch": true` recebe o `accept_batch` de volta, com o
+    /// tamanho máximo de mensagem, um com `"ping": nonce`, o `pong` que habilita
+    /// a medição de RTT, e um com `"clock"`, o relógio do servidor e o offset estimado.
+    /// Com autenticação habilitada, falta de handshake ou token incorreto geram
+    /// `PermissionDenied`. Uma `protocol_version` fora da faixa suportada é
+    /// recusada com o motivo numa linha `{"error": ...}` e gera `Unsupported`.
+    fn read_handshake(&mut self) -> TelemetryResult<bool> {
+        self.read_mode_byte()?;
+        if !self.read_message()? {
+            return Ok(false);
+        }
+
+        let message = self.line_buffer.trim();
+        let handshake = serde_json::from_str::<HandshakeMessage>(message)
+            .ok()
+            .map(|message| message.handshake);
+        protocol_debug::parsed(self.session.addr, || match &handshake {
+            Some(handshake) => Ok(format!("handshake de {}", handshake.identity.display_name())),
+            None => Ok("não é handshake, segue como amostra".to_string()),
+        });
+
+        if let Some(expected) = &self.session.state.auth_token {
+            let received = handshake.as_ref().and_then(|h| h.auth_token.as_deref());
+            if !auth::verify_token(expected, received) {
+                let reason = if received.is_some() { "token inválido" } else { "token ausente" };
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, reason));
+            }
+        }
+
+        if let Some(Handshake { identity, batch, ping, clock, protocol_version, .. }) = handshake {
+            if let Err(reason) = self.session.negotiate_protocol(protocol_version) {
+                // Melhor esforço: o cliente registra o motivo antes de ver a conexão fechar
+                let _ = self.reply(&rejection_json(&reason));
+                return Err(io::Error::new(io::ErrorKind::Unsupported, reason));
+            }
+            self.session.adopt_identity(identity);
+            if let Some(clock) = clock {
+                let server_time = epoch_millis();
+                let offset = self.session.adopt_clock(clock, server_time);
+                self.reply(&ServerCommand::Clock { server_time, offset_ms: offset.millis() }.to_json())?;
+            }
+            if batch && protocol::supports_batch(self.session.protocol_version()) {
+                self.reply(&ServerCommand::AcceptBatch { max_bytes: Some(self.max_message_size()) }.to_json())?;
+                log(LogLevel::Info, &format!("Cliente {} envia amostras em batch", self.session.addr));
+            } else if batch {
+                // Sem `accept_batch`, o cliente segue com uma amostra por mensagem
+                log(LogLevel::Warning, &format!(
+                    "Cliente {} pediu batch em protocolo v{}: recusado, exige v2", self.session.addr, self.session.protocol_version()
+                ));
+            }
+            if let Some(nonce) = ping {
+                self.reply(&ServerCommand::Pong { nonce }.to_json())?;
+            }
+            return Ok(false);
+        }
+
+        log(LogLevel::Info, &format!("Cliente {} sem handshake, tratado como anônimo", self.session.addr));
+        Ok(true)
+    }
+
+    /// Pings são respondidos na hora com o mesmo nonce; as demais mensagens são amostras
+    fn handle_message(&mut self) -> TelemetryResult<()> {
+        if let Some(ping) = Ping::parse(&self.line_buffer) {
+            protocol_debug::parsed(self.session.addr, || Ok(format!("ping {} (rtt {:?} ms)", ping.ping, ping.rtt_ms)));
+            self.session.record_rtt(ping.rtt_ms);
+            return self.reply(&ServerCommand::Pong { nonce: ping.ping }.to_json());
+        }
+        self.session.process_message(&self.line_buffer);
+        Ok(())
+    }
+
+    /// Responde na própria conexão, a partir da thread que a lê
+    fn reply(&mut self, message: &str) -> TelemetryResult<()> {
+        let stream = self.reader.get_mut();
+        stream.write_all(format!("{}\n", message).as_bytes())?;
+        stream.flush()
+    }
+
+    /// Byte de modo opcional antes do handshake. Clientes sem compressão começam
+    /// direto pela mensagem, cujo primeiro byte nunca é `0x01` (nem `{` nem o
+    /// byte alto de um comprimento dentro de `MAX_FRAME_SIZE`)
+    fn read_mode_byte(&mut self) -> TelemetryResult<()> {
+        let first = loop {
+            match self.reader.fill_buf() {
+                Ok(buf) => break buf.first().copied(),
+                Err(e) if is_timeout(&e) => self.register_timeout()?,
+                Err(e) => return Err(e),
+            }
+        };
+        if first != Some(compression::MODE_GZIP) {
+            return Ok(());
+        }
+        self.reader.consume(1);
+
+        if self.session.state.config.framing != Framing::Length {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "compressão gzip exige framing = \"length\" no servidor",
+            ));
+        }
+
+        self.compressed = true;
+        log(LogLevel::Info, &format!("Cliente {} usa compressão gzip", self.session.addr));
+        Ok(())
+    }
+
+    /// Maior mensagem aceita nesta conexão, anunciada no `accept_batch` para o
+    /// cliente dividir batches grandes: `max_line_size` por linha, `MAX_FRAME_SIZE` por frame
+    fn max_message_size(&self) -> usize {
+        match self.session.state.config.framing {
+            Framing::Line => self.session.state.config.max_line_size,
+            Framing::Length => MAX_FRAME_SIZE,
+        }
+    }
+
+    /// Lê a próxima mensagem não vazia para `line_buffer`; `Ok(false)` indica EOF
+    fn read_message(&mut self) -> TelemetryResult<bool> {
         // Limpa o buffer para reutilização
         self.line_buffer.clear();
-        
-        // Lê até encontrar newline (ou EOF)
-        match self.reader.read_line(&mut self.line_buffer) {
-            Ok(0) => Ok(None), // Cliente desconectou
-            Ok(_) => {
-                // Remove whitespace das extremidades
-                let trimmed = self.line_buffer.trim();
-                
-                if trimmed.is_empty() {
-                    // Linha vazia, continua lendo
-                    return self.read_telemetry_data();
-                }
-                
-                // Parse direto da string, sem clonar
-                match serde_json::from_str::<HashMap<String, f32>>(trimmed) {
-                    Ok(metrics) => Ok(Some(metrics)),
-                    Err(e) => {
-                        log(LogLevel::Warning, &format!("Erro ao processar JSON: {}", e));
-                        log(LogLevel::Info, &format!("Dados recebidos: {}", trimmed));
-                        // Continua tentando ler próxima linha ao invés de desconectar
-                        self.read_telemetry_data()
-                    }
+
+        loop {
+            self.flush_outbox()?;
+
+            let received = match self.session.state.config.framing {
+                Framing::Line => self.read_line_message(),
+                Framing::Length => self.read_framed(),
+            };
+
+            if let Err(e) = &received {
+                if is_timeout(e) {
+                    // Uma linha parcial continua no buffer do leitor e é completada na próxima leitura
+                    self.register_timeout()?;
+                    continue;
                 }
             }
-            Err(e) => {
-                if e.kind() == io::ErrorKind::InvalidData {
-                    log(LogLevel::Warning, &format!("Dados UTF-8 inválidos: {}", e));
+            self.consecutive_timeouts = 0;
+
+            match received {
+                Ok(false) => return Ok(false),
+                // Mensagem vazia (heartbeat), continua lendo
+                Ok(true) if self.line_buffer.trim().is_empty() => self.line_buffer.clear(),
+                Ok(true) => return Ok(true),
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    log(LogLevel::Warning, &format!("Dados inválidos de {}: {}", self.session.addr, e));
                     // Continua tentando ler ao invés de desconectar
-                    self.read_telemetry_data()
-                } else {
-                    Err(e)
+                    self.line_buffer.clear();
                 }
+                Err(e) => return Err(e),
             }
         }
     }
 
-    fn display_telemetry(&self, metrics: &HashMap<String, f32>) {
-        self.clear_screen();
-        
-        println!("📊 TELEMETRIA EM TEMPO REAL");
-        println!("🔗 Cliente: {}", self.addr);
-        println!("{}", "=".repeat(50));
-        
-        if metrics.is_empty() {
-            println!("⚠️  Nenhuma métrica recebida");
-        } else {
-            for (metric_name, value) in metrics {
-                self.format_and_print_metric(metric_name, *value);
-            }
+    /// Conta um timeout de leitura; ao atingir `max_timeouts` seguidos o cliente
+    /// é considerado morto e a conexão é encerrada com `TimedOut`
+    fn register_timeout(&mut self) -> TelemetryResult<()> {
+        self.consecutive_timeouts += 1;
+        let max = self.session.state.config.max_timeouts;
+        let silent_secs = self.session.state.config.read_timeout_secs * u64::from(self.consecutive_timeouts);
+
+        if self.consecutive_timeouts >= max {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("cliente possivelmente morto, sem dados há {}s", silent_secs),
+            ));
         }
-        
-        println!("{}", "=".repeat(50));
-        println!("⏹️  Pressione Ctrl+C para sair");
-    }
-
-    fn format_and_print_metric(&self, name: &str, value: f32) {
-        match name.to_uppercase().as_str() {
-            "CPU" => println!("🖥️  CPU: {:.1}%", value),
-            "MEM" | "MEMORY" => {
-                // sysinfo retorna memória em KB (kilobytes)
-                // Conversão correta: KB -> MB -> GB
-                let kb = value;
-                if kb >= 1_048_576.0 {
-                    // >= 1024 MB (1 GB)
-                    println!("💾 Memória: {:.2} GB", kb / 1_048_576.0);
-                } else if kb >= 1_024.0 {
-                    // >= 1 MB
-                    println!("💾 Memória: {:.2} MB", kb / 1_024.0);
-                } else {
-                    println!("💾 Memória: {:.2} KB", kb);
-                }
-            }
-            "DISK" | "STORAGE" => println!("💿 Disco: {:.1}%", value),
-            "NETWORK" | "NET" => println!("🌐 Rede: {:.2} MB/s", value),
-            "TEMPERATURE" | "TEMP" => println!("🌡️  Temperatura: {:.1}°C", value),
-            _ => println!("📈 {}: {:.2}", name, value),
+
+        log(LogLevel::Warning, &format!(
+            "Cliente {} sem dados há {}s (timeout {}/{})",
+            self.session.addr, silent_secs, self.consecutive_timeouts, max
+        ));
+        Ok(())
+    }
+
+    /// Lê até encontrar newline; `Ok(false)` indica EOF
+    fn read_line_message(&mut self) -> TelemetryResult<bool> {
+        let received = self.reader.read_line(&mut self.line_buffer);
+        let raw = self.reader.last_line();
+        if !raw.is_empty() {
+            protocol_debug::received(self.session.addr, "linha", raw);
+        }
+        received
+    }
+
+    /// Lê um frame `[u32 BE comprimento][payload]`; `Ok(false)` indica EOF
+    fn read_framed(&mut self) -> TelemetryResult<bool> {
+        let Some(payload) = framing::read_frame(&mut self.reader, MAX_FRAME_SIZE)? else {
+            return Ok(false);
+        };
+
+        if self.compressed {
+            let text = compression::decompress(&payload, MAX_FRAME_SIZE)?;
+            let kind = format!("frame gzip ({} bytes compactados)", payload.len());
+            protocol_debug::received(self.session.addr, &kind, text.as_bytes());
+            self.line_buffer.push_str(&text);
+            return Ok(true);
         }
+
+        protocol_debug::received(self.session.addr, "frame", &payload);
+        let text = String::from_utf8(payload)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.line_buffer.push_str(&text);
+        Ok(true)
     }
 
-    fn clear_screen(&self) {
-        print!("\x1B[2J\x1B[1;1H");
+    /// Envia as mensagens enfileiradas para este cliente (caminho dos clientes TLS)
+    fn flush_outbox(&mut self) -> TelemetryResult<()> {
+        let pending = match self.session.state.connections.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(&self.session.addr)
+        {
+            Some(handle) if !handle.outbox.is_empty() => std::mem::take(&mut handle.outbox),
+            _ => return Ok(()),
+        };
+
+        let stream = self.reader.get_mut();
+        for message in pending {
+            stream.write_all(message.as_bytes())?;
+        }
+        stream.flush()
+    }
+
+    /// Libera o socket do registro de conexões e encerra a sessão
+    fn cleanup(&mut self) {
+        self.session.state.connections.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.session.addr);
+        self.session.cleanup();
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, net::TcpStream};
+
+    use super::*;
+    use output::MemorySink;
+
+    /// Porta efêmera, saída JSON e sem discovery
+    fn test_config() -> ServerConfig {
+        ServerConfig {
+            address: "127.0.0.1:0".to_string(),
+            output: OutputMode::Json,
+            discovery: false,
+            ..ServerConfig::default()
+        }
+    }
+
+    /// Sobe o servidor numa thread, com a saída capturada. O teste encerra com
+    /// `state.request_shutdown()` e o `join` do handle
+    fn spawn_test_server(config: ServerConfig) -> (SocketAddr, Arc<ServerState>, Arc<MemorySink>, thread::JoinHandle<()>) {
+        let sink = Arc::new(MemorySink::default());
+        let mut server = TelemetryServer::with_output(config, sink.clone()).unwrap();
+        let address = server.listener.local_addr().unwrap();
+        let state = Arc::clone(&server.state);
+        let serving = thread::spawn(move || {
+            server.accept_loop();
+            server.shutdown();
+        });
+        (address, state, sink, serving)
+    }
+
+    /// Espera até `count` linhas na saída, por no máximo 5s
+    fn wait_for_lines(sink: &MemorySink, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sink.lines().len() < count && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bind_unix_only_replaces_stale_sockets() {
+        let path = std::env::temp_dir().join(format!("telemetry-bind-{}.sock", std::process::id()));
+        let path_str = path.to_str().unwrap();
+
+        // Arquivo comum no caminho: nada é apagado e o AddrInUse original sobe
+        std::fs::write(&path, "não é um socket").unwrap();
+        let error = bind_unix(path_str).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "não é um socket");
+        std::fs::remove_file(&path).unwrap();
+
+        // Socket abandonado por uma execução anterior: removido e reaberto
+        drop(UnixListener::bind(&path).unwrap());
+        let listener = bind_unix(path_str).unwrap();
+
+        // Socket ativo: fica com quem já o usa
+        assert_eq!(bind_unix(path_str).unwrap_err().kind(), io::ErrorKind::AddrInUse);
+        drop(listener);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Contadores inteiros chegam exatos às exportações; floats sem ruído do `f32`
+    #[test]
+    fn exported_values_keep_integer_precision() {
+        let sample: TelemetrySample =
+            serde_json::from_str(r#"{"CPU": 0.1, "RX_BYTES": 123456789013, "ON_AC": true, "STATE": "idle"}"#).unwrap();
+        let exported = TelemetryPacket::from(sample).exported();
+        assert_eq!(exported["CPU"], 0.1);
+        assert_eq!(exported["RX_BYTES"], 123_456_789_013.0);
+        assert_eq!(exported["ON_AC"], 1.0);
+        assert_eq!(exported.len(), 3);
+    }
+
+    #[test]
+    fn announces_the_port_chosen_for_port_zero() {
+        let config = ServerConfig { address: "127.0.0.1:0".to_string(), discovery: false, ..ServerConfig::default() };
+        let server = TelemetryServer::with_output(config, Arc::new(MemorySink::default())).unwrap();
+
+        let announced: SocketAddr = server.listening_address().parse().unwrap();
+        assert_ne!(announced.port(), 0);
+        assert_eq!(Some(announced), server.listener.local_addr());
+    }
+
+    /// Sobe o servidor numa porta efêmera, fala o protocolo como o cliente real
+    /// (handshake + amostras `TelemetrySample`) e confere a saída JSON emitida
+    #[test]
+    fn processes_samples_from_a_real_connection() {
+        let (address, state, sink, serving) = spawn_test_server(test_config());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let handshake = serde_json::json!({ "handshake": { "client_id": "e2e", "hostname": "teste" } });
+        let mut data = format!("{}\n", handshake);
+        for i in 0..3 {
+            let mut sample = TelemetrySample::new(1_700_000_000_000 + i);
+            sample.cpu = Some(10.0 * (i + 1) as f32);
+            sample.mem = Some(1024.0);
+            data.push_str(&serde_json::to_string(&sample).unwrap());
+            data.push('\n');
+        }
+        stream.write_all(data.as_bytes()).unwrap();
+
+        wait_for_lines(&sink, 3);
+        drop(stream);
+        state.request_shutdown();
+        serving.join().unwrap();
+
+        let records: Vec<serde_json::Value> = sink.lines().iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len(), 3);
+        for (i, record) in records.iter().enumerate() {
+            assert_eq!(record["client"], "e2e");
+            assert_eq!(record["hostname"], "teste");
+            assert_eq!(record["timestamp"], 1_700_000_000_000 + i as u64);
+            assert_eq!(record["metrics"]["CPU"], 10.0 * (i + 1) as f64);
+            assert_eq!(record["metrics"]["MEM"], 1024.0);
+        }
+        assert_eq!(state.samples_processed.load(Ordering::Relaxed), 3);
+    }
+
+    /// Dois clientes conectados ao mesmo tempo são atendidos em paralelo, cada
+    /// um com a própria sessão
+    #[test]
+    fn serves_two_concurrent_clients() {
+        let (address, state, sink, serving) = spawn_test_server(test_config());
+
+        let mut streams: Vec<TcpStream> = ["a", "b"].iter().map(|id| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let handshake = serde_json::json!({ "handshake": { "client_id": id } });
+            stream.write_all(format!("{}\n", handshake).as_bytes()).unwrap();
+            stream
+        }).collect();
+        // Ambas as conexões abertas antes de qualquer amostra
+        for (i, stream) in streams.iter_mut().enumerate() {
+            let mut sample = TelemetrySample::new(1_700_000_000_000);
+            sample.cpu = Some(i as f32);
+            stream.write_all(format!("{}\n", serde_json::to_string(&sample).unwrap()).as_bytes()).unwrap();
+        }
+
+        wait_for_lines(&sink, 2);
+        drop(streams);
+        state.request_shutdown();
+        serving.join().unwrap();
+
+        let mut clients: Vec<String> = sink.lines().iter()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["client"].as_str().unwrap().to_string())
+            .collect();
+        clients.sort();
+        assert_eq!(clients, ["a", "b"]);
+    }
+
+    /// Uma enxurrada de linhas em branco é pulada no laço de leitura, sem
+    /// recursão, e a amostra seguinte ainda é processada
+    #[test]
+    fn skips_a_flood_of_blank_lines() {
+        let (address, state, sink, serving) = spawn_test_server(test_config());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut data = "\n".repeat(100_000);
+        data.push_str("{\"CPU\": 5.0}\n");
+        stream.write_all(data.as_bytes()).unwrap();
+
+        wait_for_lines(&sink, 1);
+        drop(stream);
+        state.request_shutdown();
+        serving.join().unwrap();
+
+        assert_eq!(sink.lines().len(), 1);
+        assert_eq!(state.samples_processed.load(Ordering::Relaxed), 1);
+    }
+
+    /// Cliente com o relógio 10 minutos adiantado: o servidor responde o offset
+    /// estimado e grava as amostras já no próprio relógio
+    #[test]
+    fn corrects_timestamps_of_client_with_skewed_clock() {
+        const SKEW_MS: u64 = 10 * 60 * 1000;
+        let (address, state, sink, serving) = spawn_test_server(test_config());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let sent_at = epoch_millis();
+        let handshake = serde_json::json!({ "handshake": { "client_id": "skew", "clock": sent_at + SKEW_MS } });
+        let mut sample = TelemetrySample::new(sent_at + SKEW_MS);
+        sample.cpu = Some(1.0);
+        let data = format!("{}\n{}\n", handshake, serde_json::to_string(&sample).unwrap());
+        stream.write_all(data.as_bytes()).unwrap();
+
+        let mut reply = String::new();
+        io::BufReader::new(stream.try_clone().unwrap()).read_line(&mut reply).unwrap();
+        wait_for_lines(&sink, 1);
+        drop(stream);
+        state.request_shutdown();
+        serving.join().unwrap();
+
+        let Ok(ServerCommand::Clock { offset_ms, .. }) = serde_json::from_str::<ServerCommand>(&reply) else {
+            panic!("resposta inesperada ao handshake: {}", reply);
+        };
+        assert!((offset_ms - SKEW_MS as i64).abs() < 1_000, "offset estimado: {}", offset_ms);
+
+        let record: serde_json::Value = serde_json::from_str(&sink.lines()[0]).unwrap();
+        let timestamp = record["timestamp"].as_u64().unwrap();
+        assert!(timestamp.abs_diff(sent_at) < 1_000, "timestamp corrigido: {} (envio em {})", timestamp, sent_at);
+    }
+
+    /// O `accept_batch` anuncia o limite de linha, e um array que o ocupa inteiro
+    /// (com o `\n`) é processado amostra por amostra
+    #[test]
+    fn accepts_a_batch_of_the_advertised_size() {
+        let (address, state, sink, serving) = spawn_test_server(test_config());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let handshake = serde_json::json!({
+            "handshake": { "client_id": "batch", "batch": true, "protocol_version": protocol::PROTOCOL_VERSION }
+        });
+        stream.write_all(format!("{}\n", handshake).as_bytes()).unwrap();
+
+        let mut reply = String::new();
+        io::BufReader::new(stream.try_clone().unwrap()).read_line(&mut reply).unwrap();
+        let command: ServerCommand = serde_json::from_str(&reply).unwrap();
+        let ServerCommand::AcceptBatch { max_bytes: Some(max_bytes) } = command else {
+            panic!("resposta: {}", reply);
+        };
+        assert_eq!(max_bytes, test_config().max_line_size);
+
+        // Amostras até quase o limite; espaços completam o array até o byte exato
+        let mut samples = Vec::new();
+        let mut size = 3;
+        loop {
+            let mut sample = TelemetrySample::new(1_700_000_000_000 + samples.len() as u64);
+            sample.cpu = Some(1.0);
+            let json = serde_json::to_string(&sample).unwrap();
+            if size + json.len() + 1 > max_bytes {
+                break;
+            }
+            size += json.len() + usize::from(!samples.is_empty());
+            samples.push(json);
+        }
+        let message = format!("[{}{}]\n", samples.join(","), " ".repeat(max_bytes - size));
+        assert_eq!(message.len(), max_bytes);
+        stream.write_all(message.as_bytes()).unwrap();
+
+        wait_for_lines(&sink, samples.len());
+        drop(stream);
+        state.request_shutdown();
+        serving.join().unwrap();
+
+        assert_eq!(sink.lines().len(), samples.len());
+        assert_eq!(state.samples_processed.load(Ordering::Relaxed), samples.len() as u64);
+    }
+
+    /// Versão de protocolo que o servidor não fala: recusa com o motivo, fecha a
+    /// conexão e não processa a amostra que veio junto
+    #[test]
+    fn rejects_unsupported_protocol_version() {
+        let (address, state, sink, serving) = spawn_test_server(test_config());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let version = protocol::PROTOCOL_VERSION + 1;
+        let handshake = serde_json::json!({ "handshake": { "client_id": "futuro", "protocol_version": version } });
+        let mut sample = TelemetrySample::new(1_700_000_000_000);
+        sample.cpu = Some(1.0);
+        let data = format!("{}\n{}\n", handshake, serde_json::to_string(&sample).unwrap());
+        stream.write_all(data.as_bytes()).unwrap();
+
+        let mut replies = String::new();
+        stream.read_to_string(&mut replies).unwrap();
+        drop(stream);
+        state.request_shutdown();
+        serving.join().unwrap();
+
+        let reply: serde_json::Value = serde_json::from_str(replies.trim()).unwrap();
+        assert!(reply["error"].as_str().unwrap().contains(&version.to_string()), "resposta: {}", replies);
+        assert!(sink.lines().is_empty());
+        assert_eq!(state.samples_processed.load(Ordering::Relaxed), 0);
+    }
+
+    /// Cliente v1 (handshake sem versão): o pedido de batch fica sem `accept_batch`,
+    /// batches são descartados e séries com labels saem da amostra
+    #[test]
+    fn legacy_clients_get_no_v2_features() {
+        let (address, state, sink, serving) = spawn_test_server(test_config());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let handshake = serde_json::json!({ "handshake": { "client_id": "antigo", "batch": true, "ping": 7 } });
+        stream.write_all(format!("{}\n", handshake).as_bytes()).unwrap();
+
+        let mut reply = String::new();
+        io::BufReader::new(stream.try_clone().unwrap()).read_line(&mut reply).unwrap();
+        assert_eq!(serde_json::from_str::<ServerCommand>(&reply).unwrap(), ServerCommand::Pong { nonce: 7 });
+
+        let mut sample = TelemetrySample::new(1_700_000_000_000);
+        sample.cpu = Some(5.0);
+        sample.extra.insert("DISK{mount=\"/\"}".to_string(), Value::Number(40.0));
+        let json = serde_json::to_string(&sample).unwrap();
+        stream.write_all(format!("[{}]\n{}\n", json, json).as_bytes()).unwrap();
+
+        wait_for_lines(&sink, 1);
+        drop(stream);
+        state.request_shutdown();
+        serving.join().unwrap();
+
+        let records: Vec<serde_json::Value> = sink.lines().iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["metrics"], serde_json::json!({ "CPU": 5.0 }));
+        assert_eq!(state.samples_processed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn busy_port_fails_with_bind_exit_code() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = ServerConfig {
+            address: taken.local_addr().unwrap().to_string(),
+            discovery: false,
+            ..ServerConfig::default()
+        };
+        let Err(error) = TelemetryServer::with_output(config, Arc::new(MemorySink::default())) else {
+            panic!("bind numa porta ocupada deveria falhar");
+        };
+        assert_eq!(exit_code(&error), EXIT_BIND);
+        assert!(error.to_string().contains(&taken.local_addr().unwrap().to_string()));
+    }
+}
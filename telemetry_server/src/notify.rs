@@ -0,0 +1,115 @@
+use std::{
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use crate::{apps::find_executable, log, LogLevel};
+
+/// Título e texto chegam ao notificador por variáveis de ambiente, sem passar
+/// por escapes de AppleScript ou PowerShell
+const TITLE_ENV: &str = "TELEMETRY_NOTIFY_TITLE";
+const BODY_ENV: &str = "TELEMETRY_NOTIFY_BODY";
+
+/// Notificadores tentados em ordem. Cada entrada é o executável e os argumentos;
+/// no Linux, título e texto vêm depois, em `platform_text_args`
+#[cfg(target_os = "windows")]
+const NOTIFIERS: &[(&str, &[&str])] = &[(
+    "powershell",
+    &["-NoProfile", "-NonInteractive", "-Command", "\
+        [Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+        $toast = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+        $text = $toast.GetElementsByTagName('text'); \
+        $text.Item(0).AppendChild($toast.CreateTextNode($env:TELEMETRY_NOTIFY_TITLE)) > $null; \
+        $text.Item(1).AppendChild($toast.CreateTextNode($env:TELEMETRY_NOTIFY_BODY)) > $null; \
+        [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('Telemetria').Show([Windows.UI.Notifications.ToastNotification]::new($toast))"],
+)];
+
+#[cfg(target_os = "macos")]
+const NOTIFIERS: &[(&str, &[&str])] = &[(
+    "osascript",
+    &["-e", "display notification (system attribute \"TELEMETRY_NOTIFY_BODY\") with title (system attribute \"TELEMETRY_NOTIFY_TITLE\")"],
+)];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const NOTIFIERS: &[(&str, &[&str])] = &[
+    ("notify-send", &["--app-name=Telemetria"]),
+    ("kdialog", &[]),
+];
+
+/// Avisa uma única vez que não há como notificar, em vez de a cada alerta
+static UNAVAILABLE_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Mostra uma notificação nativa do desktop numa thread própria. Sem notificador
+/// disponível (servidor headless, SSH), avisa uma vez no log e segue sem ela
+pub fn notify(title: &str, body: &str) {
+    let (title, body) = (title.to_string(), body.to_string());
+    let spawned = thread::Builder::new()
+        .name("notify".to_string())
+        .spawn(move || {
+            if let Err(reason) = show(&title, &body) {
+                if !UNAVAILABLE_WARNED.swap(true, Ordering::Relaxed) {
+                    log(LogLevel::Warning, &format!("Notificações de desktop indisponíveis: {}", reason));
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        log(LogLevel::Warning, &format!("Falha ao notificar: {}", e));
+    }
+}
+
+/// Tenta cada notificador até um terminar com sucesso
+fn show(title: &str, body: &str) -> Result<(), String> {
+    let mut last_error = "nenhum notificador encontrado (notify-send, osascript ou PowerShell)".to_string();
+    for (notifier, args) in NOTIFIERS {
+        let Some(program) = find_executable(notifier) else {
+            continue;
+        };
+        let mut command = Command::new(program);
+        command.args(*args)
+            .env(TITLE_ENV, title)
+            .env(BODY_ENV, body)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if cfg!(not(any(target_os = "windows", target_os = "macos"))) {
+            command.args(platform_text_args(notifier, title, body));
+        }
+
+        match command.status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => last_error = format!("'{}' encerrou com {}", notifier, status),
+            Err(e) => last_error = format!("'{}': {}", notifier, e),
+        }
+    }
+    Err(last_error)
+}
+
+/// Título e texto como argumentos: `notify-send -- TÍTULO TEXTO`,
+/// `kdialog --title=TÍTULO --passivepopup=TEXTO -- 10`. O nome do cliente vem do
+/// handshake, então um texto começando com `-` nunca pode virar opção
+fn platform_text_args(notifier: &str, title: &str, body: &str) -> Vec<String> {
+    match notifier {
+        "kdialog" => vec![
+            format!("--title={}", title),
+            format!("--passivepopup={}", body),
+            "--".to_string(),
+            "10".to_string(),
+        ],
+        _ => vec!["--".to_string(), title.to_string(), body.to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_never_becomes_an_option() {
+        let args = platform_text_args("notify-send", "Alerta", "--icon=/tmp/x: CPU = 99%");
+        assert_eq!(args, ["--", "Alerta", "--icon=/tmp/x: CPU = 99%"]);
+
+        let args = platform_text_args("kdialog", "Alerta", "-h: CPU = 99%");
+        assert_eq!(args, ["--title=Alerta", "--passivepopup=-h: CPU = 99%", "--", "10"]);
+    }
+}
@@ -0,0 +1,41 @@
+use std::io::{self, Write};
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// Destino da telemetria exibida (painel, linhas ou JSON). O servidor usa o
+/// stdout; os testes injetam um sink em memória para inspecionar o que saiu
+pub trait OutputSink: Send + Sync {
+    /// Escreve o texto como está, sem acrescentar newline
+    fn write(&self, text: &str);
+}
+
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write(&self, text: &str) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(text.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+/// Acumula toda a saída para os testes
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemorySink {
+    text: Mutex<String>,
+}
+
+#[cfg(test)]
+impl MemorySink {
+    pub fn lines(&self) -> Vec<String> {
+        self.text.lock().unwrap_or_else(|e| e.into_inner()).lines().map(str::to_string).collect()
+    }
+}
+
+#[cfg(test)]
+impl OutputSink for MemorySink {
+    fn write(&self, text: &str) {
+        self.text.lock().unwrap_or_else(|e| e.into_inner()).push_str(text);
+    }
+}
@@ -0,0 +1,92 @@
+/// Mensagens mínimas antes de avaliar a taxa, para um erro isolado no início não disparar o aviso
+const MIN_MESSAGES: u64 = 20;
+
+/// Mensagens de um cliente aceitas e rejeitadas pelo parser
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseStats {
+    pub parsed: u64,
+    pub rejected: u64,
+    /// Taxa acima do limiar já avisada; rearma quando ela volta para baixo
+    warned: bool,
+}
+
+impl ParseStats {
+    /// Conta uma mensagem. Devolve `true` quando a taxa de erro acaba de passar
+    /// de `threshold` (%), uma vez por entrada nesse estado; `0` desativa o aviso
+    pub fn record(&mut self, parsed: bool, threshold: f32) -> bool {
+        if parsed {
+            self.parsed += 1;
+        } else {
+            self.rejected += 1;
+        }
+
+        if threshold <= 0.0 || self.total() < MIN_MESSAGES {
+            return false;
+        }
+        let above = self.error_rate() > threshold;
+        let crossed = above && !self.warned;
+        self.warned = above;
+        crossed
+    }
+
+    pub fn total(&self) -> u64 {
+        self.parsed + self.rejected
+    }
+
+    /// Porcentagem de mensagens rejeitadas
+    pub fn error_rate(&self) -> f32 {
+        match self.total() {
+            0 => 0.0,
+            total => self.rejected as f32 / total as f32 * 100.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use telemetry_common::sample::parse_samples;
+
+    use super::*;
+
+    fn feed(stats: &mut ParseStats, lines: &[&str], threshold: f32) -> usize {
+        lines.iter()
+            .filter(|line| stats.record(parse_samples(line).is_ok(), threshold))
+            .count()
+    }
+
+    #[test]
+    fn counts_valid_and_invalid_lines() {
+        let mut stats = ParseStats::default();
+        let lines = [r#"{"CPU": 1.0}"#, "{CPU: 1}", r#"[{"CPU": 2.0}]"#, "não é json", r#"{"MEM": 3}"#];
+        feed(&mut stats, &lines, 10.0);
+
+        assert_eq!(stats.parsed, 3);
+        assert_eq!(stats.rejected, 2);
+        assert_eq!(stats.error_rate(), 40.0);
+    }
+
+    #[test]
+    fn warns_once_per_crossing() {
+        let mut stats = ParseStats::default();
+        let valid = [r#"{"CPU": 1.0}"#; 18];
+        assert_eq!(feed(&mut stats, &valid, 10.0), 0);
+
+        // 18 válidas + 4 inválidas = 18%: avisa só ao cruzar o limiar
+        assert_eq!(feed(&mut stats, &["lixo"; 4], 10.0), 1);
+        assert!(stats.error_rate() > 10.0);
+
+        // Volta para baixo do limiar e cruza de novo
+        assert_eq!(feed(&mut stats, &[r#"{"CPU": 1.0}"#; 30], 10.0), 0);
+        assert_eq!(feed(&mut stats, &["lixo"; 10], 10.0), 1);
+    }
+
+    #[test]
+    fn ignores_rate_before_minimum_and_when_disabled() {
+        let mut stats = ParseStats::default();
+        assert_eq!(feed(&mut stats, &["lixo"; 5], 10.0), 0);
+
+        let mut disabled = ParseStats::default();
+        assert_eq!(feed(&mut disabled, &["lixo"; 50], 0.0), 0);
+        assert_eq!(disabled.rejected, 50);
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as FmtWrite,
+    io::Result as IoResult,
+    net::TcpListener,
+};
+
+use telemetry_common::{is_temperature_unavailable, labels};
+
+use crate::{
+    http::{self, Response},
+    export_values, log, LogLevel, SharedSamples,
+};
+
+/// Prefixo aplicado a todas as métricas exportadas
+const METRIC_PREFIX: &str = "telemetry_";
+
+/// Labels que o exportador põe em toda série
+const EXPORTER_LABELS: [&str; 3] = ["client", "host", "namespace"];
+
+/// Sobe o endpoint HTTP `GET /metrics` numa thread própria.
+/// O bind acontece antes do spawn para que erros de porta cheguem ao chamador.
+pub fn spawn_exporter(address: &str, samples: SharedSamples) -> IoResult<()> {
+    let listener = TcpListener::bind(address)?;
+    log(LogLevel::Info, &format!("Exportador Prometheus em http://{}/metrics", address));
+
+    http::serve("prometheus", listener, move |request, stream| {
+        Response::route(&request, "/metrics", "text/plain; version=0.0.4", || render_metrics(&samples)).write_to(stream)
+    })
+}
+
+/// Gera o formato de exposição agrupando cada métrica com todos os clientes.
+/// Séries com labels (`DISK{mount="/home"}`) entram na família da métrica pai,
+/// com as dimensões somadas aos labels `client`, `host` e `namespace`. O namespace
+/// só sai quando difere do hostname, como a tag do StatsD
+fn render_metrics(samples: &SharedSamples) -> String {
+    let mut families: BTreeMap<String, Vec<(String, f64)>> = BTreeMap::new();
+    {
+        let samples = samples.lock().unwrap_or_else(|e| e.into_inner());
+        for (client, sample) in samples.iter() {
+            for (key, value) in export_values(&sample.metrics, &sample.values) {
+                if is_temperature_unavailable(&key, value) {
+                    continue;
+                }
+                let series = labels::split_series(&key);
+                let name = series.as_ref().map_or(key.as_str(), |series| series.name);
+
+                let mut label_set = format!("client=\"{}\"", escape_label_value(&client.to_string()));
+                if let Some(hostname) = &sample.hostname {
+                    let _ = write!(label_set, ",host=\"{}\"", escape_label_value(hostname));
+                }
+                let namespace = sample.namespace.as_ref().filter(|namespace| sample.hostname.as_ref() != Some(*namespace));
+                if let Some(namespace) = namespace {
+                    let _ = write!(label_set, ",namespace=\"{}\"", escape_label_value(namespace));
+                }
+                for (label, label_value) in series.iter().flat_map(|series| &series.labels) {
+                    let _ = write!(label_set, ",{}=\"{}\"", sanitize_label_name(label), escape_label_value(label_value));
+                }
+                families.entry(sanitize_metric_name(name))
+                    .or_default()
+                    .push((label_set, value));
+            }
+        }
+    }
+
+    let mut body = String::new();
+    for (name, mut series) in families {
+        // Ordem estável entre scrapes, independente do HashMap de amostras
+        series.sort_by(|a, b| a.0.cmp(&b.0));
+        let _ = writeln!(body, "# TYPE {} gauge", name);
+        for (label_set, value) in series {
+            let _ = writeln!(body, "{}{{{}}} {}", name, label_set, value);
+        }
+    }
+    body
+}
+
+/// Nome de label em `[a-zA-Z0-9_]`, sem começar por dígito. `client`, `host` e
+/// `namespace` são do exportador: um label do cliente com um desses nomes sai
+/// como `exported_<nome>`, como no Prometheus
+fn sanitize_label_name(name: &str) -> String {
+    let mut sanitized: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    if EXPORTER_LABELS.contains(&sanitized.as_str()) {
+        sanitized.insert_str(0, "exported_");
+    }
+    sanitized
+}
+
+/// Converte o nome para `[a-z0-9_]` com o prefixo `telemetry_`
+fn sanitize_metric_name(name: &str) -> String {
+    let mut sanitized = String::with_capacity(METRIC_PREFIX.len() + name.len());
+    sanitized.push_str(METRIC_PREFIX);
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            sanitized.push(c.to_ascii_lowercase());
+        } else {
+            sanitized.push('_');
+        }
+    }
+    sanitized
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use telemetry_common::TEMPERATURE_UNAVAILABLE;
+
+    use super::*;
+    use crate::LatestSample;
+
+    /// Hosts e namespaces diferentes caem na mesma família, separados por label
+    #[test]
+    fn host_and_namespace_are_labels() {
+        let sample = |hostname: &str, namespace: &str| LatestSample {
+            hostname: Some(hostname.to_string()),
+            namespace: Some(namespace.to_string()),
+            metrics: HashMap::from([("CPU".to_string(), 42.5), ("TEMP".to_string(), TEMPERATURE_UNAVAILABLE)]),
+            values: BTreeMap::new(),
+        };
+        let samples: SharedSamples = Arc::new(Mutex::new(HashMap::from([
+            ("10.0.0.1:5000".parse().unwrap(), sample("web-01", "web-01")),
+            ("10.0.0.2:5000".parse().unwrap(), sample("web-02", "prod")),
+        ])));
+
+        let body = render_metrics(&samples);
+        assert_eq!(body, concat!(
+            "# TYPE telemetry_cpu gauge\n",
+            "telemetry_cpu{client=\"10.0.0.1:5000\",host=\"web-01\"} 42.5\n",
+            "telemetry_cpu{client=\"10.0.0.2:5000\",host=\"web-02\",namespace=\"prod\"} 42.5\n",
+        ));
+        assert_eq!(sanitize_label_name("host"), "exported_host");
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::logger::log_protocol;
+
+/// Bytes por linha do dump hexadecimal
+const HEX_ROW: usize = 16;
+
+/// Maior trecho despejado em hexa; o texto sai inteiro
+const MAX_HEX_BYTES: usize = 1024;
+
+/// Ligado por `--debug-protocol` (ou `debug_protocol = true`) e alternado pelo comando `V`
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Registra uma mensagem crua antes do parse: tamanho, texto com escapes e
+/// dump hexadecimal. `kind` diz de onde ela veio (linha, frame, datagrama)
+pub fn received(addr: SocketAddr, kind: &str, raw: &[u8]) {
+    if !enabled() {
+        return;
+    }
+    let mut message = format!(
+        "{} ← {} de {} bytes\n  texto: {:?}", addr, kind, raw.len(), String::from_utf8_lossy(raw)
+    );
+    for row in hex_dump(raw) {
+        message.push_str("\n  ");
+        message.push_str(&row);
+    }
+    log_protocol(&message);
+}
+
+/// Registra o resultado do parse; a descrição só é montada com o modo ligado
+pub fn parsed(addr: SocketAddr, describe: impl FnOnce() -> Result<String, String>) {
+    if !enabled() {
+        return;
+    }
+    match describe() {
+        Ok(result) => log_protocol(&format!("{} parse ok: {}", addr, result)),
+        Err(error) => log_protocol(&format!("{} parse falhou: {}", addr, error)),
+    }
+}
+
+/// Linhas no formato do `hexdump -C`: deslocamento, bytes em hexa e ASCII
+/// imprimível, truncadas em `MAX_HEX_BYTES`
+fn hex_dump(raw: &[u8]) -> Vec<String> {
+    let shown = &raw[..raw.len().min(MAX_HEX_BYTES)];
+    let mut rows: Vec<String> = shown.chunks(HEX_ROW).enumerate().map(|(index, chunk)| {
+        let mut row = format!("{:08x} ", index * HEX_ROW);
+        for (position, byte) in chunk.iter().enumerate() {
+            if position == HEX_ROW / 2 {
+                row.push(' ');
+            }
+            let _ = write!(row, " {:02x}", byte);
+        }
+        let missing = HEX_ROW - chunk.len();
+        let padding = missing * 3 + usize::from(chunk.len() <= HEX_ROW / 2);
+        let ascii: String = chunk.iter()
+            .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+            .collect();
+        let _ = write!(row, "{:padding$}  |{}|", "", ascii);
+        row
+    }).collect();
+
+    if raw.len() > shown.len() {
+        rows.push(format!("... {} bytes omitidos", raw.len() - shown.len()));
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dumps_bytes_like_hexdump() {
+        let rows = hex_dump(b"{\"CPU\":42.5}\r\n\xff");
+        assert_eq!(rows, [
+            "00000000  7b 22 43 50 55 22 3a 34  32 2e 35 7d 0d 0a ff     |{\"CPU\":42.5}...|",
+        ]);
+
+        let rows = hex_dump(b"abc");
+        assert_eq!(rows, ["00000000  61 62 63                                          |abc|"]);
+
+        let rows = hex_dump(&[b'x'; MAX_HEX_BYTES + 10]);
+        assert_eq!(rows.len(), MAX_HEX_BYTES / HEX_ROW + 1);
+        assert_eq!(rows.last().unwrap(), "... 10 bytes omitidos");
+    }
+}
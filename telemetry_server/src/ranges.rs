@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use telemetry_common::{labels, sample::{core_index, socket_index}};
+
+/// Faixa plausível de uma métrica; limites ausentes ficam abertos
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MetricRange {
+    #[serde(default = "open_min")]
+    pub min: f32,
+    #[serde(default = "open_max")]
+    pub max: f32,
+}
+
+fn open_min() -> f32 {
+    f32::NEG_INFINITY
+}
+
+fn open_max() -> f32 {
+    f32::INFINITY
+}
+
+impl MetricRange {
+    const fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(&self, value: f32) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+}
+
+/// Faixas por nome de métrica (em maiúsculas): padrões embutidos
+/// sobrescritos ou estendidos pela seção `[ranges]` da configuração
+#[derive(Debug)]
+pub struct RangeTable {
+    ranges: HashMap<String, MetricRange>,
+}
+
+impl RangeTable {
+    pub fn new(overrides: &HashMap<String, MetricRange>) -> Self {
+        let defaults = [
+            ("CPU", MetricRange::new(0.0, 100.0)),
+            ("DISK", MetricRange::new(0.0, 100.0)),
+            ("SWAP", MetricRange::new(0.0, 100.0)),
+            ("GPU_UTIL", MetricRange::new(0.0, 100.0)),
+            ("TEMP", MetricRange::new(-50.0, 150.0)),
+            ("MEM", MetricRange::new(0.0, f32::INFINITY)),
+            ("MEM_PERCENT", MetricRange::new(0.0, 100.0)),
+            ("NET", MetricRange::new(0.0, f32::INFINITY)),
+            ("DISK_READ", MetricRange::new(0.0, f32::INFINITY)),
+            ("DISK_WRITE", MetricRange::new(0.0, f32::INFINITY)),
+            ("PROC_COUNT", MetricRange::new(0.0, f32::INFINITY)),
+            ("TCP_CONNS", MetricRange::new(0.0, f32::INFINITY)),
+            ("UPTIME", MetricRange::new(0.0, f32::INFINITY)),
+            ("GPU_MEM", MetricRange::new(0.0, f32::INFINITY)),
+            ("CPU_FREQ", MetricRange::new(0.0, f32::INFINITY)),
+            ("BATTERY", MetricRange::new(0.0, 100.0)),
+            ("BATTERY_CHARGING", MetricRange::new(0.0, 1.0)),
+        ];
+
+        let mut ranges: HashMap<String, MetricRange> = defaults
+            .into_iter()
+            .map(|(name, range)| (name.to_string(), range))
+            .collect();
+        for (name, range) in overrides {
+            ranges.insert(name.to_uppercase(), *range);
+        }
+
+        Self { ranges }
+    }
+
+    /// Faixa violada pelo valor, se houver; métricas sem faixa são sempre aceitas
+    /// Núcleos e sockets sem faixa própria (`CPU_3`, `CPU_SOCKET_1`) herdam a faixa de `CPU`
+    pub fn violation(&self, name: &str, value: f32) -> Option<MetricRange> {
+        // Séries com labels herdam a faixa da métrica pai
+        let upper = labels::base_name(name).to_uppercase();
+        self.ranges
+            .get(&upper)
+            .or_else(|| core_index(&upper).or(socket_index(&upper)).and_then(|_| self.ranges.get("CPU")))
+            .filter(|range| !range.contains(value))
+            .copied()
+    }
+}
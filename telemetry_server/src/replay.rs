@@ -0,0 +1,169 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Result as IoResult, Write},
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use telemetry_common::{sample::TopProcess, value::Value};
+
+use crate::{
+    log, ClientIdentity, ClientSession, LogLevel, ServerState, TelemetryPacket,
+    ACCEPT_POLL_INTERVAL,
+};
+
+/// Ritmo da reprodução em relação à gravação
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Multiplicador sobre os intervalos gravados (`1x` é tempo real)
+    Factor(f32),
+    /// Sem espera entre amostras
+    Instant,
+}
+
+impl ReplaySpeed {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        if raw.eq_ignore_ascii_case("instant") {
+            return Ok(ReplaySpeed::Instant);
+        }
+        raw.trim_end_matches(['x', 'X'])
+            .parse::<f32>()
+            .ok()
+            .filter(|factor| factor.is_finite() && *factor > 0.0)
+            .map(ReplaySpeed::Factor)
+            .ok_or_else(|| format!("Velocidade inválida '{}' (ex.: 1x, 2x, instant)", raw))
+    }
+
+    /// Espera equivalente a `gap_ms` gravados; `None` no modo instantâneo
+    fn scale(&self, gap_ms: u64) -> Option<Duration> {
+        match self {
+            ReplaySpeed::Factor(factor) => Some(Duration::from_secs_f64(gap_ms as f64 / 1000.0 / *factor as f64)),
+            ReplaySpeed::Instant => None,
+        }
+    }
+}
+
+impl fmt::Display for ReplaySpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplaySpeed::Factor(factor) => write!(f, "{}x", factor),
+            ReplaySpeed::Instant => write!(f, "instantâneo"),
+        }
+    }
+}
+
+/// Grava cada amostra recebida como uma linha JSON (o mesmo formato do `--output json`)
+pub struct Recorder {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl Recorder {
+    pub fn open(path: &Path) -> IoResult<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { writer: Mutex::new(BufWriter::new(file)) })
+    }
+
+    pub fn write(&self, record: &serde_json::Value) -> IoResult<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(writer, "{}", record)?;
+        // Flush por amostra: a gravação precisa sobreviver a um encerramento abrupto
+        writer.flush()
+    }
+}
+
+/// Linha de uma gravação
+#[derive(Deserialize)]
+struct RecordedSample {
+    client: String,
+    #[serde(default)]
+    hostname: Option<String>,
+    #[serde(default)]
+    namespace: Option<String>,
+    address: String,
+    #[serde(default)]
+    timestamp: Option<u64>,
+    timestamp_received: u64,
+    metrics: HashMap<String, f32>,
+    #[serde(default)]
+    values: BTreeMap<String, Value>,
+    #[serde(default)]
+    top_process: Option<TopProcess>,
+}
+
+/// Toca a gravação pelo mesmo caminho das amostras ao vivo (faixas, alertas,
+/// exportações e exibição), respeitando os intervalos gravados conforme `speed`
+pub fn play(path: &Path, speed: ReplaySpeed, state: &Arc<ServerState>) -> IoResult<()> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut sessions: HashMap<String, ClientSession> = HashMap::new();
+    let mut previous_received: Option<u64> = None;
+    let mut played = 0u64;
+
+    for (number, line) in reader.lines().enumerate() {
+        if state.is_shutting_down() {
+            break;
+        }
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let recorded: RecordedSample = match serde_json::from_str(&line) {
+            Ok(recorded) => recorded,
+            Err(e) => {
+                log(LogLevel::Warning, &format!("Linha {} da gravação ignorada: {}", number + 1, e));
+                continue;
+            }
+        };
+
+        if let Some(previous) = previous_received {
+            let gap = recorded.timestamp_received.saturating_sub(previous);
+            if let Some(wait) = speed.scale(gap) {
+                wait_interruptibly(wait, state);
+            }
+        }
+        previous_received = Some(recorded.timestamp_received);
+
+        let session = sessions.entry(recorded.client.clone()).or_insert_with(|| {
+            let addr: SocketAddr = recorded.address.parse().unwrap_or_else(|_| SocketAddr::from(([0, 0, 0, 0], 0)));
+            let mut session = ClientSession::new(addr, Arc::clone(state));
+            session.adopt_identity(ClientIdentity {
+                client_id: recorded.client.clone(),
+                hostname: recorded.hostname.clone(),
+                namespace: recorded.namespace.clone(),
+                anonymous: false,
+            });
+            session
+        });
+
+        session.process_packet(&TelemetryPacket {
+            timestamp: recorded.timestamp,
+            top_process: recorded.top_process,
+            metrics: recorded.metrics,
+            values: recorded.values,
+        });
+        played += 1;
+    }
+
+    for session in sessions.values_mut() {
+        session.cleanup();
+    }
+    log(LogLevel::Success, &format!("Replay concluído: {} amostras reproduzidas", played));
+    Ok(())
+}
+
+/// Dorme em fatias curtas para que Ctrl+C ou `Q` interrompam o replay
+fn wait_interruptibly(total: Duration, state: &ServerState) {
+    let mut remaining = total;
+    while !remaining.is_zero() && !state.is_shutting_down() {
+        let step = remaining.min(ACCEPT_POLL_INTERVAL);
+        thread::sleep(step);
+        remaining -= step;
+    }
+}
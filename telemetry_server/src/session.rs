@@ -0,0 +1,630 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write as FmtWrite,
+    net::SocketAddr,
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::Instant,
+};
+
+use telemetry_common::{
+    format::format_uptime,
+    labels,
+    protocol,
+    sample::{self, core_index, TelemetrySample},
+    timefmt, value::Value, is_temperature_unavailable,
+};
+
+use crate::{
+    alerts::AlertMonitor, clock::ClockOffset, epoch_millis, history::MetricHistory, log, parse_stats::ParseStats,
+    protocol_debug, severity::SeverityTable, ClientIdentity, DisplayMode, LogLevel,
+    LatestSample, ServerState, TelemetryPacket, CLEAR_SCREEN, RED, RESET,
+};
+
+/// Resumo de um cliente ativo no registro central, listado pelo comando `L`
+pub struct ClientInfo {
+    pub client_id: String,
+    pub hostname: Option<String>,
+    pub connected_at: Instant,
+    pub last_sample: Option<Instant>,
+    /// Mensagens aceitas e rejeitadas pelo parser nesta conexão
+    pub parse_stats: ParseStats,
+    /// Histórico da sessão, consultado pelo `S` só quando pedido
+    pub history: Arc<Mutex<MetricHistory>>,
+}
+
+/// Estado de um cliente independente do transporte: identidade, histórico,
+/// alertas e exibição. Uma conexão TCP tem uma sessão; no UDP cada endereço de origem tem a sua
+pub struct ClientSession {
+    pub addr: SocketAddr,
+    pub state: Arc<ServerState>,
+    pub identity: ClientIdentity,
+    /// Compartilhado com o `ClientInfo`; só a thread da sessão escreve
+    pub history: Arc<Mutex<MetricHistory>>,
+    alerts: AlertMonitor,
+    /// Métricas cuja última amostra ficou fora da faixa plausível
+    out_of_range: HashSet<String>,
+    /// Último RTT informado pelo cliente nos pings, exibido como `RTT_MS`
+    rtt_ms: Option<f32>,
+    /// Última amostra chegou com timestamp no futuro (relógio do cliente adiantado)
+    clock_ahead: bool,
+    /// Diferença do relógio do cliente estimada no handshake, descontada dos timestamps
+    clock_offset: ClockOffset,
+    /// Versão negociada no handshake; sem handshake, v1
+    protocol_version: u32,
+    /// Séries com labels de um cliente v1 já foram avisadas
+    labels_rejected: bool,
+}
+
+impl ClientSession {
+    pub fn new(addr: SocketAddr, state: Arc<ServerState>) -> Self {
+        let identity = ClientIdentity::anonymous(addr);
+        let history = Arc::new(Mutex::new(MetricHistory::new(state.config.history_size, &state.config.history_tiers)));
+        state.clients.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(addr, ClientInfo {
+                client_id: identity.client_id.clone(),
+                hostname: None,
+                connected_at: Instant::now(),
+                last_sample: None,
+                parse_stats: ParseStats::default(),
+                history: Arc::clone(&history),
+            });
+
+        Self {
+            addr,
+            identity,
+            history,
+            alerts: AlertMonitor::new(Arc::clone(&state.alert_rules), addr),
+            out_of_range: HashSet::new(),
+            rtt_ms: None,
+            clock_ahead: false,
+            clock_offset: ClockOffset::default(),
+            protocol_version: protocol::LEGACY_PROTOCOL_VERSION,
+            labels_rejected: false,
+            state,
+        }
+    }
+
+    /// Adota a versão declarada no handshake, ou devolve o motivo da recusa
+    pub fn negotiate_protocol(&mut self, declared: Option<u32>) -> Result<u32, String> {
+        self.protocol_version = protocol::negotiate(declared)?;
+        Ok(self.protocol_version)
+    }
+
+    pub fn protocol_version(&self) -> u32 {
+        self.protocol_version
+    }
+
+    /// Adota a identidade declarada no handshake e retoma o histórico do mesmo ID
+    pub fn adopt_identity(&mut self, identity: ClientIdentity) {
+        log(LogLevel::Info, &format!(
+            "Cliente {} identificado como {}", self.addr, identity.display_name()
+        ));
+        self.alerts.identify(identity.display_name());
+
+        let previous = self.state.histories.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&identity.client_id);
+        if let Some(history) = previous {
+            log(LogLevel::Info, &format!(
+                "Histórico de {} retomado ({} amostras em conexões anteriores)",
+                identity.display_name(), history.received()
+            ));
+            *self.history.lock().unwrap_or_else(|e| e.into_inner()) = history;
+        }
+
+        if let Some(info) = self.state.clients.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(&self.addr)
+        {
+            info.client_id = identity.client_id.clone();
+            info.hostname = identity.hostname.clone();
+        }
+
+        self.identity = identity;
+    }
+
+    /// Registra o offset do relógio do cliente, que passa a corrigir os timestamps
+    /// das amostras; diferenças de minutos geram aviso
+    pub fn adopt_clock(&mut self, client_clock: u64, server_clock: u64) -> ClockOffset {
+        let offset = ClockOffset::estimate(client_clock, server_clock);
+        if offset.is_suspicious() {
+            log(LogLevel::Warning, &format!(
+                "Relógio de {} difere do servidor em {:+.1}s; sincronize os hosts com NTP. Os timestamps serão corrigidos pelo offset",
+                self.identity.display_name(), offset.millis() as f64 / 1000.0
+            ));
+        } else if offset.is_significant() {
+            log(LogLevel::Info, &format!(
+                "Relógio de {} difere do servidor em {:+.1}s; timestamps corrigidos pelo offset",
+                self.identity.display_name(), offset.millis() as f64 / 1000.0
+            ));
+        }
+        self.clock_offset = offset;
+        offset
+    }
+
+    /// Interpreta uma mensagem (uma amostra ou um batch), contabiliza o resultado
+    /// e processa as amostras; JSON inválido é logado e descartado
+    pub fn process_message(&mut self, message: &str) {
+        if message.trim_start().starts_with('[') && !protocol::supports_batch(self.protocol_version) {
+            log(LogLevel::Warning, &format!(
+                "Batch de {} descartado: exige protocolo v2 (cliente em v{})",
+                self.identity.display_name(), self.protocol_version
+            ));
+            self.record_parse(false);
+            return;
+        }
+        let parsed = sample::parse_samples(message);
+        protocol_debug::parsed(self.addr, || match &parsed {
+            Ok(samples) => Ok(describe_samples(samples)),
+            Err(e) => Err(e.to_string()),
+        });
+        if let Err(e) = &parsed {
+            log(LogLevel::Warning, &format!("Erro ao processar JSON: {}", e));
+            log(LogLevel::Info, &format!("Dados recebidos: {}", message.trim()));
+        }
+        self.record_parse(parsed.is_ok());
+
+        for mut sample in parsed.into_iter().flatten() {
+            // Números fora do alcance do f32 (ex.: 1e300) chegam como infinito
+            let discarded = sample.sanitize();
+            if !discarded.is_empty() {
+                log(LogLevel::Warning, &format!(
+                    "Valores não finitos (NaN/Inf) de {} descartados: {}", self.identity.display_name(), discarded.join(", "),
+                ));
+            }
+            let mut packet = TelemetryPacket::from(sample);
+            if !protocol::supports_labels(self.protocol_version) {
+                self.drop_labeled_series(&mut packet);
+            }
+            packet.timestamp = packet.timestamp.map(|timestamp| self.clock_offset.normalize(timestamp));
+            if let Some(rtt_ms) = self.rtt_ms {
+                packet.metrics.insert("RTT_MS".to_string(), rtt_ms);
+            }
+            self.process_packet(&packet);
+        }
+    }
+
+    /// Cliente v1: séries com labels ficam de fora, com um aviso por sessão
+    fn drop_labeled_series(&mut self, packet: &mut TelemetryPacket) {
+        let before = packet.metrics.len() + packet.values.len();
+        packet.metrics.retain(|name, _| labels::split_series(name).is_none());
+        packet.values.retain(|name, _| labels::split_series(name).is_none());
+        if packet.metrics.len() + packet.values.len() < before && !self.labels_rejected {
+            self.labels_rejected = true;
+            log(LogLevel::Warning, &format!(
+                "Séries com labels de {} descartadas: exigem protocolo v2 (cliente em v{})",
+                self.identity.display_name(), self.protocol_version
+            ));
+        }
+    }
+
+    /// Guarda o RTT medido pelo cliente; pings sem medida mantêm o anterior
+    pub fn record_rtt(&mut self, rtt_ms: Option<f32>) {
+        if let Some(rtt_ms) = rtt_ms.filter(|rtt| rtt.is_finite() && *rtt >= 0.0) {
+            self.rtt_ms = Some(rtt_ms);
+        }
+    }
+
+    /// Atualiza os contadores do parser e avisa quando a taxa de erro passa do limiar
+    fn record_parse(&self, parsed: bool) {
+        let threshold = self.state.config.parse_error_threshold;
+        let mut clients = self.state.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(info) = clients.get_mut(&self.addr) else {
+            return;
+        };
+
+        let stats = &mut info.parse_stats;
+        if stats.record(parsed, threshold) {
+            log(LogLevel::Warning, &format!(
+                "Cliente {}: {:.1}% das mensagens rejeitadas ({} de {}); possível incompatibilidade de protocolo",
+                self.identity.display_name(), stats.error_rate(), stats.rejected, stats.total()
+            ));
+        }
+    }
+
+    pub fn process_packet(&mut self, packet: &TelemetryPacket) {
+        self.state.samples_processed.fetch_add(1, Ordering::Relaxed);
+
+        self.validate_ranges(packet);
+        self.check_clock(packet);
+        self.persist_telemetry(packet);
+        self.history.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record(packet.timestamp.unwrap_or_else(epoch_millis), &packet.metrics);
+        if let Some(info) = self.state.clients.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(&self.addr)
+        {
+            info.last_sample = Some(Instant::now());
+        }
+        self.alerts.evaluate(&packet.metrics, &self.state.formatters);
+        self.display_telemetry(packet);
+    }
+
+    /// Sinaliza valores fora da faixa plausível sem descartá-los. O aviso sai só
+    /// na entrada da métrica nesse estado, para um cliente com bug não inundar o log
+    fn validate_ranges(&mut self, packet: &TelemetryPacket) {
+        for (name, &value) in &packet.metrics {
+            let is_sentinel = is_temperature_unavailable(name, f64::from(value));
+            let violation = self.state.ranges.violation(name, value).filter(|_| !is_sentinel);
+
+            match violation {
+                Some(range) if self.out_of_range.insert(name.clone()) => {
+                    log(LogLevel::Warning, &format!(
+                        "Cliente {}: {} = {} fora da faixa [{}, {}]",
+                        self.identity.display_name(), name, value, range.min, range.max
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    self.out_of_range.remove(name);
+                }
+            }
+        }
+    }
+
+    /// Latência negativa significa relógio do cliente adiantado em relação ao
+    /// servidor: avisa na entrada desse estado e quando ele se resolve
+    fn check_clock(&mut self, packet: &TelemetryPacket) {
+        let Some(latency) = ingest_latency_ms(packet) else {
+            return;
+        };
+
+        let ahead = latency < 0;
+        if ahead && !self.clock_ahead {
+            log(LogLevel::Warning, &format!(
+                "Cliente {}: latência de ingestão negativa ({} ms); relógio do cliente adiantado em relação ao servidor",
+                self.identity.display_name(), latency
+            ));
+        } else if !ahead && self.clock_ahead {
+            log(LogLevel::Info, &format!("Cliente {}: relógio voltou a ficar consistente", self.identity.display_name()));
+        }
+        self.clock_ahead = ahead;
+    }
+
+    fn persist_telemetry(&self, packet: &TelemetryPacket) {
+        let timestamp = packet.timestamp.unwrap_or_else(epoch_millis);
+        if let Some(recorder) = &self.state.recorder {
+            if let Err(e) = recorder.write(&self.json_record(packet)) {
+                log(LogLevel::Warning, &format!("Erro ao gravar sessão: {}", e));
+            }
+        }
+        if let Some(jsonl) = &self.state.jsonl {
+            jsonl.write(&self.identity.client_id, timestamp, &packet.metrics);
+        }
+        if self.state.sqlite.is_some() || self.state.influx.is_some() || self.state.statsd.is_some() {
+            let exported = packet.exported();
+            let identity = &self.identity;
+            if let Some(sqlite) = &self.state.sqlite {
+                sqlite.write(&identity.client_id, identity.hostname.as_deref(), timestamp, &exported);
+            }
+            if let Some(influx) = &self.state.influx {
+                influx.export(&identity.client_id, identity.hostname.as_deref(), identity.namespace(), timestamp, &exported);
+            }
+            if let Some(statsd) = &self.state.statsd {
+                statsd.export(&identity.client_id, identity.hostname.as_deref(), identity.namespace(), &exported);
+            }
+        }
+
+        let Some(csv) = &self.state.csv else {
+            return;
+        };
+
+        let mut writer = csv.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writer.write_sample(timestamp, self.addr, &packet.metrics) {
+            log(LogLevel::Warning, &format!("Erro ao gravar CSV: {}", e));
+        }
+    }
+
+    fn display_telemetry(&self, packet: &TelemetryPacket) {
+        self.state.samples.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(self.addr, LatestSample {
+                hostname: self.identity.hostname.clone(),
+                namespace: self.identity.namespace().map(str::to_string),
+                metrics: packet.metrics.clone(),
+                values: packet.values.clone(),
+            });
+
+        if let Some(web) = &self.state.web {
+            web.broadcast(&self.json_record(packet).to_string());
+        }
+
+        match self.state.display {
+            DisplayMode::Dashboard => {}
+            DisplayMode::Lines => return self.log_telemetry_line(packet),
+            DisplayMode::Json => return self.emit_json(packet),
+        }
+
+        let mut block = String::with_capacity(512);
+        self.render_telemetry(&mut block, packet);
+
+        // O lock da tela serializa a saída: blocos de clientes diferentes nunca se misturam
+        let mut screen = self.state.screen.lock().unwrap_or_else(|e| e.into_inner());
+        screen.insert(self.addr, block);
+        self.redraw(&screen);
+    }
+
+    /// Modo headless: uma linha por amostra, sem escapes ANSI
+    fn log_telemetry_line(&self, packet: &TelemetryPacket) {
+        let timestamp = packet.timestamp.unwrap_or_else(epoch_millis);
+        let mut line = format!(
+            "[{}] {} ({})",
+            timefmt::format_iso8601(timestamp),
+            self.identity.display_name(),
+            self.addr
+        );
+
+        let formatters = &self.state.formatters;
+        for (name, value) in self.state.display_filter.select(&packet.metrics) {
+            if is_temperature_unavailable(name, f64::from(value)) {
+                continue;
+            }
+            let upper = labels::base_name(name).to_uppercase();
+            let formatted = match packet.values.get(name) {
+                Some(raw) => formatters.format_raw(&upper, raw),
+                None => formatters.format(&upper, value),
+            };
+            let _ = write!(line, " {}={}", name, formatted);
+            if self.out_of_range.contains(name) {
+                line.push_str("(!)");
+            }
+        }
+        for (name, text) in self.text_values(packet) {
+            let _ = write!(line, " {}={:?}", name, text.to_string());
+        }
+        if let Some(top) = &packet.top_process {
+            let _ = write!(line, " top_process={}({:.1}%)", top.name, top.cpu);
+        }
+        if let Some(latency) = ingest_latency_ms(packet) {
+            let _ = write!(line, " latency={}ms", latency);
+        }
+
+        line.push('\n');
+        self.state.output.write(&line);
+    }
+
+    /// Modo `--output json`: reemite a amostra como uma linha JSON
+    fn emit_json(&self, packet: &TelemetryPacket) {
+        self.state.output.write(&format!("{}\n", self.json_record(packet)));
+    }
+
+    /// Amostra com a identificação do cliente, formato do `--output json` e do dashboard web
+    fn json_record(&self, packet: &TelemetryPacket) -> serde_json::Value {
+        let mut record = serde_json::json!({
+            "client": self.identity.client_id,
+            "hostname": self.identity.hostname,
+            "address": self.addr.to_string(),
+            "timestamp": packet.timestamp,
+            "timestamp_received": epoch_millis(),
+            "metrics": packet.metrics,
+        });
+        if !packet.values.is_empty() {
+            record["values"] = serde_json::json!(packet.values);
+        }
+        if let Some(namespace) = &self.identity.namespace {
+            record["namespace"] = serde_json::Value::from(namespace.as_str());
+        }
+        if let Some(top) = &packet.top_process {
+            record["top_process"] = serde_json::json!({ "name": top.name, "cpu": top.cpu });
+        }
+        record
+    }
+
+    /// Limpeza após a desconexão (ou inatividade, no UDP): tira o cliente da tela e das exportações
+    /// e guarda o histórico para uma possível reconexão do mesmo ID
+    pub fn cleanup(&mut self) {
+        self.state.clients.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.addr);
+        if let Some(web) = &self.state.web {
+            let notice = serde_json::json!({ "client": self.identity.client_id, "disconnected": true });
+            web.broadcast(&notice.to_string());
+        }
+        self.state.samples.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.addr);
+
+        // IDs anônimos mudam a cada conexão, então não há o que retomar
+        if !self.identity.anonymous {
+            let history = std::mem::replace(
+                &mut *self.history.lock().unwrap_or_else(|e| e.into_inner()),
+                MetricHistory::new(1, &[]),
+            );
+            self.state.histories.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(self.identity.client_id.clone(), history);
+        }
+
+        let mut screen = self.state.screen.lock().unwrap_or_else(|e| e.into_inner());
+        if screen.remove(&self.addr).is_some() && self.state.display == DisplayMode::Dashboard {
+            self.redraw(&screen);
+        }
+    }
+
+    fn redraw(&self, screen: &BTreeMap<SocketAddr, String>) {
+        let mut out = String::with_capacity(screen.values().map(String::len).sum::<usize>() + 128);
+        out.push_str(CLEAR_SCREEN);
+        out.push_str("📊 TELEMETRIA EM TEMPO REAL\n");
+
+        for block in screen.values() {
+            out.push_str(block);
+        }
+
+        let _ = writeln!(out, "{}", "=".repeat(50));
+        let _ = writeln!(out, "⏹️  Pressione Ctrl+C para sair");
+
+        self.state.output.write(&out);
+    }
+
+    fn render_telemetry(&self, out: &mut String, packet: &TelemetryPacket) {
+        let _ = writeln!(out, "{}", "=".repeat(50));
+        let _ = writeln!(out, "🔗 Cliente: {}", self.identity.display_name());
+        let _ = writeln!(out, "📍 Endereço: {}", self.addr);
+        match ingest_latency_ms(packet) {
+            Some(latency) if latency < 0 => {
+                let _ = writeln!(out, "⏱️  Latência de ingestão: {} ms ⚠️  relógio do cliente adiantado", latency);
+            }
+            Some(latency) => {
+                let _ = writeln!(out, "⏱️  Latência de ingestão: {} ms", latency);
+            }
+            None => {}
+        }
+        let _ = writeln!(out, "{}", "-".repeat(50));
+
+        if packet.metrics.is_empty() && packet.values.is_empty() {
+            let _ = writeln!(out, "⚠️  Nenhuma métrica recebida");
+        } else {
+            // Seções na ordem em que a primeira métrica de cada uma aparece
+            let selected = self.state.display_filter.select(&packet.metrics);
+            let texts: Vec<_> = self.text_values(packet).collect();
+            let categories = &self.state.categories;
+            let names = selected.iter().map(|(name, _)| *name).chain(texts.iter().map(|(name, _)| *name));
+
+            for section in categories.sections(names) {
+                let _ = writeln!(out, "── {}", section);
+                let mut cores = Vec::new();
+                // Métrica da linha anterior: as séries com labels se agrupam sob ela
+                let mut parent: Option<&str> = None;
+                for &(metric_name, value) in selected.iter().filter(|(name, _)| categories.category(name) == section) {
+                    let base = labels::base_name(metric_name);
+                    if base.len() < metric_name.len() && !parent.is_some_and(|parent| parent.eq_ignore_ascii_case(base)) {
+                        // Sem valor próprio da métrica pai: só o cabeçalho
+                        let _ = writeln!(out, "{}:", self.state.formatters.label(&base.to_uppercase(), base));
+                    }
+                    parent = Some(base);
+                    match core_index(metric_name) {
+                        Some(index) => cores.push((index, value)),
+                        None => self.format_metric(out, metric_name, value, packet.values.get(metric_name)),
+                    }
+                }
+                render_cores(out, &mut cores, &self.state.severity);
+                for (name, text) in texts.iter().filter(|(name, _)| categories.category(name) == section) {
+                    let _ = writeln!(out, "{}", self.state.formatters.describe(name, text));
+                }
+            }
+        }
+
+        if let Some(top) = &packet.top_process {
+            let _ = writeln!(out, "🔝 Maior consumo: {} ({:.1}% CPU)", top.name, top.cpu);
+        }
+    }
+
+    /// Métricas textuais selecionadas pelo `--show`; não têm visão numérica, então
+    /// ficam fora do `select` e do histórico
+    fn text_values<'a>(&self, packet: &'a TelemetryPacket) -> impl Iterator<Item = (&'a str, &'a Value)> + use<'a, '_> {
+        packet.values.iter().filter_map(|(name, value)| match value {
+            Value::Text(_) if self.state.display_filter.shows(name) => Some((name.as_str(), value)),
+            _ => None,
+        })
+    }
+
+    /// `raw` é o valor original das customizadas que não são float. Séries com
+    /// labels saem indentadas sob a métrica pai, com a unidade e as cores dela
+    fn format_metric(&self, out: &mut String, name: &str, value: f32, raw: Option<&Value>) {
+        let series = labels::split_series(name);
+        let upper = series.as_ref().map_or(name, |series| series.name).to_uppercase();
+
+        // Sem sensores no cliente: omite a linha em vez de exibir um valor enganoso
+        if is_temperature_unavailable(name, f64::from(value)) {
+            return;
+        }
+
+        let formatters = &self.state.formatters;
+        let formatted = match raw {
+            Some(raw) => formatters.format_raw(&upper, raw),
+            None => formatters.format(&upper, value),
+        };
+        let label = match &series {
+            Some(series) => format!("   └ {}", series.describe_labels()),
+            None => formatters.label(&upper, name),
+        };
+        // Fora da faixa plausível prevalece sobre a severidade
+        let color = if self.out_of_range.contains(name) {
+            Some(RED)
+        } else {
+            self.state.severity.color(&upper, value)
+        };
+        let _ = write!(out, "{}: {}", label, paint(&formatted, color));
+
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(stats) = history.stats(name).filter(|stats| stats.count > 1) {
+            let _ = write!(
+                out,
+                " (min {} / avg {} / max {} nos últimos {}s)",
+                formatters.format(&upper, stats.min),
+                formatters.format(&upper, stats.avg),
+                formatters.format(&upper, stats.max),
+                stats.span_ms / 1000,
+            );
+        }
+        if let Some(retained) = history.retained_stats(name) {
+            let _ = write!(
+                out,
+                " · média de {} em {}",
+                formatters.format(&upper, retained.avg),
+                format_uptime(retained.span_ms as f32 / 1000.0),
+            );
+        }
+
+        let _ = writeln!(out);
+    }
+}
+
+/// Núcleos por linha no painel; acima de `COMPACT_CORES` as barras encolhem
+const CORES_PER_ROW: usize = 4;
+const COMPACT_CORES: usize = 16;
+const COMPACT_CORES_PER_ROW: usize = 8;
+
+/// `N amostra(s): CPU=42.5 MEM=61`, como o parser as entendeu, para o `--debug-protocol`
+fn describe_samples(samples: &[TelemetrySample]) -> String {
+    let metrics: Vec<String> = samples.iter().map(|sample| {
+        let metrics: BTreeMap<String, f32> = sample.clone().take_metrics().0.into_iter().collect();
+        metrics.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join(" ")
+    }).collect();
+    format!("{} amostra(s): {}", samples.len(), metrics.join(" | "))
+}
+
+/// Tempo entre a coleta no cliente e agora; negativo quando o relógio do cliente está adiantado
+fn ingest_latency_ms(packet: &TelemetryPacket) -> Option<i64> {
+    packet.timestamp.map(|timestamp| epoch_millis() as i64 - timestamp as i64)
+}
+
+/// Envolve o texto na cor ANSI, quando houver. O painel só é desenhado num TTY,
+/// então os demais modos de saída nunca recebem escapes
+fn paint(text: &str, color: Option<&str>) -> String {
+    match color {
+        Some(color) => format!("{}{}{}", color, text, RESET),
+        None => text.to_string(),
+    }
+}
+
+/// Mini barra por núcleo, agrupadas em linhas para caber em máquinas com muitos cores
+fn render_cores(out: &mut String, cores: &mut [(usize, f32)], severity: &SeverityTable) {
+    if cores.is_empty() {
+        return;
+    }
+    cores.sort_by_key(|(index, _)| *index);
+
+    let compact = cores.len() > COMPACT_CORES;
+    let (per_row, width) = if compact { (COMPACT_CORES_PER_ROW, 4) } else { (CORES_PER_ROW, 10) };
+
+    let _ = writeln!(out, "🧮 Núcleos ({}):", cores.len());
+    for row in cores.chunks(per_row) {
+        out.push_str("  ");
+        for (index, value) in row {
+            let filled = ((value.clamp(0.0, 100.0) / 100.0) * width as f32).round() as usize;
+            let color = severity.color(&format!("CPU_{}", index), *value);
+            let _ = write!(
+                out,
+                " {:>2} {}{} {:>3.0}%",
+                index,
+                paint(&"█".repeat(filled), color),
+                "░".repeat(width - filled),
+                value
+            );
+        }
+        out.push('\n');
+    }
+}
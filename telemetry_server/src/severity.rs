@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use telemetry_common::sample::{core_index, socket_index};
+
+const GREEN: &str = "\x1B[32m";
+const YELLOW: &str = "\x1B[33m";
+const RED: &str = "\x1B[31m";
+
+/// Limiares de cor de uma métrica: verde até `warning`, amarelo até `critical`, vermelho acima
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SeverityThresholds {
+    pub warning: f32,
+    pub critical: f32,
+}
+
+impl SeverityThresholds {
+    const fn new(warning: f32, critical: f32) -> Self {
+        Self { warning, critical }
+    }
+
+    fn color(&self, value: f32) -> &'static str {
+        if value > self.critical {
+            RED
+        } else if value > self.warning {
+            YELLOW
+        } else {
+            GREEN
+        }
+    }
+}
+
+/// Limiares por nome de métrica (em maiúsculas): padrões embutidos
+/// sobrescritos ou estendidos pela seção `[severity]` da configuração
+#[derive(Debug)]
+pub struct SeverityTable {
+    thresholds: HashMap<String, SeverityThresholds>,
+}
+
+impl SeverityTable {
+    pub fn new(overrides: &HashMap<String, SeverityThresholds>) -> Self {
+        let defaults = [
+            ("CPU", SeverityThresholds::new(60.0, 85.0)),
+            ("DISK", SeverityThresholds::new(80.0, 90.0)),
+            ("SWAP", SeverityThresholds::new(50.0, 80.0)),
+            ("MEM_PERCENT", SeverityThresholds::new(75.0, 90.0)),
+            ("GPU_UTIL", SeverityThresholds::new(60.0, 85.0)),
+            ("TEMP", SeverityThresholds::new(70.0, 85.0)),
+        ];
+
+        let mut thresholds: HashMap<String, SeverityThresholds> = defaults
+            .into_iter()
+            .map(|(name, thresholds)| (name.to_string(), thresholds))
+            .collect();
+        for (name, value) in overrides {
+            thresholds.insert(name.to_uppercase(), *value);
+        }
+
+        Self { thresholds }
+    }
+
+    /// Cor ANSI do valor; métricas sem limiares ficam na cor padrão do terminal.
+    /// Núcleos e sockets sem limiares próprios (`CPU_3`, `CPU_SOCKET_1`) herdam os de `CPU`
+    pub fn color(&self, upper: &str, value: f32) -> Option<&'static str> {
+        self.thresholds
+            .get(upper)
+            .or_else(|| core_index(upper).or(socket_index(upper)).and_then(|_| self.thresholds.get("CPU")))
+            .map(|thresholds| thresholds.color(value))
+    }
+}
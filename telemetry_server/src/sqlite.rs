@@ -0,0 +1,330 @@
+use std::{
+    collections::HashMap,
+    io::{self, Result as IoResult},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rusqlite::{params, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use telemetry_common::{is_temperature_unavailable, labels};
+
+use crate::{epoch_millis, log, LogLevel};
+
+/// Intervalo entre as limpezas da retenção
+const RETENTION_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+const MILLIS_PER_DAY: u64 = 86_400_000;
+
+/// Lotes que podem esperar a thread de escrita; com o disco mais lento que as
+/// amostras, as linhas além disso são descartadas em vez de acumular na memória
+const QUEUED_BATCHES: usize = 10;
+
+/// Tabela única, uma linha por métrica de cada amostra, indexada por tempo
+const SCHEMA: &str = "
+    PRAGMA journal_mode = WAL;
+    CREATE TABLE IF NOT EXISTS samples (
+        client TEXT NOT NULL,
+        host TEXT,
+        metric TEXT NOT NULL,
+        value REAL NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS samples_timestamp ON samples (timestamp);
+    CREATE INDEX IF NOT EXISTS samples_metric_timestamp ON samples (metric, timestamp);
+";
+
+/// Seção `[sqlite]` do `telemetry.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SqliteConfig {
+    /// Arquivo do banco, criado se não existir
+    pub path: String,
+    /// Dias de amostras mantidos; as mais velhas são apagadas a cada hora. `0` guarda tudo
+    pub retention_days: u64,
+    /// Linhas por transação
+    pub batch_size: usize,
+    /// Intervalo máximo entre gravações de um lote incompleto
+    pub flush_interval_secs: u64,
+}
+
+impl Default for SqliteConfig {
+    fn default() -> Self {
+        Self {
+            path: "telemetry.db".to_string(),
+            retention_days: 30,
+            batch_size: 500,
+            flush_interval_secs: 1,
+        }
+    }
+}
+
+impl SqliteConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.path.trim().is_empty() {
+            return Err("sqlite.path não pode ser vazio".to_string());
+        }
+        if self.batch_size == 0 || self.flush_interval_secs == 0 {
+            return Err("sqlite: batch_size e flush_interval_secs devem ser maiores que zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Uma métrica de uma amostra, como gravada no banco
+struct Row {
+    client: String,
+    host: Option<String>,
+    metric: String,
+    value: f64,
+    timestamp: u64,
+}
+
+/// Resumo de uma métrica numa janela de tempo
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSummary {
+    pub count: u64,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Guarda todas as amostras num banco SQLite para consultas históricas. A
+/// escrita fica numa thread própria, em transações de até `batch_size` linhas,
+/// para que o disco não atrase as conexões de clientes
+pub struct SqliteStore {
+    /// Solto no `shutdown`, o que encerra a thread de escrita
+    sender: Mutex<Option<SyncSender<Row>>>,
+    writer: Mutex<Option<JoinHandle<()>>>,
+    /// Fila cheia desde o último envio aceito, para o aviso sair uma vez por congestionamento
+    overflowing: AtomicBool,
+    path: PathBuf,
+}
+
+impl SqliteStore {
+    pub fn spawn(config: SqliteConfig) -> IoResult<Self> {
+        let path = PathBuf::from(&config.path);
+        let connection = Connection::open(&path)
+            .and_then(|connection| connection.execute_batch(SCHEMA).map(|()| connection))
+            .map_err(|e| io::Error::other(format!("banco SQLite {}: {}", path.display(), e)))?;
+        let (sender, receiver) = mpsc::sync_channel(config.batch_size.saturating_mul(QUEUED_BATCHES));
+
+        log(LogLevel::Info, &format!("Gravando amostras no SQLite em {}", path.display()));
+        let writer = thread::Builder::new()
+            .name("sqlite".to_string())
+            .spawn(move || Writer::new(connection, config).run(receiver))?;
+
+        Ok(Self {
+            sender: Mutex::new(Some(sender)),
+            writer: Mutex::new(Some(writer)),
+            overflowing: AtomicBool::new(false),
+            path,
+        })
+    }
+
+    /// Enfileira as métricas da amostra; valores não finitos e a sentinela de temperatura ficam de fora
+    pub fn write(&self, client: &str, hostname: Option<&str>, timestamp: u64, metrics: &HashMap<String, f64>) {
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(sender) = sender.as_ref() else {
+            return;
+        };
+        for (name, value) in metrics {
+            if !value.is_finite() || is_temperature_unavailable(name, *value) {
+                continue;
+            }
+            let row = Row {
+                client: client.to_string(),
+                host: hostname.map(str::to_string),
+                metric: metric_key(name),
+                value: *value,
+                timestamp,
+            };
+            match sender.try_send(row) {
+                Ok(()) => self.overflowing.store(false, Ordering::Relaxed),
+                Err(TrySendError::Full(_)) => {
+                    if !self.overflowing.swap(true, Ordering::Relaxed) {
+                        log(LogLevel::Warning, "SQLite: gravação atrasada, descartando amostras até a fila esvaziar");
+                    }
+                }
+                Err(TrySendError::Disconnected(_)) => return,
+            }
+        }
+    }
+
+    /// Solta o canal e espera a thread de escrita gravar o último lote
+    pub fn shutdown(&self) {
+        self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(writer) = self.writer.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = writer.join();
+        }
+    }
+
+    /// Média, mínimo e máximo de `metric` desde `since_ms` (epoch em ms). O cliente
+    /// casa pelo ID ou pelo hostname; `*` junta todos. `None` sem amostras na janela
+    pub fn summarize(&self, client: &str, metric: &str, since_ms: u64) -> IoResult<Option<MetricSummary>> {
+        summarize(&self.path, client, metric, since_ms).map_err(io::Error::other)
+    }
+}
+
+/// Nome gravado no banco: a métrica pai em maiúsculas e os labels como vieram,
+/// já que valores como pontos de montagem diferenciam maiúsculas
+fn metric_key(key: &str) -> String {
+    let base = labels::base_name(key);
+    format!("{}{}", base.to_uppercase(), &key[base.len()..])
+}
+
+/// Consulta numa conexão só de leitura: com o WAL, não disputa com a escrita
+fn summarize(path: &Path, client: &str, metric: &str, since_ms: u64) -> rusqlite::Result<Option<MetricSummary>> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    connection.query_row(
+        "SELECT COUNT(*), AVG(value), MIN(value), MAX(value) FROM samples
+         WHERE metric = ?1 AND timestamp >= ?2 AND (?3 = '*' OR client = ?3 OR host = ?3)",
+        params![metric_key(metric), since_ms as i64, client],
+        |row| {
+            let count: i64 = row.get(0)?;
+            if count == 0 {
+                return Ok(None);
+            }
+            Ok(Some(MetricSummary { count: count as u64, avg: row.get(1)?, min: row.get(2)?, max: row.get(3)? }))
+        },
+    )
+}
+
+/// Acumula linhas e as grava em lotes, aplicando a retenção de tempos em tempos
+struct Writer {
+    connection: Connection,
+    config: SqliteConfig,
+    pending: Vec<Row>,
+}
+
+impl Writer {
+    fn new(connection: Connection, config: SqliteConfig) -> Self {
+        Self { connection, config, pending: Vec::new() }
+    }
+
+    fn run(mut self, receiver: Receiver<Row>) {
+        let interval = Duration::from_secs(self.config.flush_interval_secs);
+        let mut last_flush = Instant::now();
+        self.apply_retention();
+        let mut last_retention = Instant::now();
+
+        loop {
+            match receiver.recv_timeout(interval) {
+                Ok(row) => self.pending.push(row),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.flush();
+                    return;
+                }
+            }
+
+            if self.pending.len() >= self.config.batch_size || last_flush.elapsed() >= interval {
+                self.flush();
+                last_flush = Instant::now();
+            }
+            if last_retention.elapsed() >= RETENTION_CHECK_INTERVAL {
+                self.apply_retention();
+                last_retention = Instant::now();
+            }
+        }
+    }
+
+    /// Grava o lote numa transação; numa falha o lote é descartado, com aviso
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let rows = std::mem::take(&mut self.pending);
+        if let Err(e) = insert(&mut self.connection, &rows) {
+            log(LogLevel::Warning, &format!("SQLite: lote de {} linhas descartado: {}", rows.len(), e));
+        }
+    }
+
+    fn apply_retention(&self) {
+        if self.config.retention_days == 0 {
+            return;
+        }
+        let cutoff = epoch_millis().saturating_sub(self.config.retention_days * MILLIS_PER_DAY);
+        match self.connection.execute("DELETE FROM samples WHERE timestamp < ?1", params![cutoff as i64]) {
+            Ok(0) => {}
+            Ok(deleted) => log(LogLevel::Info, &format!(
+                "SQLite: {} linhas com mais de {} dias apagadas", deleted, self.config.retention_days
+            )),
+            Err(e) => log(LogLevel::Warning, &format!("SQLite: falha ao aplicar a retenção: {}", e)),
+        }
+    }
+}
+
+fn insert(connection: &mut Connection, rows: &[Row]) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+    {
+        let mut statement = transaction.prepare_cached(
+            "INSERT INTO samples (client, host, metric, value, timestamp) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for row in rows {
+            statement.execute(params![row.client, row.host, row.metric, row.value, row.timestamp as i64])?;
+        }
+    }
+    transaction.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use telemetry_common::TEMPERATURE_UNAVAILABLE;
+
+    use super::*;
+
+    #[test]
+    fn stores_batches_and_summarizes_by_client_or_host() {
+        let path = std::env::temp_dir().join(format!("telemetry-sqlite-{}.db", std::process::id()));
+        let config = SqliteConfig { path: path.display().to_string(), flush_interval_secs: 1, ..SqliteConfig::default() };
+        let store = SqliteStore::spawn(config).unwrap();
+
+        let now = epoch_millis();
+        for (offset, cpu) in [(0, 10.0), (1_000, 30.0), (2_000, f64::NAN)] {
+            let metrics = HashMap::from([("CPU".to_string(), cpu), ("TEMP".to_string(), f64::from(TEMPERATURE_UNAVAILABLE))]);
+            store.write("abc", Some("web-01"), now + offset, &metrics);
+        }
+        // Fora da janela consultada
+        store.write("abc", Some("web-01"), now - 7_200_000, &HashMap::from([("CPU".to_string(), 90.0)]));
+        store.write("xyz", None, now, &HashMap::from([("CPU".to_string(), 50.0)]));
+
+        let since = now - 3_600_000;
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let summary = loop {
+            match store.summarize("web-01", "cpu", since).unwrap() {
+                Some(summary) => break summary,
+                None if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+                None => panic!("amostras não gravadas"),
+            }
+        };
+        assert_eq!(summary, MetricSummary { count: 2, avg: 20.0, min: 10.0, max: 30.0 });
+        assert_eq!(store.summarize("abc", "CPU", since).unwrap(), Some(summary));
+        assert_eq!(store.summarize("*", "CPU", since).unwrap().unwrap().count, 3);
+        assert_eq!(store.summarize("web-01", "TEMP", since).unwrap(), None);
+
+        // Séries com labels: o nome pai não diferencia maiúsculas, o valor do label sim
+        let disk = HashMap::from([
+            ("disk{mount=\"/Home\"}".to_string(), 40.0),
+            ("TEMP{sensor=\"gpu\"}".to_string(), f64::from(TEMPERATURE_UNAVAILABLE)),
+        ]);
+        store.write("abc", Some("web-01"), now, &disk);
+        store.shutdown();
+        let summary = store.summarize("abc", "DISK{mount=\"/Home\"}", since).unwrap();
+        assert_eq!(summary.map(|summary| summary.max), Some(40.0));
+        assert_eq!(store.summarize("abc", "Disk{mount=\"/Home\"}", since).unwrap().unwrap().count, 1);
+        assert_eq!(store.summarize("abc", "DISK{mount=\"/home\"}", since).unwrap(), None);
+        assert_eq!(store.summarize("abc", "TEMP{sensor=\"gpu\"}", since).unwrap(), None);
+
+        drop(store);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
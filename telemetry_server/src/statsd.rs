@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    io::{self, Result as IoResult},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+};
+
+use serde::{Deserialize, Serialize};
+use telemetry_common::{is_temperature_unavailable, labels};
+
+use crate::{log, LogLevel};
+
+/// Menor datagrama aceito em `max_packet_bytes`; abaixo disso nem uma linha cabe
+const MIN_PACKET_BYTES: usize = 64;
+
+/// Seção `[statsd]` do `telemetry.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsdConfig {
+    /// Endpoint UDP do agente, ex.: `127.0.0.1:8125`
+    pub address: String,
+    /// Prefixo de cada métrica (`telemetry.cpu`)
+    pub prefix: String,
+    /// Cliente, namespace e labels como tags no formato do DogStatsD (`|#host:web-01`);
+    /// com `false`, o StatsD puro recebe namespace e host no nome (`telemetry.web-01.cpu`)
+    pub tags: bool,
+    /// Tamanho máximo de cada datagrama; as linhas excedentes vão no seguinte
+    pub max_packet_bytes: usize,
+}
+
+impl Default for StatsdConfig {
+    fn default() -> Self {
+        Self {
+            address: String::new(),
+            prefix: "telemetry".to_string(),
+            tags: true,
+            max_packet_bytes: 1432,
+        }
+    }
+}
+
+impl StatsdConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.address.trim().is_empty() {
+            return Err("statsd.address é obrigatório (ex.: 127.0.0.1:8125)".to_string());
+        }
+        if self.max_packet_bytes < MIN_PACKET_BYTES {
+            return Err(format!("statsd.max_packet_bytes deve ser ao menos {}", MIN_PACKET_BYTES));
+        }
+        Ok(())
+    }
+}
+
+/// Encaminha cada métrica recebida como um gauge StatsD por UDP. O envio é
+/// best-effort: socket não bloqueante e erros ignorados, para que um agente
+/// fora do ar não atrase nem interrompa o processamento dos clientes
+pub struct StatsdExporter {
+    socket: UdpSocket,
+    config: StatsdConfig,
+}
+
+impl StatsdExporter {
+    pub fn connect(config: StatsdConfig) -> IoResult<Self> {
+        let target = config.address.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("statsd.address não resolvido: {}", config.address)))?;
+        let local: SocketAddr = if target.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(target)?;
+        socket.set_nonblocking(true)?;
+
+        log(LogLevel::Info, &format!("Encaminhando métricas ao StatsD em {}", target));
+        Ok(Self { socket, config })
+    }
+
+    pub fn export(&self, client: &str, hostname: Option<&str>, namespace: Option<&str>, metrics: &HashMap<String, f64>) {
+        let lines = format_lines(&self.config, client, hostname, namespace, metrics);
+        for packet in pack(&lines, self.config.max_packet_bytes) {
+            let _ = self.socket.send(packet.as_bytes());
+        }
+    }
+}
+
+/// Uma linha `prefixo.metrica:valor|g` por métrica, em ordem alfabética; valores
+/// não finitos e a sentinela de temperatura ficam de fora. O namespace separa as
+/// séries como nas demais exportações: tag `namespace:` ou segmento antes do host,
+/// omitido quando é o próprio hostname
+fn format_lines(
+    config: &StatsdConfig,
+    client: &str,
+    hostname: Option<&str>,
+    namespace: Option<&str>,
+    metrics: &HashMap<String, f64>,
+) -> Vec<String> {
+    let namespace = namespace.filter(|namespace| Some(*namespace) != hostname);
+    let mut keys: Vec<&String> = metrics.keys().collect();
+    keys.sort();
+
+    let mut lines = Vec::with_capacity(keys.len());
+    for key in keys {
+        let value = metrics[key];
+        if !value.is_finite() || is_temperature_unavailable(key, value) {
+            continue;
+        }
+
+        let series = labels::split_series(key);
+        let name = sanitize(&series.as_ref().map_or(key.as_str(), |series| series.name).to_lowercase());
+        let series_labels = series.iter().flat_map(|series| &series.labels);
+
+        let line = if config.tags {
+            let mut tags = vec![format!("client:{}", sanitize(client))];
+            tags.extend(hostname.map(|hostname| format!("host:{}", sanitize(hostname))));
+            tags.extend(namespace.map(|namespace| format!("namespace:{}", sanitize(namespace))));
+            tags.extend(series_labels.map(|(label, label_value)| format!("{}:{}", sanitize(label), sanitize(label_value))));
+            format!("{}.{}:{}|g|#{}", config.prefix, name, value, tags.join(","))
+        } else {
+            // Sem tags, o host e os valores dos labels viram segmentos do nome
+            let mut path = vec![config.prefix.clone()];
+            path.extend(namespace.map(path_segment));
+            path.extend([path_segment(hostname.unwrap_or(client)), name]);
+            path.extend(series_labels.map(|(_, label_value)| path_segment(label_value)));
+            format!("{}:{}|g", path.join("."), value)
+        };
+        lines.push(line);
+    }
+    lines
+}
+
+/// Troca os caracteres com significado no protocolo (`:|@#,` e espaços) por `_`
+fn sanitize(raw: &str) -> String {
+    raw.chars()
+        .map(|c| if matches!(c, ':' | '|' | '@' | '#' | ',') || c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+/// Segmento do nome hierárquico do StatsD puro, onde `.` e `/` também separariam níveis
+fn path_segment(raw: &str) -> String {
+    sanitize(raw).replace(['.', '/'], "_")
+}
+
+/// Junta as linhas em datagramas separados por `\n` de até `max_bytes`. Uma linha
+/// maior que o limite segue sozinha, e o agente decide o que fazer com ela
+fn pack(lines: &[String], max_bytes: usize) -> Vec<String> {
+    let mut packets = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + 1 + line.len() > max_bytes {
+            packets.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        packets.push(current);
+    }
+    packets
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use telemetry_common::TEMPERATURE_UNAVAILABLE;
+
+    use super::*;
+
+    fn metrics() -> HashMap<String, f64> {
+        HashMap::from([
+            ("CPU".to_string(), 42.5),
+            ("DISK{mount=\"/home\"}".to_string(), 71.0),
+            ("RX_BYTES".to_string(), 123_456_789_012.0),
+            ("TEMP".to_string(), f64::from(TEMPERATURE_UNAVAILABLE)),
+            ("NET".to_string(), f64::NAN),
+        ])
+    }
+
+    #[test]
+    fn formats_gauges_with_host_as_tag_or_in_the_name() {
+        let mut config = StatsdConfig::default();
+        assert_eq!(format_lines(&config, "abc", Some("web-01"), Some("web-01"), &metrics()), [
+            "telemetry.cpu:42.5|g|#client:abc,host:web-01",
+            "telemetry.disk:71|g|#client:abc,host:web-01,mount:/home",
+            "telemetry.rx_bytes:123456789012|g|#client:abc,host:web-01",
+        ]);
+        assert_eq!(format_lines(&config, "abc", Some("web-01"), Some("prod"), &metrics())[0], "telemetry.cpu:42.5|g|#client:abc,host:web-01,namespace:prod");
+
+        config.tags = false;
+        assert_eq!(format_lines(&config, "abc", Some("web.lan"), Some("web.lan"), &metrics()), [
+            "telemetry.web_lan.cpu:42.5|g",
+            "telemetry.web_lan.disk._home:71|g",
+            "telemetry.web_lan.rx_bytes:123456789012|g",
+        ]);
+        assert_eq!(format_lines(&config, "abc", Some("web.lan"), Some("prod"), &metrics())[0], "telemetry.prod.web_lan.cpu:42.5|g");
+    }
+
+    #[test]
+    fn packs_lines_up_to_the_datagram_limit() {
+        let lines: Vec<String> = ["a:1|g", "b:2|g", "c:3|g"].map(String::from).to_vec();
+        assert_eq!(pack(&lines, 11), ["a:1|g\nb:2|g", "c:3|g"]);
+        assert_eq!(pack(&lines, 3), ["a:1|g", "b:2|g", "c:3|g"]);
+    }
+
+    #[test]
+    fn sends_datagrams_and_ignores_missing_agent() {
+        let agent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        agent.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let config = StatsdConfig { address: agent.local_addr().unwrap().to_string(), ..StatsdConfig::default() };
+        let exporter = StatsdExporter::connect(config).unwrap();
+
+        exporter.export("abc", None, None, &metrics());
+        let mut buffer = [0u8; 1500];
+        let size = agent.recv(&mut buffer).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buffer[..size]).unwrap(),
+            "telemetry.cpu:42.5|g|#client:abc\ntelemetry.disk:71|g|#client:abc,mount:/home\ntelemetry.rx_bytes:123456789012|g|#client:abc"
+        );
+
+        // Sem ninguém escutando, o envio falha em silêncio
+        drop(agent);
+        exporter.export("abc", None, None, &metrics());
+        exporter.export("abc", None, None, &metrics());
+    }
+}
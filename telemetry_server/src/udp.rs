@@ -0,0 +1,93 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, UdpSocket},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use telemetry_common::framing::is_timeout;
+
+use crate::{
+    epoch_millis, log, protocol_debug, session::ClientSession, HandshakeMessage, LogLevel, ServerState,
+};
+
+/// Maior payload de um datagrama UDP sobre IPv4
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+/// Inatividade após a qual um remetente some da tela quando o timeout de leitura está desativado
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct UdpPeer {
+    session: ClientSession,
+    last_seen: Instant,
+}
+
+/// Recebe telemetria por UDP até o pedido de encerramento. Cada datagrama é uma
+/// amostra independente; o endereço de origem identifica o cliente, que pode se
+/// apresentar com um datagrama de handshake. Remetentes inativos por
+/// `read_timeout_secs * max_timeouts` são removidos.
+pub fn serve(socket: &UdpSocket, state: &Arc<ServerState>) {
+    let idle_timeout = state.config.read_timeout()
+        .map_or(DEFAULT_IDLE_TIMEOUT, |timeout| timeout * state.config.max_timeouts);
+    let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+    let mut peers: HashMap<SocketAddr, UdpPeer> = HashMap::new();
+
+    while !state.is_shutting_down() {
+        match socket.recv_from(&mut buffer) {
+            Ok((len, addr)) => {
+                let peer = peers.entry(addr).or_insert_with(|| {
+                    log(LogLevel::Success, &format!("Cliente UDP ativo: {}", addr));
+                    UdpPeer { session: ClientSession::new(addr, Arc::clone(state)), last_seen: Instant::now() }
+                });
+                peer.last_seen = Instant::now();
+                handle_datagram(&mut peer.session, &buffer[..len]);
+            }
+            // Timeout do poll ou sinal (Ctrl+C) interrompendo a espera
+            Err(e) if is_timeout(&e) || e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => log(LogLevel::Warning, &format!("Erro ao receber datagrama: {}", e)),
+        }
+
+        peers.retain(|addr, peer| {
+            let active = peer.last_seen.elapsed() < idle_timeout;
+            if !active {
+                log(LogLevel::Info, &format!("Cliente UDP {} inativo há {}s", addr, idle_timeout.as_secs()));
+                peer.session.cleanup();
+            }
+            active
+        });
+    }
+
+    for peer in peers.values_mut() {
+        peer.session.cleanup();
+    }
+}
+
+fn handle_datagram(session: &mut ClientSession, datagram: &[u8]) {
+    protocol_debug::received(session.addr, "datagrama", datagram);
+    let Ok(text) = std::str::from_utf8(datagram) else {
+        log(LogLevel::Warning, &format!("Datagrama de {} com UTF-8 inválido", session.addr));
+        return;
+    };
+
+    // Datagrama vazio é heartbeat: só renova a atividade
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+
+    if let Ok(message) = serde_json::from_str::<HandshakeMessage>(text) {
+        // Sem canal de volta para a recusa: a sessão fica na versão anterior
+        if let Err(reason) = session.negotiate_protocol(message.handshake.protocol_version) {
+            log(LogLevel::Warning, &format!("Handshake de {} ignorado: {}", session.addr, reason));
+            return;
+        }
+        session.adopt_identity(message.handshake.identity);
+        // Sem canal de volta no UDP: o offset só corrige os timestamps
+        if let Some(clock) = message.handshake.clock {
+            session.adopt_clock(clock, epoch_millis());
+        }
+        return;
+    }
+
+    session.process_message(text);
+}
@@ -0,0 +1,179 @@
+use std::{
+    io::{Result as IoResult, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use ring::digest::{digest, SHA1_FOR_LEGACY_USE_ONLY};
+
+use crate::{
+    http::{self, Request, Response},
+    log, LogLevel,
+};
+
+/// Página do dashboard, embutida no binário
+const DASHBOARD_HTML: &str = include_str!("dashboard.html");
+
+/// GUID fixo da RFC 6455 usado no cálculo do `Sec-WebSocket-Accept`
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Navegador que não consome um frame neste tempo é desconectado
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Frames à espera de cada navegador; com a fila cheia, os novos são descartados
+/// para ele até que ela ande
+const BROWSER_QUEUE: usize = 64;
+
+/// Navegadores conectados ao endpoint `/ws`, que recebem cada amostra em JSON.
+/// Cada um tem a própria thread de escrita: o broadcast só enfileira, e um
+/// navegador lento não atrasa a thread do cliente nem os demais navegadores
+#[derive(Default)]
+pub struct WebHub {
+    browsers: Mutex<Vec<SyncSender<Arc<[u8]>>>>,
+}
+
+impl WebHub {
+    /// Enfileira a mensagem como frame de texto; quem já desconectou sai da lista
+    pub fn broadcast(&self, message: &str) {
+        let frame: Arc<[u8]> = text_frame(message).into();
+        let mut browsers = self.browsers.lock().unwrap_or_else(|e| e.into_inner());
+        browsers.retain(|browser| match browser.try_send(Arc::clone(&frame)) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+
+    fn add(&self, socket: TcpStream) -> IoResult<()> {
+        let (sender, frames) = mpsc::sync_channel(BROWSER_QUEUE);
+        thread::Builder::new()
+            .name("web-navegador".to_string())
+            .spawn(move || write_frames(socket, frames))?;
+        self.browsers.lock().unwrap_or_else(|e| e.into_inner()).push(sender);
+        Ok(())
+    }
+}
+
+/// Thread de escrita de um navegador; uma falha (ou o timeout) encerra a
+/// thread, e o próximo broadcast o tira da lista
+fn write_frames(mut socket: TcpStream, frames: Receiver<Arc<[u8]>>) {
+    for frame in frames {
+        if socket.write_all(&frame).is_err() {
+            return;
+        }
+    }
+}
+
+/// Sobe o dashboard web (`GET /` e o WebSocket em `/ws`) numa thread própria.
+/// O bind acontece antes do spawn para que erros de porta cheguem ao chamador.
+pub fn spawn_dashboard(address: &str, hub: Arc<WebHub>) -> IoResult<()> {
+    let listener = TcpListener::bind(address)?;
+    log(LogLevel::Info, &format!("Dashboard web em http://{}/", address));
+
+    http::serve("web", listener, move |request, stream| handle_request(&request, stream, &hub))
+}
+
+/// `/ws` com `Sec-WebSocket-Key` vira WebSocket e entra no hub; `/` é a página
+fn handle_request(request: &Request, mut stream: TcpStream, hub: &WebHub) -> IoResult<()> {
+    match (request.method.as_str(), request.path.as_str(), request.header("sec-websocket-key")) {
+        ("GET", "/ws", Some(key)) => {
+            let response = format!(
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept_key(key)
+            );
+            stream.write_all(response.as_bytes())?;
+            stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+            hub.add(stream)
+        }
+        ("GET", "/", _) => Response::ok("text/html; charset=utf-8", DASHBOARD_HTML.to_string()).write_to(stream),
+        ("GET", _, _) => Response::not_found().write_to(stream),
+        _ => Response::method_not_allowed().write_to(stream),
+    }
+}
+
+/// `base64(sha1(chave + GUID))`, como exige o handshake da RFC 6455
+fn accept_key(key: &str) -> String {
+    let hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, format!("{}{}", key, WEBSOCKET_GUID).as_bytes());
+    base64(hash.as_ref())
+}
+
+/// Frame de texto final, sem máscara (servidores nunca mascaram)
+fn text_frame(message: &str) -> Vec<u8> {
+    let payload = message.as_bytes();
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x81);
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    /// Exemplo da seção 1.3 da RFC 6455
+    #[test]
+    fn accept_key_matches_rfc_6455() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    /// Um navegador que não lê não impede a entrega aos outros
+    #[test]
+    fn stalled_browser_does_not_block_broadcast() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let hub = WebHub::default();
+        let _stalled = TcpStream::connect(address).unwrap();
+        hub.add(listener.accept().unwrap().0).unwrap();
+        let mut reader = TcpStream::connect(address).unwrap();
+        hub.add(listener.accept().unwrap().0).unwrap();
+
+        // Bem mais que os buffers do socket do navegador parado
+        let message = "x".repeat(64 * 1024);
+        let started = std::time::Instant::now();
+        for _ in 0..2 * BROWSER_QUEUE {
+            hub.broadcast(&message);
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Pelo menos a fila inteira chega ao navegador que lê
+        let mut received = vec![0u8; text_frame(&message).len() * BROWSER_QUEUE];
+        reader.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        reader.read_exact(&mut received).unwrap();
+        assert_eq!(&received[..4], &[0x81, 127, 0, 0]);
+    }
+}